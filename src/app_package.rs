@@ -0,0 +1,100 @@
+// App package module for packaged (MSIX/UWP) app detection and termination
+
+use crate::error::{Error, Win32Error};
+use windows::Win32::Foundation::{
+    APPMODEL_ERROR_NO_PACKAGE, CloseHandle, ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS,
+};
+use windows::Win32::Storage::Packaging::Appx::GetPackageFullName;
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx, CoUninitialize,
+};
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+use windows::Win32::UI::Shell::{IPackageDebugSettings, PackageDebugSettings};
+use windows::core::{HSTRING, PWSTR};
+
+/// Get the full name of the package a process belongs to
+/// Returns Ok(None) for ordinary, unpackaged processes
+pub fn get_package_full_name(pid: u32) -> Result<Option<String>, String> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .map_err(|e| format!("Failed to open process {}: {}", pid, Win32Error::from(e)))?;
+
+        // Ask for the required length first
+        let mut len: u32 = 0;
+        let mut result = GetPackageFullName(handle, &mut len, None);
+
+        let mut buffer = vec![0u16; len as usize];
+        if result == ERROR_INSUFFICIENT_BUFFER {
+            result = GetPackageFullName(handle, &mut len, Some(PWSTR(buffer.as_mut_ptr())));
+        }
+        let _ = CloseHandle(handle);
+
+        if result == APPMODEL_ERROR_NO_PACKAGE {
+            return Ok(None);
+        }
+        if result != ERROR_SUCCESS {
+            return Err(format!(
+                "Failed to query the package of process {}: {}",
+                pid,
+                Win32Error::from_code(result.0)
+            ));
+        }
+
+        // The returned length includes the null terminator
+        let name_len = (len as usize).saturating_sub(1).min(buffer.len());
+        Ok(buffer
+            .get(..name_len)
+            .map(String::from_utf16_lossy)
+            .filter(|name| !name.is_empty()))
+    }
+}
+
+/// Terminate every process of a package through the app model
+///
+/// Unlike TerminateProcess this lets the app model record the app as closed,
+/// so it starts cleanly next time instead of trying to resume a dead instance
+pub fn terminate_package(full_name: &str) -> Result<(), Error> {
+    unsafe {
+        // COM may already be initialized by the caller in another mode, which is fine
+        let init = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let result = CoCreateInstance::<_, IPackageDebugSettings>(
+            &PackageDebugSettings,
+            None,
+            CLSCTX_INPROC_SERVER,
+        )
+        .and_then(|settings| settings.TerminateAllProcesses(&HSTRING::from(full_name)))
+        .map_err(|e| {
+            Error::win32(
+                format!("Failed to terminate package {}", full_name),
+                Win32Error::from(e),
+            )
+        });
+
+        if init.is_ok() {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_process_is_not_packaged() {
+        let result = get_package_full_name(std::process::id());
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_package_full_name_with_invalid_pid() {
+        assert!(get_package_full_name(9999999).is_err());
+    }
+
+    #[test]
+    fn test_terminate_unknown_package_fails() {
+        assert!(terminate_package("Evict.NoSuchPackage_1.0.0.0_x64__0000000000000").is_err());
+    }
+}
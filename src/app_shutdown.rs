@@ -0,0 +1,113 @@
+// App shutdown module for application-level graceful shutdown requests
+
+use crate::config::ShutdownAction;
+use crate::process_service;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// How long the application gets to accept and answer a shutdown request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Ask an application to shut itself down
+/// HTTP actions are sent to the port being freed on the loopback interface
+pub fn request_shutdown(pid: u32, port: u16, action: &ShutdownAction) -> Result<(), String> {
+    match action {
+        ShutdownAction::HttpPost(path) => http_post(port, path),
+        ShutdownAction::CtrlC => process_service::send_ctrl_c(pid),
+    }
+}
+
+/// Send an empty POST request and check for a successful status
+fn http_post(port: u16, path: &str) -> Result<(), String> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, REQUEST_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to port {}: {}", port, e))?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(REQUEST_TIMEOUT)))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: localhost:{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send shutdown request: {}", e))?;
+
+    // Only the status line matters
+    let mut response = [0u8; 64];
+    let len = stream
+        .read(&mut response)
+        .map_err(|e| format!("No response to shutdown request: {}", e))?;
+    let status_line = String::from_utf8_lossy(response.get(..len).unwrap_or(&[])).to_string();
+
+    match parse_status(&status_line) {
+        Some(status) if (200..300).contains(&status) => Ok(()),
+        Some(status) => Err(format!("POST {} returned HTTP {}", path, status)),
+        None => Err(format!("POST {} returned an invalid HTTP response", path)),
+    }
+}
+
+/// Extract the status code from an HTTP status line
+fn parse_status(status_line: &str) -> Option<u16> {
+    let mut parts = status_line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status("HTTP/1.1 200 OK\r\n"), Some(200));
+        assert_eq!(parse_status("HTTP/1.0 404 Not Found"), Some(404));
+        assert_eq!(parse_status("SSH-2.0-OpenSSH"), None);
+        assert_eq!(parse_status(""), None);
+    }
+
+    #[test]
+    fn test_http_post_accepts_success_status() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 256];
+            let len = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&request[..len]).to_string()
+        });
+
+        assert!(http_post(port, "/actuator/shutdown").is_ok());
+        assert!(
+            server
+                .join()
+                .unwrap()
+                .starts_with("POST /actuator/shutdown HTTP/1.1")
+        );
+    }
+
+    #[test]
+    fn test_http_post_rejects_error_status() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 256];
+            let _ = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\n\r\n").unwrap();
+        });
+
+        let result = http_post(port, "/actuator/shutdown");
+        server.join().unwrap();
+        assert!(result.unwrap_err().contains("HTTP 404"));
+    }
+}
@@ -0,0 +1,237 @@
+// Certificate module for reading X.509 certificates sent by TLS listeners
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// DER tags used while walking a certificate
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_EXPLICIT_VERSION: u8 = 0xa0;
+
+/// Attribute types shown for subject and issuer names, by DER-encoded OID
+const NAME_ATTRIBUTES: [(&[u8], &str); 4] = [
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (&[0x55, 0x04, 0x06], "C"),
+];
+
+/// The parts of a certificate that tell listeners apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    pub subject: String,
+    pub issuer: String,
+    /// Expiry as YYYY-MM-DD HH:MM:SS UTC
+    pub not_after: String,
+    pub expired: bool,
+}
+
+/// Parse a DER-encoded X.509 certificate
+pub fn parse(der: &[u8]) -> Option<Certificate> {
+    let (tag, certificate, _) = read_element(der)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+    let (tag, tbs, _) = read_element(certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+
+    // Skip the optional version and the serial number and signature algorithm
+    let mut rest = tbs;
+    if rest.first() == Some(&TAG_EXPLICIT_VERSION) {
+        rest = read_element(rest)?.2;
+    }
+    rest = read_element(rest)?.2;
+    rest = read_element(rest)?.2;
+
+    let (_, issuer, rest) = read_element(rest)?;
+    let (_, validity, rest) = read_element(rest)?;
+    let (_, subject, _) = read_element(rest)?;
+
+    let not_after_element = read_element(validity)?.2;
+    let (tag, not_after, _) = read_element(not_after_element)?;
+    let not_after = parse_time(tag, not_after)?;
+
+    Some(Certificate {
+        subject: format_name(subject),
+        issuer: format_name(issuer),
+        expired: not_after < current_time(),
+        not_after: format_time(&not_after),
+    })
+}
+
+/// Read one DER element, returning its tag, its content and what follows it
+fn read_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first_len = *data.get(1)?;
+
+    let (len, header_len) = if first_len < 0x80 {
+        (first_len as usize, 2)
+    } else {
+        // Long form: the low bits give the number of length bytes
+        let count = (first_len & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = data
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+        (len, 2 + count)
+    };
+
+    let content = data.get(header_len..header_len.checked_add(len)?)?;
+    let rest = data.get(header_len + len..)?;
+    Some((tag, content, rest))
+}
+
+/// Render a distinguished name as "CN=..., O=..."
+fn format_name(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut sets = name;
+
+    while let Some((TAG_SET, set, rest)) = read_element(sets) {
+        sets = rest;
+        let Some((TAG_SEQUENCE, attribute, _)) = read_element(set) else {
+            continue;
+        };
+        let Some((TAG_OID, oid, value)) = read_element(attribute) else {
+            continue;
+        };
+        let Some((_, value, _)) = read_element(value) else {
+            continue;
+        };
+
+        if let Some((_, label)) = NAME_ATTRIBUTES.iter().find(|(known, _)| *known == oid) {
+            parts.push(format!("{}={}", label, String::from_utf8_lossy(value)));
+        }
+    }
+
+    if parts.is_empty() {
+        "(empty)".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Parse a UTCTime or GeneralizedTime into a sortable YYYYMMDDHHMMSS string
+fn parse_time(tag: u8, value: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let full = match tag {
+        // Two-digit years: 50-99 mean 19xx, 00-49 mean 20xx
+        TAG_UTC_TIME if text.len() == 12 => {
+            let century = if text.get(..2)? >= "50" { "19" } else { "20" };
+            format!("{}{}", century, text)
+        }
+        TAG_GENERALIZED_TIME if text.len() == 14 => text.to_string(),
+        _ => return None,
+    };
+
+    full.bytes().all(|b| b.is_ascii_digit()).then_some(full)
+}
+
+/// Render a YYYYMMDDHHMMSS string for display
+fn format_time(time: &str) -> String {
+    let part = |range: std::ops::Range<usize>| time.get(range).unwrap_or("");
+    format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        part(0..4),
+        part(4..6),
+        part(6..8),
+        part(8..10),
+        part(10..12),
+        part(12..14)
+    )
+}
+
+/// Get the current time as a YYYYMMDDHHMMSS string
+fn current_time() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) date
+/// Howard Hinnant's civil_from_days algorithm
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Self-signed certificate for CN=evict.test, O=Evict Tests, valid until 2051
+    const TEST_CERTIFICATE: &[u8] = include_bytes!("../tests/fixtures/evict-test.der");
+
+    #[test]
+    fn test_parse_test_certificate() {
+        let certificate = parse(TEST_CERTIFICATE).unwrap();
+        assert_eq!(certificate.subject, "CN=evict.test, O=Evict Tests");
+        assert_eq!(certificate.issuer, "CN=evict.test, O=Evict Tests");
+        assert!(certificate.not_after.starts_with("2051-"));
+        assert!(!certificate.expired);
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert_eq!(parse(b""), None);
+        assert_eq!(parse(b"\x30\x82\xff\xff"), None);
+        assert_eq!(parse(&TEST_CERTIFICATE[..TEST_CERTIFICATE.len() / 2]), None);
+    }
+
+    #[test]
+    fn test_parse_time_formats() {
+        assert_eq!(
+            parse_time(TAG_UTC_TIME, b"491231235959Z"),
+            Some("20491231235959".to_string())
+        );
+        assert_eq!(
+            parse_time(TAG_UTC_TIME, b"990101000000Z"),
+            Some("19990101000000".to_string())
+        );
+        assert_eq!(
+            parse_time(TAG_GENERALIZED_TIME, b"20510101120000Z"),
+            Some("20510101120000".to_string())
+        );
+        assert_eq!(parse_time(TAG_UTC_TIME, b"4912312359Z"), None);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time("20510101120000"), "2051-01-01 12:00:00 UTC");
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+    }
+}
@@ -1,9 +1,187 @@
 // CLI module for argument parsing and output formatting
 
+use crate::certificate::Certificate;
+use crate::config::{self, AppShutdown, Config};
+use crate::error::{Error, ErrorKind};
+use crate::execution::{FailurePolicy, Outcome, StepResult};
+use crate::explain::Explanation;
+use crate::find::{Filter, Match};
+use crate::inventory::{self, InventoryProcess};
+use crate::method::Method;
+use crate::port_range::RangeUsage;
+use crate::port_service::{Backend, PortBinding, Protocol, TcpState};
+use crate::privilege::{Capability, ELEVATED_OUTPUT_FLAG};
+use crate::probe::HttpPage;
+use crate::process_service::{
+    CONSOLE_CTRL_HELPER_FLAG, DEFAULT_EXIT_CODE, GRACEFUL_TIMEOUT, Signal,
+};
+use crate::project::{Candidate, ProjectListener};
+use crate::service_control::ServiceInfo;
+use crate::validation;
+use crate::verify;
 use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
+/// Whether errors are written as JSON objects, set by --json-errors
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Whether stdout carries one JSON result instead of text, set by --json
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// What evict was asked to do
+#[derive(Debug)]
+pub enum Command {
+    /// Free a port by terminating its owner
+    Evict(CliArgs),
+    /// Print a free port, preferably close to the given one
+    FreePort { near: Option<u16> },
+    /// Show which ports in a range are used and by whom
+    Range {
+        start: u16,
+        end: u16,
+        summary: bool,
+        backend: Option<Backend>,
+    },
+    /// Explain why a port is unavailable without terminating anything
+    Explain { port: u16, backend: Option<Backend> },
+    /// List every listening TCP socket with its port, address, PID and process name
+    List { backend: Option<Backend> },
+    /// List the TCP sockets that match every given filter, without acting on them
+    Find {
+        filter: Filter,
+        backend: Option<Backend>,
+    },
+    /// List every listening process on the machine, optionally with details
+    Inventory {
+        json: bool,
+        details: bool,
+        backend: Option<Backend>,
+    },
+    /// Terminate every process with the given image name that holds a TCP port
+    ByName {
+        name: String,
+        yes: bool,
+        force: bool,
+        privileged_ok: bool,
+        json: bool,
+        policy: FailurePolicy,
+        backend: Option<Backend>,
+    },
+    /// Terminate whatever binds the port, until evict is interrupted
+    Watch {
+        port: u16,
+        yes: bool,
+        privileged_ok: bool,
+        exit_code: u32,
+        backend: Option<Backend>,
+    },
+    /// Terminate every listener that belongs to the current repository
+    Workspace {
+        yes: bool,
+        force: bool,
+        privileged_ok: bool,
+        json: bool,
+        orphans_only: bool,
+        policy: FailurePolicy,
+        backend: Option<Backend>,
+    },
+    /// Terminate the processes bound to an AF_UNIX socket path
+    Unix {
+        path: PathBuf,
+        yes: bool,
+        force: bool,
+        policy: FailurePolicy,
+    },
+    /// Terminate or restart the processes locking a file or directory
+    File {
+        path: PathBuf,
+        yes: bool,
+        force: bool,
+        restart: bool,
+        policy: FailurePolicy,
+    },
+    /// Terminate the processes holding a serial port open
+    Com {
+        port: String,
+        yes: bool,
+        force: bool,
+        policy: FailurePolicy,
+    },
+    /// Check the configuration file and show the settings in effect
+    ConfigValidate,
+    /// Write a commented configuration file, optionally asking for its settings
+    ConfigInit { force: bool, interactive: bool },
+}
+
+#[derive(Debug)]
 pub struct CliArgs {
     pub port: u16,
+    pub privileged_ok: bool,
+    /// Terminate the owner without asking first
+    pub yes: bool,
+    pub backend: Option<Backend>,
+    /// Protocol of the port, TCP unless --udp is given
+    pub protocol: Protocol,
+    /// Only a listening TCP socket holds the port, unless --any-state is given
+    pub listen_only: bool,
+    pub exit_code: u32,
+    pub signal: Signal,
+    /// Whether --sigterm, --sigkill or --exit-code chose how to terminate;
+    /// otherwise the configured termination for the owner applies
+    pub signal_chosen: bool,
+    /// Single mechanism chosen with --method instead of the automatic ladder
+    pub method: Option<Method>,
+    pub kill_job: bool,
+    /// Also terminate every process started under the owner
+    pub tree: bool,
+    pub group: bool,
+    /// Ask the owner to exit and terminate it only if it is still running after the timeout
+    pub graceful: bool,
+    /// How long a process asked to exit is given before evict moves on
+    pub graceful_timeout: Duration,
+    pub dump: bool,
+    pub app_graceful: bool,
+    pub identify: bool,
+    pub suggest: bool,
+    pub fail_if_free: bool,
+    pub strict: bool,
+    pub all: bool,
+    pub policy: FailurePolicy,
+    pub copy: bool,
+    /// Show which actions against the owner the current token can take
+    pub details: bool,
+    /// Allow terminating a service process under the paranoid safety profile
+    pub force: bool,
+    /// Command that checks the replacement once it listens on the port again
+    pub verify_cmd: Option<String>,
+    /// Time allowed for the replacement to come up and pass the check
+    pub verify_timeout: Duration,
+    /// Whether to prove the port is free by binding it after the owner is gone
+    pub verify_bind: bool,
+    /// File to write a Markdown change record of the run to
+    pub transcript: Option<PathBuf>,
+    /// Print the result as one JSON object instead of text
+    pub json: bool,
+    /// Look up and check everything, then show what would be terminated instead
+    pub dry_run: bool,
+    /// Relaunch as administrator when the owner needs elevation, instead of asking
+    pub elevate: bool,
+}
+
+/// What happened to a port's owner, as copied to the clipboard by --copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultReport {
+    pub port: u16,
+    pub pid: u32,
+    pub name: String,
+    pub path: Option<String>,
+    pub command_line: Option<String>,
+    pub action: &'static str,
+    pub outcome: Result<(), String>,
 }
 
 /// Display help message
@@ -13,163 +191,3163 @@ fn display_help(program_name: &str) {
     println!("USAGE:");
     println!("    {} <PORT>", program_name);
     println!("    {} [OPTIONS]", program_name);
+    println!(
+        "    {} --name <PATTERN> [--yes] [--force] [--privileged-ok] [--json] [--fail-fast | --keep-going]",
+        program_name
+    );
+    println!(
+        "    {} --watch <PORT> [--yes] [--privileged-ok] [--exit-code <N>]",
+        program_name
+    );
+    println!("    {} free-port [--near <PORT>]", program_name);
+    println!("    {} range <START-END> [--summary]", program_name);
+    println!(
+        "    {} workspace [--yes] [--force] [--privileged-ok] [--json] [--orphans-only] [--fail-fast | --keep-going]",
+        program_name
+    );
+    println!("    {} explain <PORT>", program_name);
+    println!("    {} list", program_name);
+    println!(
+        "    {} find [--port <PORT>] [--name <PATTERN>] [--user <USER>] [--state <STATE>]",
+        program_name
+    );
+    println!(
+        "    {} inventory [--output text|json] [--details]",
+        program_name
+    );
+    println!(
+        "    {} unix <PATH> [--yes] [--force] [--fail-fast | --keep-going]",
+        program_name
+    );
+    println!(
+        "    {} com <COMn> [--yes] [--force] [--fail-fast | --keep-going]",
+        program_name
+    );
+    println!(
+        "    {} file <PATH> [--restart] [--yes] [--force] [--fail-fast | --keep-going]",
+        program_name
+    );
+    println!("    {} config validate", program_name);
+    println!("    {} config init [--force] [--interactive]", program_name);
     println!();
     println!("DESCRIPTION:");
     println!("    Identifies and terminates the process using the specified TCP port.");
     println!("    This tool helps developers quickly free up ports that are in use.");
     println!();
     println!("ARGUMENTS:");
-    println!("    <PORT>    The TCP port number to free (1-65535)");
+    println!("    <PORT>    The TCP (or, with --udp, UDP) port number to free (1-65535)");
     println!();
     println!("OPTIONS:");
-    println!("    -h, --help    Display this help message");
+    println!("    -h, --help         Display this help message");
+    println!("    -y, --yes          Terminate the owner without asking first");
+    println!("    --privileged-ok    Skip the confirmation for privileged and production ports");
+    println!("    --elevate          Relaunch as administrator through the UAC prompt when the");
+    println!("                       owner cannot be terminated without elevation");
+    println!("    --force            Allow terminating a service process under the paranoid");
+    println!("                       safety profile");
+    println!("    --udp              Free a UDP port instead of a TCP port");
+    println!("    --listen-only      Only target a process listening on the port (default)");
+    println!("    --any-state        Also target processes whose connections use the port as");
+    println!("                       their local port, such as outgoing ones");
+    println!("    --backend <NAME>   Connection table source: iphlpapi or wmi");
+    println!("                       (default: iphlpapi, falling back to wmi if it fails);");
+    println!("                       range, workspace, explain, list, find, inventory, --name");
+    println!("                       and --watch accept it too");
+    println!("    --exit-code <N>    Exit code given to the terminated process (default: 1)");
+    println!("    --sigterm          Ask the process to exit (WM_CLOSE / Ctrl+Break) and wait");
+    println!("    --sigkill          Terminate the process immediately (default)");
+    println!("    --graceful         Ask the process to exit, and terminate it if it is still");
+    println!("                       running after the graceful timeout");
+    println!(
+        "    --graceful-timeout <SECONDS>  Time a process asked to exit is given (default: {})",
+        GRACEFUL_TIMEOUT.as_secs()
+    );
+    println!("    --group            With --sigterm or --method ctrl-break, signal the target's");
+    println!("                       whole console group");
+    println!("    --method <NAME>    Use exactly one mechanism: terminate, ctrl-break, wm-close,");
+    println!("                       service-stop or connection-reset");
+    println!(
+        "    --app-graceful     Try the application's own shutdown (HTTP endpoint, Ctrl+C) first"
+    );
+    println!(
+        "    --details          Show which actions against the owner evict is allowed to take"
+    );
+    println!("    --identify         Probe the port and show which protocol it speaks");
+    println!("    --suggest          Suggest a nearby free port if the owner is not terminated");
+    println!("    --fail-if-free     Exit with code 2 if nothing is using the port");
+    println!("    --dump             Write a minidump of the process before terminating it");
+    println!("    --kill-job         Terminate every process in the target's job object");
+    println!("    --tree             Also terminate the processes the target started, and theirs");
+    println!(
+        "    --strict           Fail instead of picking one when several processes hold the port"
+    );
+    println!("    --all              Terminate every process holding the port");
+    println!(
+        "    --fail-fast        With --all, stop at the first process that cannot be terminated"
+    );
+    println!("    --keep-going       With --all, try every process before failing (default)");
+    println!(
+        "    --json             Print the result as one JSON object (errors as with --json-errors)"
+    );
+    println!("    --json-errors      Write errors to stderr as JSON objects");
+    println!("    --copy             Copy the PID, path and command line of the owner and the");
+    println!("                       outcome to the clipboard");
+    println!("    --dry-run          Show which processes would be terminated, and how, without");
+    println!("                       terminating anything");
+    println!("    --verify-bind      After freeing the port, bind it once to prove it is usable");
+    println!("    --transcript <FILE> Write a Markdown change record of what was found and done");
+    println!("    --verify-cmd <CMD> After freeing the port, wait for a new listener and run CMD");
+    println!("                       to check that the replacement works");
+    println!(
+        "    --verify-timeout <SECONDS>  Time allowed for the replacement and CMD (default: {})",
+        verify::DEFAULT_TIMEOUT.as_secs()
+    );
     println!();
     println!("EXAMPLES:");
     println!("    {} 8080       # Free port 8080", program_name);
     println!("    {} 3000       # Free port 3000", program_name);
     println!("    {} --help     # Show this help message", program_name);
+    println!(
+        "    {} 8080 --verify-cmd \"curl -f localhost:8080/health\"    # Free, then check the restart",
+        program_name
+    );
+    println!(
+        "    {} --name node.exe    # Terminate every node.exe that holds a TCP port",
+        program_name
+    );
+    println!(
+        "    {} --watch 3000 --yes    # Keep port 3000 free until Ctrl+C",
+        program_name
+    );
+    println!(
+        "    {} free-port --near 3000    # Print a free port close to 3000",
+        program_name
+    );
+    println!(
+        "    {} range 8000-9000 --summary    # Show who uses ports 8000-9000",
+        program_name
+    );
+    println!(
+        "    {} workspace    # Terminate every listener started from this repository",
+        program_name
+    );
+    println!(
+        "    {} explain 8080    # Say why 8080 is busy and what to do, changing nothing",
+        program_name
+    );
+    println!(
+        "    {} list    # Show every listening port with its process name",
+        program_name
+    );
+    println!(
+        "    {} inventory --output json --details    # Export every listener for a CMDB",
+        program_name
+    );
+    println!(
+        "    {} unix C:\\tmp\\app.sock    # Free an AF_UNIX socket path",
+        program_name
+    );
+    println!(
+        "    {} com COM3    # Close COM3 by terminating the program that has it open",
+        program_name
+    );
+    println!(
+        "    {} file build\\app.dll --restart    # Restart whatever locks app.dll",
+        program_name
+    );
     println!();
     println!("NOTE:");
     println!("    This tool may require administrator privileges to terminate certain processes.");
     println!("    If you encounter permission errors, try running as administrator.");
+    println!();
+    println!("    Ports below 1024 and the ports listed in production_ports require typing");
+    println!("    the port number back before the owning process is terminated.");
+    println!();
+    println!("CONFIGURATION:");
+    println!("    Read from %APPDATA%\\evict\\evict.conf, or the file named by EVICT_CONFIG.");
+    println!("    Run 'config init' to create it and 'config validate' to check it.");
+    println!("    production_ports = 443, 8443    # Ports that always require confirmation");
+    println!("    app_shutdown = java.exe /actuator/shutdown    # POST to the freed port");
+    println!("    app_shutdown = node.exe ctrl-c                # Send Ctrl+C");
+    println!("    termination = java.exe graceful    # Ask it to exit before terminating it");
+    println!("    safety = paranoid                  # paranoid, normal or yolo");
 }
 
-/// Parse command line arguments to extract the port number
+/// Parse command line arguments into the command to run
 /// Returns an error with usage instructions if arguments are invalid
-pub fn parse_args() -> Result<CliArgs, String> {
+pub fn parse_args() -> Result<Command, String> {
     let args: Vec<String> = env::args().collect();
     let program_name = args.first().map(|s| s.as_str()).unwrap_or("evict");
+    let rest = match args.get(1..).unwrap_or(&[]) {
+        // An elevated relaunch names its output file first, see parse_elevated_output
+        [flag, _, rest @ ..] if *flag == ELEVATED_OUTPUT_FLAG => rest,
+        rest => rest,
+    };
+
+    // --json-errors applies to every command, including errors parsing the rest
+    let rest: Vec<String> = rest
+        .iter()
+        .filter(|arg| {
+            let json_errors = *arg == "--json-errors";
+            if json_errors {
+                JSON_ERRORS.store(true, Ordering::Relaxed);
+            }
+            !json_errors
+        })
+        .cloned()
+        .collect();
+    let rest = rest.as_slice();
 
     // Check if help flag is provided
-    if args
-        .get(1)
-        .is_some_and(|arg| arg == "-h" || arg == "--help")
-    {
+    if rest.iter().any(|arg| arg == "-h" || arg == "--help") {
         display_help(program_name);
         std::process::exit(0);
     }
 
+    match rest.split_first() {
+        Some((command, options)) if command == "free-port" => parse_free_port_args(options),
+        Some((command, options)) if command == "range" => parse_range_args(options),
+        Some((command, options)) if command == "workspace" => parse_workspace_args(options),
+        Some((command, options)) if command == "explain" => parse_explain_args(options),
+        Some((command, options)) if command == "list" => parse_list_args(options),
+        Some((command, options)) if command == "find" => parse_find_args(options),
+        Some((command, options)) if command == "inventory" => parse_inventory_args(options),
+        Some((command, options)) if command == "unix" => parse_unix_args(options),
+        Some((command, options)) if command == "com" => parse_com_args(options),
+        Some((command, options)) if command == "file" => parse_file_args(options),
+        Some((command, options)) if command == "config" => parse_config_args(options),
+        _ if rest.iter().any(|arg| arg == "--name") => parse_name_args(rest),
+        _ if rest.iter().any(|arg| arg == "--watch") => parse_watch_args(rest),
+        _ => parse_arg_list(program_name, rest).map(Command::Evict),
+    }
+}
+
+/// Parse the arguments of the range command
+fn parse_range_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut range = None;
+    let mut summary = false;
+
+    for arg in &args {
+        match arg.as_str() {
+            "--summary" => summary = true,
+            option if is_option(option) => {
+                return Err(format!("Unknown option for range: '{}'", option));
+            }
+            _ if range.is_some() => {
+                return Err(format!("Unexpected argument for range: '{}'", arg));
+            }
+            _ => range = Some(validation::validate_port_range(arg)?),
+        }
+    }
+
+    let (start, end) = range.ok_or_else(|| "Usage: range <START-END> [--summary]".to_string())?;
+    Ok(Command::Range {
+        start,
+        end,
+        summary,
+        backend,
+    })
+}
+
+/// Parse the value of --backend
+fn parse_backend(name: Option<&String>) -> Result<Backend, String> {
+    let name = name.ok_or_else(|| "Missing value for --backend".to_string())?;
+    Backend::from_name(name)
+        .ok_or_else(|| format!("Invalid backend: '{}' (expected iphlpapi or wmi)", name))
+}
+
+/// Take --backend <NAME> out of the options of a command that reads the connection table
+/// Returns the backend and the remaining options
+fn take_backend(args: &[String]) -> Result<(Option<Backend>, Vec<String>), String> {
+    let mut backend = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--backend" {
+            backend = Some(parse_backend(iter.next())?);
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((backend, rest))
+}
+
+/// Parse the action of the config command
+fn parse_config_args(args: &[String]) -> Result<Command, String> {
+    const USAGE: &str = "Usage: config validate | config init [--force] [--interactive]";
+
+    match args.split_first() {
+        Some((action, [])) if action == "validate" => Ok(Command::ConfigValidate),
+        Some((action, options)) if action == "init" => {
+            let mut force = false;
+            let mut interactive = false;
+            for option in options {
+                match option.as_str() {
+                    "--force" => force = true,
+                    "--interactive" | "-i" => interactive = true,
+                    other => {
+                        return Err(format!("Unexpected argument for config init: '{}'", other));
+                    }
+                }
+            }
+            Ok(Command::ConfigInit { force, interactive })
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Parse the port of the explain command
+fn parse_explain_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    match args.as_slice() {
+        [port] if !is_option(port) => Ok(Command::Explain {
+            port: validation::validate_port(port)?,
+            backend,
+        }),
+        _ => Err("Usage: explain <PORT>".to_string()),
+    }
+}
+
+/// Parse the arguments of the list command, which takes none
+fn parse_list_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    match args.first() {
+        Some(other) => Err(format!("Unexpected argument for list: '{}'", other)),
+        None => Ok(Command::List { backend }),
+    }
+}
+
+/// Parse the filters of the find command; none at all lists every TCP socket
+fn parse_find_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut filter = Filter::default();
+
+    let mut iter = args.iter();
+    while let Some(option) = iter.next() {
+        let value = match option.as_str() {
+            "--port" | "--name" | "--user" | "--state" => iter
+                .next()
+                .filter(|value| !is_option(value) && !value.trim().is_empty())
+                .ok_or_else(|| format!("Missing value for {}", option))?,
+            other => return Err(format!("Unexpected argument for find: '{}'", other)),
+        };
+        match option.as_str() {
+            "--port" => filter.port = Some(validation::validate_port(value)?),
+            "--name" => filter.name = Some(value.trim().to_string()),
+            "--user" => filter.user = Some(value.trim().to_string()),
+            _ => {
+                let states: Vec<&str> = TcpState::ALL.iter().map(|state| state.as_str()).collect();
+                filter.state = Some(TcpState::from_name(value).ok_or_else(|| {
+                    format!(
+                        "Invalid state: '{}' (expected {})",
+                        value,
+                        states.join(", ")
+                    )
+                })?);
+            }
+        }
+    }
+
+    Ok(Command::Find { filter, backend })
+}
+
+/// Parse the options of the inventory command
+fn parse_inventory_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut json = false;
+    let mut details = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--details" => details = true,
+            "--output" => {
+                let format = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --output".to_string())?;
+                json = match format.as_str() {
+                    "json" => true,
+                    "text" => false,
+                    other => {
+                        return Err(format!(
+                            "Invalid output format: '{}' (expected text or json)",
+                            other
+                        ));
+                    }
+                };
+            }
+            other => return Err(format!("Unexpected argument for inventory: '{}'", other)),
+        }
+    }
+
+    Ok(Command::Inventory {
+        json,
+        details,
+        backend,
+    })
+}
+
+/// Parse the arguments of --name, which replaces the port with a process name
+fn parse_name_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut name = None;
+    let mut yes = false;
+    let mut force = false;
+    let mut privileged_ok = false;
+    let mut json = false;
+    let mut policy = FailurePolicy::KeepGoing;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--name" => {
+                let value = iter
+                    .next()
+                    .filter(|value| !is_option(value))
+                    .ok_or_else(|| "Missing value for --name".to_string())?;
+                name = Some(validation::validate_process_name(value)?);
+            }
+            "--yes" | "-y" => yes = true,
+            "--force" => force = true,
+            "--privileged-ok" => privileged_ok = true,
+            "--json" => json = true,
+            "--fail-fast" => policy = FailurePolicy::FailFast,
+            "--keep-going" => policy = FailurePolicy::KeepGoing,
+            option if is_option(option) => {
+                return Err(format!("Unknown option for --name: '{}'", option));
+            }
+            other => {
+                return Err(format!(
+                    "--name replaces the port and cannot be combined with '{}'",
+                    other
+                ));
+            }
+        }
+    }
+
+    let name = name.ok_or_else(|| "Missing value for --name".to_string())?;
+    Ok(Command::ByName {
+        name,
+        yes,
+        force,
+        privileged_ok,
+        json,
+        policy,
+        backend,
+    })
+}
+
+/// Parse the arguments of --watch, which keeps terminating whatever binds the port
+fn parse_watch_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut port = None;
+    let mut yes = false;
+    let mut privileged_ok = false;
+    let mut exit_code = DEFAULT_EXIT_CODE;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--watch" => {
+                let value = iter
+                    .next()
+                    .filter(|value| !is_option(value))
+                    .ok_or_else(|| "Missing value for --watch".to_string())?;
+                port = Some(validation::validate_port(value)?);
+            }
+            "--yes" | "-y" => yes = true,
+            "--privileged-ok" => privileged_ok = true,
+            "--exit-code" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --exit-code".to_string())?;
+                exit_code = value
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid exit code: '{}' is not a valid number", value))?;
+            }
+            option if is_option(option) => {
+                return Err(format!("Unknown option for --watch: '{}'", option));
+            }
+            other => {
+                return Err(format!(
+                    "--watch takes the port as its value and cannot be combined with '{}'",
+                    other
+                ));
+            }
+        }
+    }
+
+    let port = port.ok_or_else(|| "Missing value for --watch".to_string())?;
+    Ok(Command::Watch {
+        port,
+        yes,
+        privileged_ok,
+        exit_code,
+        backend,
+    })
+}
+
+/// Parse the options of the workspace command
+fn parse_workspace_args(args: &[String]) -> Result<Command, String> {
+    let (backend, args) = take_backend(args)?;
+    let mut yes = false;
+    let mut force = false;
+    let mut privileged_ok = false;
+    let mut json = false;
+    let mut orphans_only = false;
+    let mut policy = FailurePolicy::KeepGoing;
+
+    for arg in &args {
+        match arg.as_str() {
+            "--yes" | "-y" => yes = true,
+            "--force" => force = true,
+            "--privileged-ok" => privileged_ok = true,
+            "--json" => json = true,
+            "--orphans-only" => orphans_only = true,
+            "--fail-fast" => policy = FailurePolicy::FailFast,
+            "--keep-going" => policy = FailurePolicy::KeepGoing,
+            other => return Err(format!("Unexpected argument for workspace: '{}'", other)),
+        }
+    }
+
+    Ok(Command::Workspace {
+        yes,
+        force,
+        privileged_ok,
+        json,
+        orphans_only,
+        policy,
+        backend,
+    })
+}
+
+/// Parse the path and options of the unix command
+fn parse_unix_args(args: &[String]) -> Result<Command, String> {
+    let parsed = parse_resource_args("unix", "<PATH>", args)?;
+    Ok(Command::Unix {
+        path: PathBuf::from(parsed.target),
+        yes: parsed.yes,
+        force: parsed.force,
+        policy: parsed.policy,
+    })
+}
+
+/// Parse the path and options of the file command
+fn parse_file_args(args: &[String]) -> Result<Command, String> {
+    let restart = args.iter().any(|arg| arg == "--restart");
+    let rest: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--restart")
+        .cloned()
+        .collect();
+    let parsed = parse_resource_args("file", "<PATH> [--restart]", &rest)?;
+    Ok(Command::File {
+        path: PathBuf::from(parsed.target),
+        yes: parsed.yes,
+        force: parsed.force,
+        restart,
+        policy: parsed.policy,
+    })
+}
+
+/// Parse the port name and options of the com command
+fn parse_com_args(args: &[String]) -> Result<Command, String> {
+    let parsed = parse_resource_args("com", "<COMn>", args)?;
+    Ok(Command::Com {
+        port: validation::validate_com_port(&parsed.target)?,
+        yes: parsed.yes,
+        force: parsed.force,
+        policy: parsed.policy,
+    })
+}
+
+/// The arguments shared by commands that free a resource other than a port
+struct ResourceArgs {
+    target: String,
+    yes: bool,
+    force: bool,
+    policy: FailurePolicy,
+}
+
+/// Parse the arguments shared by commands that free a resource other than a port:
+/// the resource itself, --yes, --force and the failure policy
+fn parse_resource_args(
+    command: &str,
+    resource: &str,
+    args: &[String],
+) -> Result<ResourceArgs, String> {
+    let mut target = None;
+    let mut yes = false;
+    let mut force = false;
+    let mut policy = FailurePolicy::KeepGoing;
+
+    for arg in args {
+        match arg.as_str() {
+            "--yes" | "-y" => yes = true,
+            "--force" => force = true,
+            "--fail-fast" => policy = FailurePolicy::FailFast,
+            "--keep-going" => policy = FailurePolicy::KeepGoing,
+            option if is_option(option) => {
+                return Err(format!("Unknown option for {}: '{}'", command, option));
+            }
+            _ if target.is_some() => {
+                return Err(format!("Unexpected argument for {}: '{}'", command, arg));
+            }
+            _ => target = Some(arg.clone()),
+        }
+    }
+
+    let target = target.ok_or_else(|| {
+        format!(
+            "Usage: {} {} [--yes] [--force] [--fail-fast | --keep-going]",
+            command, resource
+        )
+    })?;
+    Ok(ResourceArgs {
+        target,
+        yes,
+        force,
+        policy,
+    })
+}
+
+/// Parse the options of the free-port command
+fn parse_free_port_args(args: &[String]) -> Result<Command, String> {
+    let mut near = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--near" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --near".to_string())?;
+                near = Some(match value.parse::<u16>() {
+                    Ok(port) if port != 0 => port,
+                    _ => return Err(format!("Invalid port: '{}'", value)),
+                });
+            }
+            other => return Err(format!("Unexpected argument for free-port: '{}'", other)),
+        }
+    }
+
+    Ok(Command::FreePort { near })
+}
+
+/// Parse the arguments following the program name
+fn parse_arg_list(program_name: &str, args: &[String]) -> Result<CliArgs, String> {
+    let mut port_arg = None;
+    let mut privileged_ok = false;
+    let mut yes = false;
+    let mut backend = None;
+    let mut exit_code = None;
+    let mut signal = None;
+    let mut method = None;
+    let mut kill_job = false;
+    let mut tree = false;
+    let mut group = false;
+    let mut graceful = false;
+    let mut graceful_timeout = None;
+    let mut dump = false;
+    let mut app_graceful = false;
+    let mut identify = false;
+    let mut suggest = false;
+    let mut fail_if_free = false;
+    let mut strict = false;
+    let mut all = false;
+    let mut policy = FailurePolicy::KeepGoing;
+    let mut copy = false;
+    let mut force = false;
+    let mut details = false;
+    let mut protocol = Protocol::Tcp;
+    let mut listen_only = None;
+    let mut verify_cmd = None;
+    let mut verify_timeout = None;
+    let mut verify_bind = false;
+    let mut transcript = None;
+    let mut json = false;
+    let mut dry_run = false;
+    let mut elevate = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--privileged-ok" => privileged_ok = true,
+            "--yes" | "-y" => yes = true,
+            "--elevate" => elevate = true,
+            "--copy" => copy = true,
+            "--force" => force = true,
+            "--details" => details = true,
+            "--udp" => protocol = Protocol::Udp,
+            "--listen-only" | "--any-state" => {
+                let requested = arg == "--listen-only";
+                if listen_only.is_some_and(|current| current != requested) {
+                    return Err("--listen-only cannot be combined with --any-state".to_string());
+                }
+                listen_only = Some(requested);
+            }
+            "--verify-bind" => verify_bind = true,
+            "--json" => json = true,
+            "--dry-run" => dry_run = true,
+            "--kill-job" => kill_job = true,
+            "--tree" => tree = true,
+            "--group" => group = true,
+            "--graceful" => graceful = true,
+            "--dump" => dump = true,
+            "--app-graceful" => app_graceful = true,
+            "--identify" => identify = true,
+            "--suggest" => suggest = true,
+            "--fail-if-free" => fail_if_free = true,
+            "--strict" => strict = true,
+            "--all" => all = true,
+            "--fail-fast" => policy = FailurePolicy::FailFast,
+            "--keep-going" => policy = FailurePolicy::KeepGoing,
+            "--backend" => backend = Some(parse_backend(iter.next())?),
+            "--verify-cmd" => {
+                let command = iter
+                    .next()
+                    .filter(|command| !command.trim().is_empty())
+                    .ok_or_else(|| "Missing value for --verify-cmd".to_string())?;
+                verify_cmd = Some(command.clone());
+            }
+            "--transcript" => {
+                let path = iter
+                    .next()
+                    .filter(|path| !is_option(path))
+                    .ok_or_else(|| "Missing value for --transcript".to_string())?;
+                transcript = Some(PathBuf::from(path));
+            }
+            "--verify-timeout" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --verify-timeout".to_string())?;
+                verify_timeout = Some(match value.parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+                    _ => {
+                        return Err(format!(
+                            "Invalid verify timeout: '{}' is not a positive number of seconds",
+                            value
+                        ));
+                    }
+                });
+            }
+            "--graceful-timeout" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --graceful-timeout".to_string())?;
+                graceful_timeout = Some(match value.parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+                    _ => {
+                        return Err(format!(
+                            "Invalid graceful timeout: '{}' is not a positive number of seconds",
+                            value
+                        ));
+                    }
+                });
+            }
+            "--method" => {
+                let name = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --method".to_string())?;
+                if name == "socket-close" {
+                    return Err("--method socket-close is not supported: Windows cannot close a listening socket from outside the process that owns it; use --method terminate".to_string());
+                }
+                method = Some(Method::from_name(name).ok_or_else(|| {
+                    format!(
+                        "Invalid method: '{}' (expected terminate, ctrl-break, wm-close, service-stop or connection-reset)",
+                        name
+                    )
+                })?);
+            }
+            "--exit-code" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| "Missing value for --exit-code".to_string())?;
+                exit_code = Some(value.parse::<u32>().map_err(|_| {
+                    format!("Invalid exit code: '{}' is not a valid number", value)
+                })?);
+            }
+            "--sigterm" | "--sigkill" => {
+                let requested = if arg == "--sigterm" {
+                    Signal::Term
+                } else {
+                    Signal::Kill
+                };
+                if signal.is_some_and(|current| current != requested) {
+                    return Err("--sigterm and --sigkill cannot be combined".to_string());
+                }
+                signal = Some(requested);
+            }
+            option if is_option(option) => {
+                return Err(format!("Unknown option: '{}'", option));
+            }
+            _ if port_arg.is_some() => {
+                return Err(format!("Unexpected argument: '{}'", arg));
+            }
+            _ => port_arg = Some(arg),
+        }
+    }
+
     // Parse the port argument
-    let port_str = args.get(1).ok_or_else(|| {
+    let port_str = port_arg.ok_or_else(|| {
         format!(
             "Usage: {} <port>\n\nTerminate the process using the specified port.\n\nExample:\n  {} 8080\n\nFor more information, use: {} --help",
             program_name, program_name, program_name
         )
     })?;
-    let port = port_str
-        .parse::<u16>()
-        .map_err(|_| format!("Invalid port: '{}' is not a valid number", port_str))?;
+    let normalized = validation::normalize_port_input(port_str);
+    let port =
+        normalized
+            .parse::<u16>()
+            .map_err(|_| match validation::port_format_hint(normalized) {
+                Some(hint) => format!("Invalid port: '{}' {}", port_str, hint),
+                None => format!("Invalid port: '{}' is not a valid number", port_str),
+            })?;
+
+    let signal_chosen = signal.is_some() || exit_code.is_some();
+    let sigkill = signal == Some(Signal::Kill);
+    let signal = signal.unwrap_or(Signal::Kill);
+    if signal == Signal::Term && exit_code.is_some() {
+        return Err("--exit-code only applies to forced termination (--sigkill)".to_string());
+    }
+    if graceful {
+        let conflict = [
+            ("--sigterm", signal == Signal::Term),
+            ("--sigkill", sigkill),
+            ("--method", method.is_some()),
+            ("--kill-job", kill_job),
+        ]
+        .into_iter()
+        .find_map(|(option, given)| given.then_some(option));
+        if let Some(option) = conflict {
+            return Err(format!("--graceful cannot be combined with {}", option));
+        }
+    }
+    let asks_to_exit = match method {
+        Some(method) => matches!(method, Method::CtrlBreak | Method::WmClose),
+        None => !sigkill && !kill_job,
+    };
+    if graceful_timeout.is_some() && !asks_to_exit {
+        return Err(
+            "--graceful-timeout only applies when the process is asked to exit first".to_string(),
+        );
+    }
+    if let Some(method) = method {
+        let conflict = [
+            ("--sigterm", signal == Signal::Term),
+            ("--sigkill", sigkill),
+            ("--kill-job", kill_job),
+            ("--tree", tree),
+            ("--app-graceful", app_graceful),
+        ]
+        .into_iter()
+        .find_map(|(option, given)| given.then_some(option));
+        if let Some(option) = conflict {
+            return Err(format!("--method cannot be combined with {}", option));
+        }
+        if exit_code.is_some() && method != Method::Terminate {
+            return Err("--exit-code only applies to --method terminate".to_string());
+        }
+        if group && method != Method::CtrlBreak {
+            return Err("--group only applies to --method ctrl-break".to_string());
+        }
+        if (verify_bind || verify_cmd.is_some()) && method == Method::ConnectionReset {
+            return Err(
+                "--verify-bind and --verify-cmd need a freed port, but --method connection-reset leaves the listener in place"
+                    .to_string(),
+            );
+        }
+    } else if group && signal != Signal::Term {
+        return Err("--group only applies to graceful termination (--sigterm)".to_string());
+    }
+    if protocol == Protocol::Udp && identify {
+        return Err(
+            "--identify probes TCP listeners and cannot be combined with --udp".to_string(),
+        );
+    }
+    if protocol == Protocol::Udp && listen_only == Some(false) {
+        return Err("--any-state only applies to TCP; UDP sockets have no state".to_string());
+    }
+    if protocol == Protocol::Udp && verify_cmd.is_some() {
+        return Err(
+            "--verify-cmd waits for a TCP listener and cannot be combined with --udp".to_string(),
+        );
+    }
+    if verify_timeout.is_some() && verify_cmd.is_none() {
+        return Err("--verify-timeout only applies together with --verify-cmd".to_string());
+    }
+    if dry_run {
+        let conflict = [
+            ("--verify-bind", verify_bind),
+            ("--verify-cmd", verify_cmd.is_some()),
+            ("--dump", dump),
+            ("--copy", copy),
+        ]
+        .into_iter()
+        .find_map(|(option, given)| given.then_some(option));
+        if let Some(option) = conflict {
+            return Err(format!(
+                "{} needs a terminated process and cannot be combined with --dry-run",
+                option
+            ));
+        }
+    }
+    if tree {
+        let conflict = [("--kill-job", kill_job), ("--all", all)]
+            .into_iter()
+            .find_map(|(option, given)| given.then_some(option));
+        if let Some(option) = conflict {
+            return Err(format!("--tree cannot be combined with {}", option));
+        }
+    }
+    if signal == Signal::Term && kill_job {
+        return Err(
+            "--kill-job terminates forcibly and cannot be combined with --sigterm".to_string(),
+        );
+    }
 
-    Ok(CliArgs { port })
+    Ok(CliArgs {
+        port,
+        privileged_ok,
+        yes,
+        backend,
+        protocol,
+        listen_only: listen_only.unwrap_or(true),
+        exit_code: exit_code.unwrap_or(DEFAULT_EXIT_CODE),
+        signal,
+        signal_chosen,
+        method,
+        kill_job,
+        tree,
+        group,
+        graceful,
+        graceful_timeout: graceful_timeout.unwrap_or(GRACEFUL_TIMEOUT),
+        dump,
+        app_graceful,
+        identify,
+        suggest,
+        fail_if_free,
+        strict,
+        all,
+        policy,
+        copy,
+        details,
+        force,
+        verify_cmd,
+        verify_timeout: verify_timeout.unwrap_or(verify::DEFAULT_TIMEOUT),
+        verify_bind,
+        transcript,
+        json,
+        dry_run,
+        elevate,
+    })
 }
 
-/// Display information about the process using the port
-pub fn display_process_info(pid: u32, name: &str) {
-    println!("Found process using port:");
-    println!("  PID: {}", pid);
-    println!("  Name: {}", name);
-    println!();
+/// Detect the elevated relaunch started by --elevate
+/// Returns the file its output goes to, which the unelevated evict reads back
+pub fn parse_elevated_output() -> Option<PathBuf> {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) != Some(ELEVATED_OUTPUT_FLAG) {
+        return None;
+    }
+    args.get(2).map(PathBuf::from)
 }
 
-/// Display success message after terminating the process
-pub fn display_success(port: u16) {
-    println!("Terminating process...");
-    println!("Port {} is now free", port);
+/// Detect the console control helper mode used internally by --sigterm
+/// Returns the target PID, process group and control event when evict was started as the helper
+pub fn parse_console_ctrl_helper() -> Option<(u32, u32, u32)> {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) != Some(CONSOLE_CTRL_HELPER_FLAG) {
+        return None;
+    }
+
+    let pid = args.get(2)?.parse().ok()?;
+    let group = args.get(3)?.parse().ok()?;
+    let event = args.get(4)?.parse().ok()?;
+    Some((pid, group, event))
 }
 
-/// Display error message with proper formatting
-pub fn display_error(error: &str) {
-    eprintln!("Error: {}", error);
+/// Check whether an argument looks like an option rather than a value
+/// Negative numbers are treated as values so they get a port error instead
+fn is_option(arg: &str) -> bool {
+    arg.starts_with('-') && arg.len() > 1 && arg.parse::<i64>().is_err()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use proptest::prelude::*;
-    use std::io::Write;
+/// Ask the user to confirm terminating the owner of a privileged or production port
+/// The user must type the port number back; stdin that is not a terminal is refused
+pub fn confirm_sensitive_port(port: u16) -> Result<(), Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::new(
+            ErrorKind::NotConfirmed,
+            format!(
+                "Port {} is a privileged or production port; pass --privileged-ok to terminate its owner without confirmation",
+                port
+            ),
+        ));
+    }
 
-    // **Feature: port-killer, Property 3: Display output contains required information**
-    proptest! {
-        #![proptest_config(ProptestConfig::with_cases(100))]
+    let answer = prompt(&format!(
+        "Port {} is a privileged or production port. Type the port number to confirm: ",
+        port
+    ))?;
 
-        #[test]
-        fn test_display_output_contains_required_info(
-            pid in 1u32..100000u32,
-            name in "[a-zA-Z0-9_-]{1,20}\\.(exe|dll|bin)"
-        ) {
-            // Capture stdout
-            let mut buffer = Vec::new();
-            {
-                let output = format!("Found process using port:\n  PID: {}\n  Name: {}\n\n", pid, name);
-                buffer.write_all(output.as_bytes()).unwrap();
-            }
+    if answer == port.to_string() {
+        display_line("");
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::NotConfirmed,
+            "Confirmation did not match the port number, nothing was terminated",
+        ))
+    }
+}
 
-            let output_str = String::from_utf8(buffer).unwrap();
+/// Display the configuration evict runs with, built-in defaults included
+pub fn display_effective_config(config: &Config) {
+    println!("Effective configuration:");
+    let ports: Vec<String> = config
+        .production_ports
+        .iter()
+        .map(|port| port.to_string())
+        .collect();
+    println!(
+        "  production_ports = {}    # ports below 1024 always need confirmation",
+        ports.join(", ")
+    );
+    println!("  safety = {}", config.safety.as_str());
+    for (rule, built_in) in config.effective_termination() {
+        let origin = if built_in { "    # built-in" } else { "" };
+        println!(
+            "  termination = {} {}{}",
+            rule.process,
+            rule.method.as_str(),
+            origin
+        );
+    }
+    for (entry, built_in) in config.effective_app_shutdown() {
+        let origin = if built_in { "    # built-in" } else { "" };
+        println!(
+            "  app_shutdown = {} {}{}",
+            entry.process, entry.action, origin
+        );
+    }
+}
 
-            // Verify output contains both PID and process name
-            prop_assert!(output_str.contains(&pid.to_string()), "Output should contain PID");
-            prop_assert!(output_str.contains(&name), "Output should contain process name");
+/// Ask for the settings of a new configuration file
+/// Invalid answers are asked again; stdin that is not a terminal is refused
+pub fn prompt_config_setup() -> Result<(Vec<u16>, Vec<AppShutdown>), String> {
+    if !io::stdin().is_terminal() {
+        return Err(
+            "Interactive setup needs a terminal; run config init without --interactive".to_string(),
+        );
+    }
+
+    let production_ports = loop {
+        let answer = prompt(
+            "Ports that always need confirmation before termination, besides those below 1024\n\
+             (comma-separated, empty for none): ",
+        )?;
+        match config::parse_port_list(&answer) {
+            Ok(ports) => break ports,
+            Err(err) => display_error(&Error::new(ErrorKind::InvalidConfig, err)),
+        }
+    };
+
+    println!();
+    println!("Application shutdown actions for --app-graceful, as '<executable> <action>'");
+    println!("where the action is an HTTP path to POST to or ctrl-c. Empty line to finish.");
+    let mut app_shutdown = Vec::new();
+    loop {
+        let answer = prompt("> ")?;
+        if answer.is_empty() {
+            break;
+        }
+        match config::parse_app_shutdown(&answer) {
+            Ok(entry) => app_shutdown.push(entry),
+            Err(err) => display_error(&Error::new(ErrorKind::InvalidConfig, err)),
         }
     }
 
-    // **Feature: port-killer, Property 5: Error conditions produce error messages**
-    proptest! {
-        #![proptest_config(ProptestConfig::with_cases(100))]
+    Ok((production_ports, app_shutdown))
+}
 
-        #[test]
-        fn test_error_conditions_produce_messages(
-            error_msg in "[a-zA-Z0-9 ]{5,50}"
-        ) {
-            // Capture stderr
-            let mut buffer = Vec::new();
-            {
-                let output = format!("Error: {}\n", error_msg);
-                buffer.write_all(output.as_bytes()).unwrap();
-            }
+/// Print a prompt and read one trimmed line from stdin
+/// With --json the prompt goes to stderr, so stdout carries only the JSON result
+fn prompt(question: &str) -> Result<String, String> {
+    let written = if json_output() {
+        eprint!("{}", question);
+        io::stderr().flush()
+    } else {
+        print!("{}", question);
+        io::stdout().flush()
+    };
+    written.map_err(|e| format!("Failed to write prompt: {}", e))?;
 
-            let output_str = String::from_utf8(buffer).unwrap();
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Failed to read answer: {}", e))?;
+    Ok(answer.trim().to_string())
+}
 
-            // Verify error output contains the error message and "Error:" prefix
-            prop_assert!(output_str.contains("Error:"), "Output should contain 'Error:' prefix");
-            prop_assert!(output_str.contains(&error_msg), "Output should contain error message");
+/// Display the per-target summary of a run against several processes
+pub fn display_summary(results: &[StepResult]) {
+    if json_output() {
+        return;
+    }
+    println!();
+    println!(
+        "{:<12} {:<24} {:<10} {:<8} DURATION",
+        "TARGET", "OWNER", "ACTION", "OUTCOME"
+    );
+    for result in results {
+        let (outcome, duration) = match &result.outcome {
+            Outcome::Done => ("ok", format!("{} ms", result.duration.as_millis())),
+            Outcome::Failed(_) => ("failed", format!("{} ms", result.duration.as_millis())),
+            Outcome::Skipped => ("skipped", "-".to_string()),
+        };
+        println!(
+            "{:<12} {:<24} {:<10} {:<8} {}",
+            result.target, result.owner, result.action, outcome, duration
+        );
+        if let Outcome::Failed(err) = &result.outcome {
+            println!("  {}", err);
         }
     }
+    println!();
+}
 
-    // **Feature: port-killer, Property 7: Success operations produce confirmation messages**
-    // **Validates: Requirements 2.2**
-    proptest! {
-        #![proptest_config(ProptestConfig::with_cases(100))]
+/// Display why a port is unavailable and what to do about it
+pub fn display_explanation(port: u16, explanation: &Explanation) {
+    println!("Port {}:", port);
+    for reason in &explanation.reasons {
+        println!("  {}", reason);
+    }
+    println!();
+    println!("Recommended: {}", explanation.remedy);
+}
 
-        #[test]
-        fn test_success_operations_produce_confirmation(
-            port in 1u16..=65535u16
-        ) {
-            // Capture stdout
-            let mut buffer = Vec::new();
-            {
-                let output = format!("Terminating process...\nPort {} is now free\n", port);
-                buffer.write_all(output.as_bytes()).unwrap();
-            }
+/// Display the listening sockets of the machine, one line per socket
+pub fn display_listing(listeners: &[(PortBinding, String)]) {
+    println!("{:<7} {:<40} {:<8} NAME", "PORT", "ADDRESS", "PID");
+    for (binding, name) in listeners {
+        println!(
+            "{:<7} {:<40} {:<8} {}",
+            binding.port,
+            format_addresses(std::slice::from_ref(&binding.address)),
+            binding.pid,
+            name
+        );
+    }
+    println!();
+    println!("{} listening socket(s)", listeners.len());
+}
 
-            let output_str = String::from_utf8(buffer).unwrap();
+/// Display what --dry-run found: each process as (PID, name, action, path)
+pub fn display_dry_run(targets: &[(u32, String, &'static str, Option<String>)]) {
+    if json_output() {
+        return;
+    }
+    println!("Dry run, nothing was terminated. Without --dry-run evict would:");
+    for (pid, name, action, path) in targets {
+        println!(
+            "  {:<10} PID {} {} ({})",
+            action,
+            pid,
+            name,
+            path.as_deref().unwrap_or("path unknown")
+        );
+    }
+}
 
-            // Verify success output contains confirmation message and port number
-            prop_assert!(output_str.contains("Terminating process"), "Output should contain 'Terminating process'");
-            prop_assert!(output_str.contains("is now free"), "Output should contain 'is now free'");
-            prop_assert!(output_str.contains(&port.to_string()), "Output should contain port number");
+/// Display the sockets the find command matched, one line per socket
+pub fn display_matches(matches: &[Match]) {
+    println!(
+        "{:<7} {:<40} {:<13} {:<8} {:<24} USER",
+        "PORT", "ADDRESS", "STATE", "PID", "NAME"
+    );
+    for found in matches {
+        println!(
+            "{:<7} {:<40} {:<13} {:<8} {:<24} {}",
+            found.binding.port,
+            format_addresses(std::slice::from_ref(&found.binding.address)),
+            found.binding.state.map_or("-", TcpState::as_str),
+            found.binding.pid,
+            found.name,
+            found.user.as_deref().unwrap_or("unknown")
+        );
+    }
+    println!();
+    println!("{} matching socket(s)", matches.len());
+}
+
+/// Display the listening processes of the machine, one line per process
+pub fn display_inventory(processes: &[InventoryProcess]) {
+    for process in processes {
+        let ports: Vec<String> = process.ports.iter().map(|port| port.to_string()).collect();
+        println!(
+            "PID {} {} (port {})",
+            process.pid,
+            process.name,
+            ports.join(", ")
+        );
+        if let Some(details) = &process.details {
+            let unknown = || "unknown".to_string();
+            println!("  Path: {}", details.path.clone().unwrap_or_else(unknown));
+            println!(
+                "  Command line: {}",
+                details.command_line.clone().unwrap_or_else(unknown)
+            );
+            println!("  User: {}", details.user.clone().unwrap_or_else(unknown));
+            if !details.services.is_empty() {
+                println!("  Services: {}", details.services.join(", "));
+            }
+            println!(
+                "  SHA-256: {}",
+                details.sha256.clone().unwrap_or_else(unknown)
+            );
+            println!("  Signature: {}", details.signature.unwrap_or("unknown"));
         }
     }
+    println!();
+    println!("{} listening process(es)", processes.len());
+}
 
-    // Unit tests for CLI functions
+/// Format the inventory as one JSON document
+///
+/// Every field is always present so collectors can rely on the layout; detail
+/// fields are null without --details or when they cannot be read
+pub fn inventory_json(host: &str, collected_at: u64, processes: &[InventoryProcess]) -> String {
+    let optional = |value: Option<&str>| value.map_or_else(|| "null".to_string(), json_string);
 
-    #[test]
-    fn test_parse_args_with_valid_port() {
-        // Note: This test would need to mock env::args() which is difficult in Rust
-        // In practice, this would be tested through integration tests
-        // For now, we'll test the validation logic indirectly
-        let port_str = "8080";
-        let port: Result<u16, _> = port_str.parse();
-        assert!(port.is_ok());
+    let entries: Vec<String> = processes
+        .iter()
+        .map(|process| {
+            let details = process.details.clone().unwrap_or_default();
+            let ports: Vec<String> = process.ports.iter().map(|port| port.to_string()).collect();
+            let services = if process.details.is_some() {
+                let names: Vec<String> = details.services.iter().map(|s| json_string(s)).collect();
+                format!("[{}]", names.join(","))
+            } else {
+                "null".to_string()
+            };
+            format!(
+                "{{\"pid\":{},\"name\":{},\"ports\":[{}],\"path\":{},\"command_line\":{},\"user\":{},\"services\":{},\"sha256\":{},\"signature\":{}}}",
+                process.pid,
+                json_string(&process.name),
+                ports.join(","),
+                optional(details.path.as_deref()),
+                optional(details.command_line.as_deref()),
+                optional(details.user.as_deref()),
+                services,
+                optional(details.sha256.as_deref()),
+                optional(details.signature),
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"schema_version\":{},\"host\":{},\"collected_at\":{},\"processes\":[{}]}}",
+        inventory::SCHEMA_VERSION,
+        json_string(host),
+        collected_at,
+        entries.join(",")
+    )
+}
+
+/// Display listening processes under a heading, with the ports each one holds
+pub fn display_listeners(heading: &str, listeners: &[ProjectListener]) {
+    if json_output() {
+        return;
+    }
+    println!("{}:", heading);
+    for listener in listeners {
+        println!("  {}", describe_listener(listener));
+    }
+    println!();
+}
+
+/// Describe a listening process with its ports, and whether its parent is gone
+pub fn describe_listener(listener: &ProjectListener) -> String {
+    let ports: Vec<String> = listener.ports.iter().map(|port| port.to_string()).collect();
+    let orphaned = if listener.orphaned { " [orphaned]" } else { "" };
+    format!(
+        "PID {} {} (port {}){}",
+        listener.pid,
+        listener.name,
+        ports.join(", "),
+        orphaned
+    )
+}
+
+/// Ask the user to confirm terminating the processes that were listed
+/// stdin that is not a terminal is refused, --yes skips the question
+pub fn confirm_termination(count: usize) -> Result<(), Error> {
+    confirm_processes(&format!("Terminate {} process(es)?", count), "terminate")
+        .map_err(|declined| declined.unwrap_or_else(|| not_confirmed("Nothing was terminated")))
+}
+
+/// Ask the user to confirm terminating a port's owner and the others going with it
+/// stdin that is not a terminal is refused, --yes skips the question
+pub fn confirm_kill(name: &str, pid: u32, others: usize) -> Result<(), Error> {
+    let question = match others {
+        0 => format!("Kill {} (PID {})?", name, pid),
+        _ => format!(
+            "Kill {} (PID {}) and {} other process(es)?",
+            name, pid, others
+        ),
+    };
+    confirm_processes(&question, "terminate")
+        .map_err(|declined| declined.unwrap_or_else(|| not_confirmed("Nothing was terminated")))
+}
+
+/// Offer to relaunch evict as administrator to terminate a process it may not touch
+/// stdin that is not a terminal is refused
+pub fn confirm_elevation(name: &str, pid: u32) -> Result<(), Error> {
+    let question = format!(
+        "Relaunch evict as administrator to terminate {} (PID {})?",
+        name, pid
+    );
+    confirm_processes(&question, "elevate")
+        .map_err(|declined| declined.unwrap_or_else(|| not_confirmed("Nothing was terminated")))
+}
+
+/// Ask the user to confirm terminating everything that binds the port from now on
+/// stdin that is not a terminal is refused, --yes skips the question
+pub fn confirm_watch(port: u16) -> Result<(), Error> {
+    let question = format!(
+        "Terminate every process that binds port {} until evict is stopped?",
+        port
+    );
+    confirm_processes(&question, "watch the port")
+        .map_err(|declined| declined.unwrap_or_else(|| not_confirmed("Nothing is being watched")))
+}
+
+/// Ask the user to confirm restarting the processes that were listed
+/// stdin that is not a terminal is refused, --yes skips the question
+pub fn confirm_restart(count: usize) -> Result<(), Error> {
+    confirm_processes(&format!("Restart {} process(es)?", count), "restart")
+        .map_err(|declined| declined.unwrap_or_else(|| not_confirmed("Nothing was restarted")))
+}
+
+/// The error of a question that was answered no
+fn not_confirmed(message: &str) -> Error {
+    Error::new(ErrorKind::NotConfirmed, message)
+}
+
+/// Ask a yes/no question before acting on the listed processes
+/// Fails with Some(error) when the question cannot be asked, None when the answer is no
+fn confirm_processes(question: &str, action: &str) -> Result<(), Option<Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(Some(not_confirmed(&format!(
+            "Refusing to {} without confirmation; pass --yes",
+            action
+        ))));
+    }
+
+    let answer = prompt(&format!("{} [y/N] ", question)).map_err(|err| Some(err.into()))?;
+
+    if matches!(answer.as_str(), "y" | "Y" | "yes") {
+        Ok(())
+    } else {
+        Err(None)
+    }
+}
+
+/// Display the processes holding a resource other than a port
+pub fn display_holders(resource: &str, holders: &[(u32, String)]) {
+    println!("{} is held by:", resource);
+    for (pid, name) in holders {
+        println!("  PID {} {}", pid, name);
+    }
+    println!();
+}
+
+/// Display the processes sharing a port and which one is targeted
+pub fn display_candidates(port: u16, candidates: &[Candidate], chosen: u32) {
+    if json_output() {
+        return;
+    }
+    println!("Port {} is held by {} processes:", port, candidates.len());
+    for candidate in candidates {
+        let marker = if candidate.pid == chosen { "*" } else { " " };
+        println!("  {} {}", marker, describe_candidate(candidate));
+    }
+    println!();
+}
+
+/// Describe one of the processes holding a port, with its addresses and whether it is
+/// from the current project
+pub fn describe_candidate(candidate: &Candidate) -> String {
+    let project = if candidate.in_project {
+        " (current project)"
+    } else {
+        ""
+    };
+    let addresses = if candidate.addresses.is_empty() {
+        String::new()
+    } else {
+        format!(" on {}", format_addresses(&candidate.addresses))
+    };
+    format!(
+        "PID {} {}{}{}",
+        candidate.pid, candidate.name, addresses, project
+    )
+}
+
+/// A process offered by the picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickEntry {
+    /// PID, name and where it holds ports
+    pub description: String,
+    /// Executable path, which tells apart processes with the same name
+    pub path: Option<String>,
+}
+
+/// Check whether questions can be asked, so a picker can replace picking automatically
+pub fn can_prompt() -> bool {
+    io::stdin().is_terminal()
+}
+
+/// Let the user choose from a numbered menu which of several processes to terminate
+/// Returns the indexes of the chosen entries, asking again after an invalid answer;
+/// Enter takes the default when there is one, and only `multiple` allows several
+pub fn pick_processes(
+    heading: &str,
+    entries: &[PickEntry],
+    default: Option<usize>,
+    multiple: bool,
+) -> Result<Vec<usize>, Error> {
+    println!("{}:", heading);
+    for (index, entry) in entries.iter().enumerate() {
+        println!("  {}) {}", index + 1, entry.description);
+        println!("     {}", entry.path.as_deref().unwrap_or("path unknown"));
+    }
+    println!();
+
+    let mut options = vec![format!("1-{}", entries.len())];
+    if multiple {
+        options.push("several as 1,3".to_string());
+        options.push("a for all".to_string());
+    }
+    options.push(match default {
+        Some(index) => format!("Enter for {}", index + 1),
+        None => "Enter to cancel".to_string(),
+    });
+    loop {
+        print!("Terminate which? [{}] ", options.join(", "));
+        io::stdout()
+            .flush()
+            .map_err(|e| format!("Failed to write prompt: {}", e))?;
+
+        let mut answer = String::new();
+        let read = io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| format!("Failed to read choice: {}", e))?;
+        if read == 0 {
+            return Err(not_confirmed("Nothing was terminated"));
+        }
+        match parse_pick(&answer, entries.len(), default, multiple) {
+            Ok(Some(chosen)) => return Ok(chosen),
+            Ok(None) => return Err(not_confirmed("Nothing was terminated")),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+/// Parse an answer to the picker: 1-based numbers separated by commas or spaces,
+/// "a" for all, "q" or Enter without a default to choose nothing
+fn parse_pick(
+    answer: &str,
+    count: usize,
+    default: Option<usize>,
+    multiple: bool,
+) -> Result<Option<Vec<usize>>, String> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(default.map(|index| vec![index]));
+    }
+    if answer.eq_ignore_ascii_case("q") {
+        return Ok(None);
+    }
+    if multiple && (answer.eq_ignore_ascii_case("a") || answer.eq_ignore_ascii_case("all")) {
+        return Ok(Some((0..count).collect()));
+    }
+
+    let mut chosen = Vec::new();
+    for part in answer.split([',', ' ']).filter(|part| !part.is_empty()) {
+        let index = part
+            .parse::<usize>()
+            .ok()
+            .filter(|number| (1..=count).contains(number))
+            .ok_or_else(|| format!("Choose a number between 1 and {}, got: {}", count, part))?;
+        if !chosen.contains(&(index - 1)) {
+            chosen.push(index - 1);
+        }
+    }
+    if chosen.len() > 1 && !multiple {
+        return Err("Choose a single process".to_string());
+    }
+    Ok(Some(chosen))
+}
+
+/// Display information about the process using the port
+/// Each of its sockets on the port is shown with its state and local address,
+/// so a loopback-only bind stands out from one on every interface
+pub fn display_process_info(pid: u32, name: &str, sockets: &[PortBinding]) {
+    if json_output() {
+        return;
+    }
+    println!("Found process using port:");
+    println!("  PID: {}", pid);
+    println!("  Name: {}", name);
+    let mut shown: Vec<String> = Vec::new();
+    for socket in sockets {
+        let description = socket.describe();
+        if !shown.contains(&description) {
+            println!("  Socket: {}", description);
+            shown.push(description);
+        }
+    }
+    println!();
+}
+
+/// Display the capability matrix of --details: which actions against the
+/// owner the current token can take, and why the others would fail
+pub fn display_capabilities(capabilities: &[(&str, Capability)]) {
+    if json_output() {
+        return;
+    }
+    println!("  Capabilities:");
+    for (action, capability) in capabilities {
+        let verdict = match capability {
+            Capability::Allowed => "allowed".to_string(),
+            Capability::Denied(reason) => format!("denied ({})", reason),
+            Capability::NotApplicable => "not applicable".to_string(),
+        };
+        println!("    {:<18}{}", action, verdict);
+    }
+}
+
+/// Display the actions a run without elevation skipped, with the reason for each
+/// Only the denied entries of the capability matrix are listed
+pub fn display_skipped_actions(capabilities: &[(&str, Capability)]) {
+    if json_output() {
+        return;
+    }
+    let denied: Vec<(&str, &String)> = capabilities
+        .iter()
+        .filter_map(|(action, capability)| match capability {
+            Capability::Denied(reason) => Some((*action, reason)),
+            _ => None,
+        })
+        .collect();
+    if denied.is_empty() {
+        return;
+    }
+    println!("  Skipped for lack of privileges:");
+    for (action, reason) in denied {
+        println!("    {:<18}{}", action, reason);
+    }
+}
+
+/// Join local addresses for display, with IPv6 ones in brackets
+pub fn format_addresses(addresses: &[IpAddr]) -> String {
+    let formatted: Vec<String> = addresses
+        .iter()
+        .map(|address| match address {
+            IpAddr::V4(address) => address.to_string(),
+            IpAddr::V6(address) => format!("[{}]", address),
+        })
+        .collect();
+    formatted.join(", ")
+}
+
+/// Display the certificate presented by a TLS listener
+pub fn display_certificate(certificate: &Certificate) {
+    if json_output() {
+        return;
+    }
+    println!("  Certificate: {}", certificate.subject);
+    println!("  Issuer: {}", certificate.issuer);
+    if certificate.expired {
+        println!("  Expires: {} (expired)", certificate.not_after);
+    } else {
+        println!("  Expires: {}", certificate.not_after);
+    }
+}
+
+/// Display what an HTTP listener serves at its root
+pub fn display_http_page(page: &HttpPage) {
+    if json_output() {
+        return;
+    }
+    println!("  HTTP status: {}", page.status);
+    if let Some(title) = &page.title {
+        println!("  Page title: {}", title);
+    }
+}
+
+/// Display the used ports of a range with their owners, then the totals
+/// The summary merges consecutive ports with the same owners into one line
+pub fn display_range_usage(
+    usage: &RangeUsage,
+    summary: bool,
+    process_name: impl Fn(u32) -> String,
+) {
+    let owners = |pids: &[u32]| {
+        pids.iter()
+            .map(|&pid| format!("{} ({})", process_name(pid), pid))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    println!("Ports {}-{}:", usage.start, usage.end);
+    if summary {
+        for run in usage.runs() {
+            let ports = if run.start == run.end {
+                run.start.to_string()
+            } else {
+                format!("{}-{}", run.start, run.end)
+            };
+            println!("  {:<11} {}", ports, owners(&run.pids));
+        }
+    } else {
+        for (port, pids) in &usage.used {
+            println!("  {:<11} {}", port, owners(pids));
+        }
+    }
+
+    println!();
+    println!("{} used, {} free", usage.used.len(), usage.free_count());
+}
+
+/// Display a free alternative to a port that stays busy
+pub fn display_suggestion(port: u16, alternative: Option<u16>) {
+    if json_output() {
+        return;
+    }
+    match alternative {
+        Some(alternative) => println!("Port {} is busy, {} is free", port, alternative),
+        None => println!(
+            "Port {} is busy and no free port was found close to it",
+            port
+        ),
+    }
+}
+
+/// Format the outcome of freeing a port for pasting into a ticket or chat
+pub fn format_report(report: &ResultReport) -> String {
+    let outcome = match &report.outcome {
+        Ok(()) => "freed".to_string(),
+        Err(err) => format!("not freed ({})", err),
+    };
+    let unknown = || "unknown".to_string();
+
+    [
+        format!("Port {}: {}", report.port, outcome),
+        format!("  PID: {}", report.pid),
+        format!("  Name: {}", report.name),
+        format!("  Path: {}", report.path.clone().unwrap_or_else(unknown)),
+        format!(
+            "  Command line: {}",
+            report.command_line.clone().unwrap_or_else(unknown)
+        ),
+        format!("  Action: {}", report.action),
+    ]
+    .join("\r\n")
+}
+
+/// Switch stdout to the single JSON result of --json; errors become JSON as well
+pub fn enable_json_output() {
+    JSON_OUTPUT.store(true, Ordering::Relaxed);
+    JSON_ERRORS.store(true, Ordering::Relaxed);
+}
+
+/// Whether --json is in effect
+pub fn json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Display a line of progress text, which --json leaves out
+pub fn display_line(text: &str) {
+    if !json_output() {
+        println!("{}", text);
+    }
+}
+
+/// What --json prints once evict is done with a port
+pub struct JsonResult<'a> {
+    pub port: u16,
+    pub protocol: Protocol,
+    /// The socket evict acted on and its process name; None when the port was not in use
+    pub found: Option<(&'a PortBinding, &'a str)>,
+    /// How the owner was terminated; None when it was not
+    pub action: Option<&'static str>,
+    /// Every process acted on, the chosen owner first
+    pub steps: &'a [StepResult],
+    pub outcome: &'a Result<(), Error>,
+    /// Outcome of --verify-bind and --verify-cmd, one line each
+    pub checks: &'a [String],
+    /// Whether --dry-run stopped evict before it terminated anything
+    pub dry_run: bool,
+}
+
+/// Format the result of freeing a port as one JSON object
+/// Every field is always present; the ones that do not apply are null or empty
+pub fn result_json(result: &JsonResult) -> String {
+    let optional = |value: Option<&str>| value.map_or_else(|| "null".to_string(), json_string);
+    let binding = result.found.map_or_else(
+        || "null".to_string(),
+        |(binding, _)| {
+            format!(
+                "{{\"pid\":{},\"port\":{},\"address\":{},\"state\":{}}}",
+                binding.pid,
+                binding.port,
+                json_string(&binding.address.to_string()),
+                optional(binding.state.map(TcpState::as_str))
+            )
+        },
+    );
+    let steps: Vec<String> = result.steps.iter().map(step_json).collect();
+    let checks: Vec<String> = result
+        .checks
+        .iter()
+        .map(|check| json_string(check))
+        .collect();
+
+    format!(
+        "{{\"port\":{},\"protocol\":\"{}\",\"in_use\":{},\"binding\":{},\"process_name\":{},\"action\":{},\"freed\":{},\"error\":{},\"steps\":[{}],\"checks\":[{}],\"dry_run\":{}}}",
+        result.port,
+        result.protocol.as_str(),
+        result.found.is_some(),
+        binding,
+        optional(result.found.map(|(_, name)| name)),
+        optional(result.action),
+        result.found.is_some() && result.outcome.is_ok() && !result.dry_run,
+        optional(
+            result
+                .outcome
+                .as_ref()
+                .err()
+                .map(|err| err.message.as_str())
+        ),
+        steps.join(","),
+        checks.join(","),
+        result.dry_run
+    )
+}
+
+/// Format the result of --name or workspace as one JSON object
+/// `matched` counts the processes selected; steps are empty when none were terminated
+pub fn batch_json(matched: usize, steps: &[StepResult], outcome: &Result<(), Error>) -> String {
+    let steps: Vec<String> = steps.iter().map(step_json).collect();
+    format!(
+        "{{\"matched\":{},\"error\":{},\"steps\":[{}]}}",
+        matched,
+        outcome
+            .as_ref()
+            .err()
+            .map_or_else(|| "null".to_string(), |err| json_string(&err.message)),
+        steps.join(",")
+    )
+}
+
+/// Format what was done to one process as a JSON object
+fn step_json(step: &StepResult) -> String {
+    let (outcome, error) = match &step.outcome {
+        Outcome::Done => ("ok", None),
+        Outcome::Failed(err) => ("failed", Some(err.message.as_str())),
+        Outcome::Skipped => ("skipped", None),
+    };
+    format!(
+        "{{\"target\":{},\"owner\":{},\"action\":\"{}\",\"outcome\":\"{}\",\"error\":{}}}",
+        json_string(&step.target),
+        json_string(&step.owner),
+        step.action,
+        outcome,
+        error.map_or_else(|| "null".to_string(), json_string)
+    )
+}
+
+/// Display success message after terminating the process
+pub fn display_success(port: u16) {
+    if json_output() {
+        return;
+    }
+    println!("Terminating process...");
+    println!("Port {} is now free", port);
+}
+
+/// Describe how to bring back the process that was terminated, for when its
+/// replacement does not come up or fails the --verify-cmd check
+pub fn rollback_advice(name: &str, pid: u32, command_line: Option<&str>) -> String {
+    match command_line {
+        Some(command_line) => format!(
+            "To roll back, start the previous owner again: {}",
+            command_line
+        ),
+        None => format!(
+            "To roll back, start the previous owner ({}, PID {}) again",
+            name, pid
+        ),
+    }
+}
+
+/// Display the alternatives to terminating a protected process
+/// With --json-errors they are one JSON object with an "alternatives" list
+pub fn display_protected_alternatives(services: &[ServiceInfo]) {
+    let mut alternatives: Vec<String> = services
+        .iter()
+        .map(|service| {
+            format!(
+                "Stop the '{}' service it hosts: sc stop {}",
+                service.display_name, service.name
+            )
+        })
+        .collect();
+    if services.is_empty() {
+        alternatives.push(
+            "Stop or reconfigure the product that owns it (usually security software)".to_string(),
+        );
+    }
+    alternatives.push(
+        "Reset its connections instead of terminating it (requires administrator)".to_string(),
+    );
+
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        let quoted: Vec<String> = alternatives.iter().map(|item| json_string(item)).collect();
+        eprintln!("{{\"alternatives\":[{}]}}", quoted.join(","));
+        return;
+    }
+    eprintln!("Alternatives:");
+    for alternative in &alternatives {
+        eprintln!("  - {}", alternative);
+    }
+}
+
+/// Display the job object a process belongs to, when it matters for termination
+pub fn display_job_info(pid: u32, member_pids: &[u32], kill_on_close: bool) {
+    if json_output() {
+        return;
+    }
+    let others: Vec<String> = member_pids
+        .iter()
+        .filter(|&&member| member != pid)
+        .map(|member| member.to_string())
+        .collect();
+
+    if !others.is_empty() {
+        println!("Job object: shared with PID {}", others.join(", "));
+    }
+    if kill_on_close {
+        println!("Job object: kill-on-close (the job owner may restart or tear down its members)");
+    }
+    if !others.is_empty() || kill_on_close {
+        println!("Note: Use --kill-job to terminate the whole job at once");
+    }
+}
+
+/// Display the processes --tree terminates along with the owner
+pub fn display_tree_info(pid: u32, children: &[(u32, String)]) {
+    if json_output() {
+        return;
+    }
+    if children.is_empty() {
+        println!(
+            "Process tree: PID {} has not started any running process",
+            pid
+        );
+        return;
+    }
+    println!(
+        "Process tree: {} process(es) started under PID {}",
+        children.len(),
+        pid
+    );
+    for (child, name) in children {
+        println!("  PID {} {}", child, name);
+    }
+}
+
+/// Display error message with proper formatting
+pub fn display_error(error: &Error) {
+    report_error(error, None);
+}
+
+/// Display an error together with a hint on how to get past it
+pub fn display_error_with_hint(error: &Error, hint: &str) {
+    report_error(error, Some(hint));
+}
+
+/// Display a warning about something that does not stop evict
+pub fn display_warning(warning: &str) {
+    report_notice("warning", "Warning", warning);
+}
+
+/// Display a note about how evict goes about its work
+pub fn display_note(note: &str) {
+    report_notice("note", "Note", note);
+}
+
+/// Write a warning or note to stderr as "Label: text", or as {"key":"text"} with --json-errors
+fn report_notice(key: &str, label: &str, text: &str) {
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        eprintln!("{{\"{}\":{}}}", key, json_string(text));
+    } else {
+        eprintln!("{}: {}", label, text);
+    }
+}
+
+/// Write an error to stderr as text, or as one JSON object per line with --json-errors
+fn report_error(error: &Error, hint: Option<&str>) {
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        eprintln!("{}", error_json(error, hint));
+        return;
+    }
+
+    eprintln!("Error: {}", error);
+    if let Some(hint) = hint {
+        eprintln!("Hint: {}", hint);
+    }
+}
+
+/// Render an error as a single-line JSON object
+/// Fields: error (message), code and category (see ErrorKind), win32_code, hint
+fn error_json(error: &Error, hint: Option<&str>) -> String {
+    let win32_code = error
+        .win32_code
+        .map_or_else(|| "null".to_string(), |code| code.to_string());
+    let hint = hint.map_or_else(|| "null".to_string(), json_string);
+
+    format!(
+        "{{\"error\":{},\"code\":\"{}\",\"category\":\"{}\",\"win32_code\":{},\"hint\":{}}}",
+        json_string(&error.message),
+        error.kind.code(),
+        error.kind.category(),
+        win32_code,
+        hint
+    )
+}
+
+/// Quote a string as a JSON string literal
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Write;
+
+    // **Feature: port-killer, Property 3: Display output contains required information**
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_display_output_contains_required_info(
+            pid in 1u32..100000u32,
+            name in "[a-zA-Z0-9_-]{1,20}\\.(exe|dll|bin)"
+        ) {
+            // Capture stdout
+            let mut buffer = Vec::new();
+            {
+                let output = format!("Found process using port:\n  PID: {}\n  Name: {}\n\n", pid, name);
+                buffer.write_all(output.as_bytes()).unwrap();
+            }
+
+            let output_str = String::from_utf8(buffer).unwrap();
+
+            // Verify output contains both PID and process name
+            prop_assert!(output_str.contains(&pid.to_string()), "Output should contain PID");
+            prop_assert!(output_str.contains(&name), "Output should contain process name");
+        }
+    }
+
+    // **Feature: port-killer, Property 5: Error conditions produce error messages**
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_error_conditions_produce_messages(
+            error_msg in "[a-zA-Z0-9 ]{5,50}"
+        ) {
+            // Capture stderr
+            let mut buffer = Vec::new();
+            {
+                let output = format!("Error: {}\n", error_msg);
+                buffer.write_all(output.as_bytes()).unwrap();
+            }
+
+            let output_str = String::from_utf8(buffer).unwrap();
+
+            // Verify error output contains the error message and "Error:" prefix
+            prop_assert!(output_str.contains("Error:"), "Output should contain 'Error:' prefix");
+            prop_assert!(output_str.contains(&error_msg), "Output should contain error message");
+        }
+    }
+
+    // **Feature: port-killer, Property 7: Success operations produce confirmation messages**
+    // **Validates: Requirements 2.2**
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_success_operations_produce_confirmation(
+            port in 1u16..=65535u16
+        ) {
+            // Capture stdout
+            let mut buffer = Vec::new();
+            {
+                let output = format!("Terminating process...\nPort {} is now free\n", port);
+                buffer.write_all(output.as_bytes()).unwrap();
+            }
+
+            let output_str = String::from_utf8(buffer).unwrap();
+
+            // Verify success output contains confirmation message and port number
+            prop_assert!(output_str.contains("Terminating process"), "Output should contain 'Terminating process'");
+            prop_assert!(output_str.contains("is now free"), "Output should contain 'is now free'");
+            prop_assert!(output_str.contains(&port.to_string()), "Output should contain port number");
+        }
+    }
+
+    // Unit tests for CLI functions
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_port() {
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.port, 8080);
+        assert!(!args.privileged_ok);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_privileged_ok() {
+        let args = parse_arg_list("evict", &to_args(&["--privileged-ok", "443"])).unwrap();
+        assert_eq!(args.port, 443);
+        assert!(args.privileged_ok);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_elevate() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--elevate"])).unwrap();
+        assert!(args.elevate);
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.elevate);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_backend() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--backend", "wmi"])).unwrap();
+        assert_eq!(args.backend, Some(Backend::Wmi));
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.backend, None);
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_bad_backend() {
+        let result = parse_arg_list("evict", &to_args(&["8080", "--backend", "netstat"]));
+        assert!(result.unwrap_err().contains("Invalid backend"));
+
+        let result = parse_arg_list("evict", &to_args(&["8080", "--backend"]));
+        assert!(result.unwrap_err().contains("Missing value"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_exit_code() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--exit-code", "137"])).unwrap();
+        assert_eq!(args.exit_code, 137);
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.exit_code, DEFAULT_EXIT_CODE);
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_bad_exit_code() {
+        let result = parse_arg_list("evict", &to_args(&["8080", "--exit-code", "-5"]));
+        assert!(result.unwrap_err().contains("Invalid exit code"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_signals() {
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.signal, Signal::Kill);
+
+        let args = parse_arg_list("evict", &to_args(&["8080", "--sigterm"])).unwrap();
+        assert_eq!(args.signal, Signal::Term);
+
+        let args = parse_arg_list("evict", &to_args(&["--sigkill", "8080"])).unwrap();
+        assert_eq!(args.signal, Signal::Kill);
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_conflicting_signals() {
+        let result = parse_arg_list("evict", &to_args(&["8080", "--sigterm", "--sigkill"]));
+        assert!(result.unwrap_err().contains("cannot be combined"));
+
+        let result = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--sigterm", "--exit-code", "3"]),
+        );
+        assert!(
+            result
+                .unwrap_err()
+                .contains("only applies to forced termination")
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_kill_job() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--kill-job"])).unwrap();
+        assert!(args.kill_job);
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.kill_job);
+
+        let result = parse_arg_list("evict", &to_args(&["8080", "--kill-job", "--sigterm"]));
+        assert!(result.unwrap_err().contains("--kill-job"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_tree() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--tree"])).unwrap();
+        assert!(args.tree);
+        assert!(!parse_arg_list("evict", &to_args(&["8080"])).unwrap().tree);
+
+        let err = |list: &[&str]| parse_arg_list("evict", &to_args(list)).unwrap_err();
+        assert_eq!(
+            err(&["8080", "--tree", "--kill-job"]),
+            "--tree cannot be combined with --kill-job"
+        );
+        assert_eq!(
+            err(&["8080", "--tree", "--all"]),
+            "--tree cannot be combined with --all"
+        );
+        assert_eq!(
+            err(&["8080", "--tree", "--method", "terminate"]),
+            "--method cannot be combined with --tree"
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_graceful() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--graceful"])).unwrap();
+        assert!(args.graceful);
+        assert_eq!(args.graceful_timeout, GRACEFUL_TIMEOUT);
+
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--graceful", "--graceful-timeout", "20"]),
+        )
+        .unwrap();
+        assert_eq!(args.graceful_timeout, Duration::from_secs(20));
+
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--graceful", "--exit-code", "3"]),
+        )
+        .unwrap();
+        assert_eq!(args.exit_code, 3);
+
+        let err = |list: &[&str]| parse_arg_list("evict", &to_args(list)).unwrap_err();
+        assert_eq!(
+            err(&["8080", "--graceful", "--sigkill"]),
+            "--graceful cannot be combined with --sigkill"
+        );
+        assert_eq!(
+            err(&["8080", "--graceful", "--method", "wm-close"]),
+            "--graceful cannot be combined with --method"
+        );
+        assert!(err(&["8080", "--graceful-timeout", "0"]).contains("Invalid graceful timeout"));
+        assert!(err(&["8080", "--sigkill", "--graceful-timeout", "10"]).contains("only applies"));
+        assert!(
+            parse_arg_list(
+                "evict",
+                &to_args(&["8080", "--method", "ctrl-break", "--graceful-timeout", "10"])
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_group() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--sigterm", "--group"])).unwrap();
+        assert!(args.group);
+
+        let result = parse_arg_list("evict", &to_args(&["8080", "--group"]));
+        assert!(result.unwrap_err().contains("--group only applies"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_method() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--method", "wm-close"])).unwrap();
+        assert_eq!(args.method, Some(Method::WmClose));
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.method, None);
+
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--method", "ctrl-break", "--group"]),
+        )
+        .unwrap();
+        assert!(args.group);
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--method", "terminate", "--exit-code", "7"]),
+        )
+        .unwrap();
+        assert_eq!(args.exit_code, 7);
+
+        let err = parse_arg_list("evict", &to_args(&["8080", "--method", "nuke"])).unwrap_err();
+        assert!(err.starts_with("Invalid method: 'nuke'"));
+        let err =
+            parse_arg_list("evict", &to_args(&["8080", "--method", "socket-close"])).unwrap_err();
+        assert!(err.contains("socket-close is not supported"), "{}", err);
+        let err = parse_arg_list("evict", &to_args(&["8080", "--method"])).unwrap_err();
+        assert_eq!(err, "Missing value for --method");
+    }
+
+    #[test]
+    fn test_parse_arg_list_method_conflicts() {
+        let err = |args: &[&str]| parse_arg_list("evict", &to_args(args)).unwrap_err();
+        assert_eq!(
+            err(&["8080", "--method", "terminate", "--sigkill"]),
+            "--method cannot be combined with --sigkill"
+        );
+        assert_eq!(
+            err(&["8080", "--method", "wm-close", "--app-graceful"]),
+            "--method cannot be combined with --app-graceful"
+        );
+        assert_eq!(
+            err(&["8080", "--method", "wm-close", "--exit-code", "3"]),
+            "--exit-code only applies to --method terminate"
+        );
+        assert_eq!(
+            err(&["8080", "--method", "terminate", "--group"]),
+            "--group only applies to --method ctrl-break"
+        );
+        assert!(
+            err(&["8080", "--method", "connection-reset", "--verify-bind"]).contains("listener")
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_yes() {
+        assert!(
+            parse_arg_list("evict", &to_args(&["8080", "--yes"]))
+                .unwrap()
+                .yes
+        );
+        assert!(
+            parse_arg_list("evict", &to_args(&["-y", "8080"]))
+                .unwrap()
+                .yes
+        );
+        assert!(!parse_arg_list("evict", &to_args(&["8080"])).unwrap().yes);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_dump() {
+        let args = parse_arg_list("evict", &to_args(&["--dump", "8080"])).unwrap();
+        assert!(args.dump);
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.dump);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_app_graceful() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--app-graceful"])).unwrap();
+        assert!(args.app_graceful);
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.app_graceful);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_identify() {
+        let args = parse_arg_list("evict", &to_args(&["--identify", "8080"])).unwrap();
+        assert!(args.identify);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_strict_and_all() {
+        let args = parse_arg_list("evict", &to_args(&["3000"])).unwrap();
+        assert!(!args.strict);
+        assert!(!args.all);
+
+        let args = parse_arg_list("evict", &to_args(&["3000", "--strict", "--all"])).unwrap();
+        assert!(args.strict);
+        assert!(args.all);
+        assert_eq!(args.policy, FailurePolicy::KeepGoing);
+
+        let args = parse_arg_list("evict", &to_args(&["3000", "--all", "--fail-fast"])).unwrap();
+        assert_eq!(args.policy, FailurePolicy::FailFast);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_fail_if_free() {
+        let args = parse_arg_list("evict", &to_args(&["3000"])).unwrap();
+        assert!(!args.fail_if_free);
+
+        let args = parse_arg_list("evict", &to_args(&["--fail-if-free", "3000"])).unwrap();
+        assert!(args.fail_if_free);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_verify_cmd() {
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--verify-cmd", "curl -f localhost:8080/health"]),
+        )
+        .unwrap();
+        assert_eq!(
+            args.verify_cmd.as_deref(),
+            Some("curl -f localhost:8080/health")
+        );
+        assert_eq!(args.verify_timeout, verify::DEFAULT_TIMEOUT);
+
+        let args = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--verify-cmd", "exit 0", "--verify-timeout", "30"]),
+        )
+        .unwrap();
+        assert_eq!(args.verify_timeout, Duration::from_secs(30));
+
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.verify_cmd, None);
+        assert!(!args.verify_bind);
+
+        let args = parse_arg_list("evict", &to_args(&["53", "--udp", "--verify-bind"])).unwrap();
+        assert!(args.verify_bind);
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_bad_verify_options() {
+        let err = parse_arg_list("evict", &to_args(&["8080", "--verify-cmd"])).unwrap_err();
+        assert!(err.contains("Missing value for --verify-cmd"));
+        let err = parse_arg_list("evict", &to_args(&["8080", "--verify-cmd", " "])).unwrap_err();
+        assert!(err.contains("Missing value for --verify-cmd"));
+        let err = parse_arg_list(
+            "evict",
+            &to_args(&["8080", "--verify-cmd", "exit 0", "--verify-timeout", "0"]),
+        )
+        .unwrap_err();
+        assert!(err.contains("Invalid verify timeout"));
+        let err =
+            parse_arg_list("evict", &to_args(&["8080", "--verify-timeout", "30"])).unwrap_err();
+        assert!(err.contains("only applies together with --verify-cmd"));
+    }
+
+    #[test]
+    fn test_rollback_advice() {
+        assert_eq!(
+            rollback_advice("node.exe", 9876, Some("node server.js")),
+            "To roll back, start the previous owner again: node server.js"
+        );
+        assert_eq!(
+            rollback_advice("node.exe", 9876, None),
+            "To roll back, start the previous owner (node.exe, PID 9876) again"
+        );
+    }
+
+    #[test]
+    fn test_parse_name_args() {
+        let command =
+            parse_name_args(&to_args(&["--name", "node", "--yes", "--fail-fast"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::ByName {
+                ref name,
+                yes: true,
+                force: false,
+                privileged_ok: false,
+                json: false,
+                policy: FailurePolicy::FailFast,
+                backend: None
+            } if name == "node.exe"
+        ));
+        let command = parse_name_args(&to_args(&[
+            "--name",
+            "nginx",
+            "--privileged-ok",
+            "--json",
+            "--force",
+        ]))
+        .unwrap();
+        assert!(matches!(
+            command,
+            Command::ByName {
+                yes: false,
+                force: true,
+                privileged_ok: true,
+                json: true,
+                ..
+            }
+        ));
+
+        let command = parse_name_args(&to_args(&["-y", "--name", "java.exe"])).unwrap();
+        assert!(matches!(command, Command::ByName { yes: true, .. }));
+
+        let err = parse_name_args(&to_args(&["--name"])).unwrap_err();
+        assert_eq!(err, "Missing value for --name");
+        let err = parse_name_args(&to_args(&["--name", "node", "3000"])).unwrap_err();
+        assert!(err.contains("cannot be combined with '3000'"));
+        let err = parse_name_args(&to_args(&["--name", "node", "--all"])).unwrap_err();
+        assert_eq!(err, "Unknown option for --name: '--all'");
+    }
+
+    #[test]
+    fn test_parse_watch_args() {
+        let command = parse_watch_args(&to_args(&["--watch", "3000", "--yes"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Watch {
+                port: 3000,
+                yes: true,
+                privileged_ok: false,
+                exit_code: DEFAULT_EXIT_CODE,
+                backend: None
+            }
+        ));
+
+        let command = parse_watch_args(&to_args(&["--watch", "80", "--backend", "wmi"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Watch {
+                backend: Some(Backend::Wmi),
+                ..
+            }
+        ));
+
+        let command = parse_watch_args(&to_args(&[
+            "--exit-code",
+            "7",
+            "--watch",
+            "80",
+            "--privileged-ok",
+        ]))
+        .unwrap();
+        assert!(matches!(
+            command,
+            Command::Watch {
+                port: 80,
+                yes: false,
+                privileged_ok: true,
+                exit_code: 7,
+                backend: None
+            }
+        ));
+
+        let err = parse_watch_args(&to_args(&["--watch"])).unwrap_err();
+        assert_eq!(err, "Missing value for --watch");
+        let err = parse_watch_args(&to_args(&["--watch", "3000", "8080"])).unwrap_err();
+        assert!(err.contains("cannot be combined with '8080'"));
+        let err = parse_watch_args(&to_args(&["--watch", "3000", "--all"])).unwrap_err();
+        assert_eq!(err, "Unknown option for --watch: '--all'");
+        assert!(parse_watch_args(&to_args(&["--watch", "http"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_find_args() {
+        let command = parse_find_args(&to_args(&[
+            "--port",
+            "8080",
+            "--name",
+            "java*",
+            "--user",
+            r"CI\agent",
+            "--state",
+            "listen",
+        ]))
+        .unwrap();
+        let Command::Find { filter, .. } = command else {
+            panic!("expected the find command");
+        };
+        assert_eq!(filter.port, Some(8080));
+        assert_eq!(filter.name.as_deref(), Some("java*"));
+        assert_eq!(filter.user.as_deref(), Some(r"CI\agent"));
+        assert_eq!(filter.state, Some(TcpState::Listen));
+
+        assert!(matches!(
+            parse_find_args(&[]),
+            Ok(Command::Find { filter, backend: None }) if filter == Filter::default()
+        ));
+        let err = parse_find_args(&to_args(&["--state", "open"])).unwrap_err();
+        assert!(err.starts_with("Invalid state: 'open' (expected closed, listen"));
+        let err = parse_find_args(&to_args(&["--port"])).unwrap_err();
+        assert_eq!(err, "Missing value for --port");
+        let err = parse_find_args(&to_args(&["8080"])).unwrap_err();
+        assert_eq!(err, "Unexpected argument for find: '8080'");
+    }
+
+    #[test]
+    fn test_parse_explain_args() {
+        assert!(matches!(
+            parse_explain_args(&to_args(&["8080"])),
+            Ok(Command::Explain {
+                port: 8080,
+                backend: None
+            })
+        ));
+        assert!(parse_explain_args(&[]).is_err());
+        assert!(parse_explain_args(&to_args(&["0"])).is_err());
+        assert!(parse_explain_args(&to_args(&["8080", "--all"])).is_err());
+    }
+
+    #[test]
+    fn test_result_json_of_free_port() {
+        let json = result_json(&JsonResult {
+            port: 8080,
+            protocol: Protocol::Tcp,
+            found: None,
+            action: None,
+            steps: &[],
+            outcome: &Ok(()),
+            checks: &[],
+            dry_run: false,
+        });
+        assert_eq!(
+            json,
+            r#"{"port":8080,"protocol":"TCP","in_use":false,"binding":null,"process_name":null,"action":null,"freed":false,"error":null,"steps":[],"checks":[],"dry_run":false}"#
+        );
+    }
+
+    #[test]
+    fn test_result_json_of_terminated_owner() {
+        let binding = PortBinding {
+            pid: 1234,
+            port: 8080,
+            address: "::".parse().unwrap(),
+            state: Some(TcpState::Listen),
+        };
+        let steps = [
+            StepResult {
+                target: "PID 1234".to_string(),
+                owner: "node.exe".to_string(),
+                action: "kill",
+                outcome: Outcome::Done,
+                duration: Duration::from_millis(2),
+            },
+            StepResult {
+                target: "PID 5678".to_string(),
+                owner: "svc \"x\".exe".to_string(),
+                action: "kill",
+                outcome: Outcome::Failed("Access is denied.".into()),
+                duration: Duration::from_millis(1),
+            },
+        ];
+        let json = result_json(&JsonResult {
+            port: 8080,
+            protocol: Protocol::Tcp,
+            found: Some((&binding, "node.exe")),
+            action: Some("kill"),
+            steps: &steps,
+            outcome: &Err("PID 5678: Access is denied.".into()),
+            checks: &["--verify-bind failed".to_string()],
+            dry_run: false,
+        });
+        assert_eq!(
+            json,
+            r#"{"port":8080,"protocol":"TCP","in_use":true,"binding":{"pid":1234,"port":8080,"address":"::","state":"listen"},"process_name":"node.exe","action":"kill","freed":false,"error":"PID 5678: Access is denied.","steps":[{"target":"PID 1234","owner":"node.exe","action":"kill","outcome":"ok","error":null},{"target":"PID 5678","owner":"svc \"x\".exe","action":"kill","outcome":"failed","error":"Access is denied."}],"checks":["--verify-bind failed"],"dry_run":false}"#
+        );
+    }
+
+    #[test]
+    fn test_batch_json() {
+        assert_eq!(
+            batch_json(0, &[], &Ok(())),
+            r#"{"matched":0,"error":null,"steps":[]}"#
+        );
+        let steps = [StepResult {
+            target: "PID 1234".to_string(),
+            owner: "node.exe".to_string(),
+            action: "graceful",
+            outcome: Outcome::Done,
+            duration: Duration::from_millis(2),
+        }];
+        assert_eq!(
+            batch_json(2, &steps, &Err(not_confirmed("Nothing was terminated"))),
+            r#"{"matched":2,"error":"Nothing was terminated","steps":[{"target":"PID 1234","owner":"node.exe","action":"graceful","outcome":"ok","error":null}]}"#
+        );
+    }
+
+    #[test]
+    fn test_result_json_of_dry_run() {
+        let binding = PortBinding {
+            pid: 1234,
+            port: 3000,
+            address: "127.0.0.1".parse().unwrap(),
+            state: Some(TcpState::Listen),
+        };
+        let steps = [StepResult {
+            target: "PID 1234".to_string(),
+            owner: "node.exe".to_string(),
+            action: "graceful",
+            outcome: Outcome::Skipped,
+            duration: Duration::ZERO,
+        }];
+        let json = result_json(&JsonResult {
+            port: 3000,
+            protocol: Protocol::Tcp,
+            found: Some((&binding, "node.exe")),
+            action: Some("graceful"),
+            steps: &steps,
+            outcome: &Ok(()),
+            checks: &[],
+            dry_run: true,
+        });
+        assert!(json.contains(r#""freed":false"#), "{}", json);
+        assert!(json.contains(r#""outcome":"skipped""#), "{}", json);
+        assert!(json.ends_with(r#""dry_run":true}"#), "{}", json);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_dry_run() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--dry-run", "--all"])).unwrap();
+        assert!(args.dry_run);
+        assert!(
+            !parse_arg_list("evict", &to_args(&["8080"]))
+                .unwrap()
+                .dry_run
+        );
+
+        let err =
+            parse_arg_list("evict", &to_args(&["8080", "--dry-run", "--verify-bind"])).unwrap_err();
+        assert_eq!(
+            err,
+            "--verify-bind needs a terminated process and cannot be combined with --dry-run"
+        );
+        let err = parse_arg_list("evict", &to_args(&["8080", "--dump", "--dry-run"])).unwrap_err();
+        assert!(err.starts_with("--dump"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_json() {
+        assert!(
+            parse_arg_list("evict", &to_args(&["8080", "--json"]))
+                .unwrap()
+                .json
+        );
+        assert!(!parse_arg_list("evict", &to_args(&["8080"])).unwrap().json);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_transcript() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--transcript", "out.md"])).unwrap();
+        assert_eq!(args.transcript, Some(PathBuf::from("out.md")));
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert_eq!(args.transcript, None);
+
+        let err = parse_arg_list("evict", &to_args(&["8080", "--transcript"])).unwrap_err();
+        assert!(err.contains("Missing value for --transcript"));
+        let err =
+            parse_arg_list("evict", &to_args(&["8080", "--transcript", "--all"])).unwrap_err();
+        assert!(err.contains("Missing value for --transcript"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_connection_state() {
+        let args = parse_arg_list("evict", &to_args(&["3000"])).unwrap();
+        assert!(args.listen_only);
+        let args = parse_arg_list("evict", &to_args(&["3000", "--listen-only"])).unwrap();
+        assert!(args.listen_only);
+        let args = parse_arg_list("evict", &to_args(&["3000", "--any-state"])).unwrap();
+        assert!(!args.listen_only);
+
+        let err = parse_arg_list("evict", &to_args(&["3000", "--listen-only", "--any-state"]))
+            .unwrap_err();
+        assert!(err.contains("cannot be combined with --any-state"));
+        let err = parse_arg_list("evict", &to_args(&["53", "--udp", "--any-state"])).unwrap_err();
+        assert!(err.contains("UDP sockets have no state"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_udp() {
+        let args = parse_arg_list("evict", &to_args(&["53", "--udp"])).unwrap();
+        assert_eq!(args.protocol, Protocol::Udp);
+        let args = parse_arg_list("evict", &to_args(&["53"])).unwrap();
+        assert_eq!(args.protocol, Protocol::Tcp);
+
+        let err = parse_arg_list("evict", &to_args(&["53", "--udp", "--identify"])).unwrap_err();
+        assert!(err.contains("cannot be combined with --udp"));
+        let err = parse_arg_list(
+            "evict",
+            &to_args(&["53", "--udp", "--verify-cmd", "exit 0"]),
+        )
+        .unwrap_err();
+        assert!(err.contains("cannot be combined with --udp"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_details() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--details"])).unwrap();
+        assert!(args.details);
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.details);
+    }
+
+    #[test]
+    fn test_display_capabilities_format() {
+        display_capabilities(&[
+            ("query", Capability::Allowed),
+            (
+                "terminate",
+                Capability::Denied("Access is denied (5)".to_string()),
+            ),
+            ("service stop", Capability::NotApplicable),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_arg_list_signal_chosen() {
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.signal_chosen);
+        assert_eq!(args.signal, Signal::Kill);
+        for option in [&["--sigkill"][..], &["--sigterm"], &["--exit-code", "137"]] {
+            let mut list = vec!["8080"];
+            list.extend_from_slice(option);
+            assert!(
+                parse_arg_list("evict", &to_args(&list))
+                    .unwrap()
+                    .signal_chosen
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_force() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--force"])).unwrap();
+        assert!(args.force);
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.force);
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_copy() {
+        let args = parse_arg_list("evict", &to_args(&["8080", "--copy"])).unwrap();
+        assert!(args.copy);
+        let args = parse_arg_list("evict", &to_args(&["8080"])).unwrap();
+        assert!(!args.copy);
+    }
+
+    #[test]
+    fn test_format_report() {
+        let mut report = ResultReport {
+            port: 8080,
+            pid: 9876,
+            name: "node.exe".to_string(),
+            path: Some(r"C:\Program Files\nodejs\node.exe".to_string()),
+            command_line: None,
+            action: "kill",
+            outcome: Ok(()),
+        };
+        let text = format_report(&report);
+        assert!(text.starts_with("Port 8080: freed\r\n  PID: 9876\r\n"));
+        assert!(text.contains(r"Path: C:\Program Files\nodejs\node.exe"));
+        assert!(text.contains("Command line: unknown"));
+
+        report.outcome = Err("Access is denied (5)".to_string());
+        assert!(format_report(&report).starts_with("Port 8080: not freed (Access is denied (5))"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_with_suggest() {
+        let args = parse_arg_list("evict", &to_args(&["3000", "--suggest"])).unwrap();
+        assert!(args.suggest);
+    }
+
+    #[test]
+    fn test_parse_free_port_args() {
+        let command = parse_free_port_args(&to_args(&[])).unwrap();
+        assert!(matches!(command, Command::FreePort { near: None }));
+
+        let command = parse_free_port_args(&to_args(&["--near", "3000"])).unwrap();
+        assert!(matches!(command, Command::FreePort { near: Some(3000) }));
+    }
+
+    #[test]
+    fn test_parse_free_port_args_rejects_bad_input() {
+        let result = parse_free_port_args(&to_args(&["--near", "0"]));
+        assert!(result.unwrap_err().contains("Invalid port"));
+
+        let result = parse_free_port_args(&to_args(&["--near"]));
+        assert!(result.unwrap_err().contains("Missing value"));
+
+        let result = parse_free_port_args(&to_args(&["3000"]));
+        assert!(result.unwrap_err().contains("Unexpected argument"));
+    }
+
+    #[test]
+    fn test_error_json_fields() {
+        let error = Error {
+            kind: ErrorKind::AccessDenied,
+            message: "Failed to open process 42: Access is denied (5)".to_string(),
+            win32_code: Some(5),
+        };
+        let json = error_json(&error, Some("Try running as administrator"));
+        assert_eq!(
+            json,
+            r#"{"error":"Failed to open process 42: Access is denied (5)","code":"access_denied","category":"permission","win32_code":5,"hint":"Try running as administrator"}"#
+        );
+
+        let error = Error::new(ErrorKind::InvalidArgument, "Unknown option: '--bogus'");
+        let json = error_json(&error, None);
+        assert!(json.contains(r#""code":"invalid_argument","category":"usage""#));
+        assert!(json.ends_with(r#""win32_code":null,"hint":null}"#));
+
+        // The kind decides the code, not the wording of the message
+        let json = error_json(&Error::from("Unknown option: '--bogus'"), None);
+        assert!(json.contains(r#""code":"failed","category":"system""#));
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string(r#"a "b" \ c"#), r#""a \"b\" \\ c""#);
+        assert_eq!(json_string("line\nbreak\u{1}"), r#""line\nbreak\u0001""#);
+    }
+
+    #[test]
+    fn test_parse_config_args() {
+        let command = parse_config_args(&to_args(&["validate"])).unwrap();
+        assert!(matches!(command, Command::ConfigValidate));
+
+        let command = parse_config_args(&to_args(&["init", "--force", "-i"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::ConfigInit {
+                force: true,
+                interactive: true
+            }
+        ));
+        assert!(
+            parse_config_args(&to_args(&["init", "--yes"]))
+                .unwrap_err()
+                .contains("Unexpected argument")
+        );
+
+        assert!(
+            parse_config_args(&to_args(&[]))
+                .unwrap_err()
+                .contains("Usage")
+        );
+        assert!(
+            parse_config_args(&to_args(&["validate", "extra"]))
+                .unwrap_err()
+                .contains("Usage")
+        );
+    }
+
+    #[test]
+    fn test_parse_list_command() {
+        assert!(matches!(
+            parse_list_args(&[]),
+            Ok(Command::List { backend: None })
+        ));
+        assert!(matches!(
+            parse_list_args(&to_args(&["--backend", "wmi"])),
+            Ok(Command::List {
+                backend: Some(Backend::Wmi)
+            })
+        ));
+        let err = parse_list_args(&to_args(&["--all"])).unwrap_err();
+        assert_eq!(err, "Unexpected argument for list: '--all'");
+        let err = parse_list_args(&to_args(&["--backend", "netstat"])).unwrap_err();
+        assert!(err.contains("Invalid backend"));
+    }
+
+    #[test]
+    fn test_parse_inventory_args() {
+        let command = parse_inventory_args(&to_args(&[])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Inventory {
+                json: false,
+                details: false,
+                backend: None
+            }
+        ));
+
+        let command = parse_inventory_args(&to_args(&["--output", "json", "--details"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Inventory {
+                json: true,
+                details: true,
+                backend: None
+            }
+        ));
+
+        let result = parse_inventory_args(&to_args(&["--output", "xml"]));
+        assert!(result.unwrap_err().contains("Invalid output format"));
+        let result = parse_inventory_args(&to_args(&["--output"]));
+        assert!(result.unwrap_err().contains("Missing value"));
+    }
+
+    #[test]
+    fn test_inventory_json_layout() {
+        let processes = [
+            InventoryProcess {
+                pid: 9876,
+                name: "node.exe".to_string(),
+                ports: vec![3000, 5173],
+                details: None,
+            },
+            InventoryProcess {
+                pid: 4412,
+                name: "svchost.exe".to_string(),
+                ports: vec![135],
+                details: Some(inventory::ProcessDetails {
+                    path: Some(r"C:\Windows\System32\svchost.exe".to_string()),
+                    user: Some(r"NT AUTHORITY\NETWORK SERVICE".to_string()),
+                    services: vec!["RpcSs".to_string()],
+                    signature: Some("unsigned"),
+                    ..Default::default()
+                }),
+            },
+        ];
+        let json = inventory_json("BUILD01", 1700000000, &processes);
+
+        assert!(json.starts_with(
+            r#"{"schema_version":1,"host":"BUILD01","collected_at":1700000000,"processes":["#
+        ));
+        assert!(json.contains(
+            r#"{"pid":9876,"name":"node.exe","ports":[3000,5173],"path":null,"command_line":null,"user":null,"services":null,"sha256":null,"signature":null}"#
+        ));
+        assert!(json.contains(
+            r#""path":"C:\\Windows\\System32\\svchost.exe","command_line":null,"user":"NT AUTHORITY\\NETWORK SERVICE","services":["RpcSs"],"sha256":null,"signature":"unsigned"}"#
+        ));
+        assert!(json.ends_with("]}"));
+    }
+
+    #[test]
+    fn test_parse_workspace_args() {
+        let command = parse_workspace_args(&to_args(&[])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Workspace {
+                yes: false,
+                force: false,
+                privileged_ok: false,
+                json: false,
+                orphans_only: false,
+                policy: FailurePolicy::KeepGoing,
+                backend: None
+            }
+        ));
+
+        let command = parse_workspace_args(&to_args(&[
+            "--yes",
+            "--orphans-only",
+            "--fail-fast",
+            "--privileged-ok",
+            "--json",
+            "--force",
+        ]))
+        .unwrap();
+        assert!(matches!(
+            command,
+            Command::Workspace {
+                yes: true,
+                force: true,
+                privileged_ok: true,
+                json: true,
+                orphans_only: true,
+                policy: FailurePolicy::FailFast,
+                backend: None
+            }
+        ));
+
+        let result = parse_workspace_args(&to_args(&["3000"]));
+        assert!(result.unwrap_err().contains("Unexpected argument"));
+    }
+
+    #[test]
+    fn test_parse_unix_args() {
+        let command = parse_unix_args(&to_args(&[r"C:\tmp\app.sock", "-y"])).unwrap();
+        match command {
+            Command::Unix {
+                path,
+                yes,
+                force,
+                policy,
+            } => {
+                assert_eq!(path, PathBuf::from(r"C:\tmp\app.sock"));
+                assert!(yes);
+                assert!(!force);
+                assert_eq!(policy, FailurePolicy::KeepGoing);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert!(
+            parse_unix_args(&to_args(&[]))
+                .unwrap_err()
+                .contains("Usage")
+        );
+        assert!(
+            parse_unix_args(&to_args(&["a.sock", "b.sock"]))
+                .unwrap_err()
+                .contains("Unexpected argument")
+        );
+    }
+
+    #[test]
+    fn test_parse_file_args() {
+        let command = parse_file_args(&to_args(&["--restart", "out", "--yes", "--force"])).unwrap();
+        match command {
+            Command::File {
+                path,
+                yes,
+                force,
+                restart,
+                policy,
+            } => {
+                assert_eq!(path, PathBuf::from("out"));
+                assert!(yes);
+                assert!(force);
+                assert!(restart);
+                assert_eq!(policy, FailurePolicy::KeepGoing);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let err = parse_file_args(&to_args(&["--restart"])).unwrap_err();
+        assert!(err.contains("Usage: file <PATH> [--restart]"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_com_args() {
+        let command = parse_com_args(&to_args(&["com3", "--fail-fast"])).unwrap();
+        match command {
+            Command::Com {
+                port,
+                yes,
+                force,
+                policy,
+            } => {
+                assert_eq!(port, "COM3");
+                assert!(!yes);
+                assert!(!force);
+                assert_eq!(policy, FailurePolicy::FailFast);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert!(
+            parse_com_args(&to_args(&["LPT1"]))
+                .unwrap_err()
+                .contains("COM1-COM255")
+        );
+        assert!(
+            parse_com_args(&to_args(&["COM3", "--all"]))
+                .unwrap_err()
+                .contains("Unknown option for com")
+        );
+    }
+
+    #[test]
+    fn test_parse_range_args() {
+        let command = parse_range_args(&to_args(&["8000-9000", "--summary"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Range {
+                start: 8000,
+                end: 9000,
+                summary: true,
+                backend: None
+            }
+        ));
+
+        let command = parse_range_args(&to_args(&["80-80"])).unwrap();
+        assert!(matches!(command, Command::Range { summary: false, .. }));
+    }
+
+    #[test]
+    fn test_parse_range_args_rejects_bad_input() {
+        assert!(
+            parse_range_args(&to_args(&[]))
+                .unwrap_err()
+                .contains("Usage")
+        );
+        assert!(parse_range_args(&to_args(&["9000-8000"])).is_err());
+        assert!(
+            parse_range_args(&to_args(&["1-2", "3-4"]))
+                .unwrap_err()
+                .contains("Unexpected argument")
+        );
+        assert!(
+            parse_range_args(&to_args(&["1-2", "--all"]))
+                .unwrap_err()
+                .contains("Unknown option")
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_unknown_option() {
+        let result = parse_arg_list("evict", &to_args(&["8080", "--bogus"]));
+        assert!(result.unwrap_err().contains("Unknown option"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_rejects_extra_argument() {
+        let result = parse_arg_list("evict", &to_args(&["8080", "3000"]));
+        assert!(result.unwrap_err().contains("Unexpected argument"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_accepts_copied_port() {
+        let args = parse_arg_list("evict", &to_args(&[":3000"])).unwrap();
+        assert_eq!(args.port, 3000);
+
+        let result = parse_arg_list("evict", &to_args(&["8,080"]));
+        assert_eq!(
+            result.unwrap_err(),
+            "Invalid port: '8,080' uses digit separators; write 8080"
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_list_negative_port_is_invalid_number() {
+        let result = parse_arg_list("evict", &to_args(&["-1"]));
+        assert!(result.unwrap_err().contains("not a valid number"));
+    }
+
+    #[test]
+    fn test_parse_arg_list_without_port_shows_usage() {
+        let result = parse_arg_list("evict", &[]);
+        assert!(result.unwrap_err().contains("Usage:"));
+    }
+
+    #[test]
+    fn test_parse_args_with_valid_port() {
+        // Note: This test would need to mock env::args() which is difficult in Rust
+        // In practice, this would be tested through integration tests
+        // For now, we'll test the validation logic indirectly
+        let port_str = "8080";
+        let port: Result<u16, _> = port_str.parse();
+        assert!(port.is_ok());
         assert_eq!(port.unwrap(), 8080);
     }
 
@@ -177,10 +3355,68 @@ mod tests {
     fn test_display_process_info_format() {
         // Test that display_process_info produces expected format
         // We can't easily capture stdout in unit tests, but we can verify the function doesn't panic
-        display_process_info(12345, "node.exe");
+        display_process_info(12345, "node.exe", &[]);
+        let socket = PortBinding {
+            pid: 12345,
+            port: 8080,
+            address: "0.0.0.0".parse().unwrap(),
+            state: Some(TcpState::Listen),
+        };
+        display_process_info(12345, "node.exe", &[socket.clone(), socket]);
         // If we reach here without panic, the test passes
     }
 
+    #[test]
+    fn test_format_addresses() {
+        let addresses: Vec<IpAddr> = vec![
+            "0.0.0.0".parse().unwrap(),
+            "::".parse().unwrap(),
+            "::1".parse().unwrap(),
+        ];
+        assert_eq!(format_addresses(&addresses), "0.0.0.0, [::], [::1]");
+        assert_eq!(format_addresses(&[]), "");
+    }
+
+    #[test]
+    fn test_display_candidates_format() {
+        let candidates = [
+            Candidate {
+                pid: 4120,
+                name: "node.exe".to_string(),
+                in_project: false,
+                addresses: Vec::new(),
+            },
+            Candidate {
+                pid: 9876,
+                name: "node.exe".to_string(),
+                in_project: true,
+                addresses: vec!["127.0.0.1".parse().unwrap()],
+            },
+        ];
+        display_candidates(3000, &candidates, 9876);
+        assert_eq!(
+            describe_candidate(&candidates[1]),
+            "PID 9876 node.exe on 127.0.0.1 (current project)"
+        );
+    }
+
+    #[test]
+    fn test_parse_pick() {
+        assert_eq!(parse_pick("2\n", 3, None, false), Ok(Some(vec![1])));
+        assert_eq!(parse_pick("", 3, Some(2), false), Ok(Some(vec![2])));
+        assert_eq!(parse_pick("  ", 3, None, true), Ok(None));
+        assert_eq!(parse_pick("q", 3, Some(0), true), Ok(None));
+        assert_eq!(parse_pick("3, 1 3", 3, None, true), Ok(Some(vec![2, 0])));
+        assert_eq!(parse_pick("A", 3, None, true), Ok(Some(vec![0, 1, 2])));
+
+        assert!(parse_pick("a", 3, None, false).is_err());
+        assert!(parse_pick("1,2", 3, None, false).is_err());
+        let err = parse_pick("4", 3, None, true).unwrap_err();
+        assert!(err.contains("between 1 and 3"));
+        assert!(parse_pick("0", 3, None, true).is_err());
+        assert!(parse_pick("x", 3, None, true).is_err());
+    }
+
     #[test]
     fn test_display_success_format() {
         // Test that display_success produces expected format
@@ -191,7 +3427,7 @@ mod tests {
     #[test]
     fn test_display_error_format() {
         // Test that display_error produces expected format
-        display_error("Test error message");
+        display_error(&Error::from("Test error message"));
         // If we reach here without panic, the test passes
     }
 }
@@ -0,0 +1,54 @@
+// Clipboard module for placing text on the Windows clipboard
+
+use crate::error::Win32Error;
+use windows::Win32::Foundation::{GlobalFree, HANDLE};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock};
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+
+/// Replace the clipboard contents with the given text
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        OpenClipboard(None)
+            .map_err(|e| format!("Failed to open the clipboard: {}", Win32Error::from(e)))?;
+        let result = set_text(&wide);
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Helper function to fill the open clipboard with a NUL-terminated UTF-16 string
+unsafe fn set_text(wide: &[u16]) -> Result<(), String> {
+    unsafe {
+        EmptyClipboard()
+            .map_err(|e| format!("Failed to empty the clipboard: {}", Win32Error::from(e)))?;
+
+        // The clipboard takes ownership of movable global memory only
+        let memory = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(wide)).map_err(|e| {
+            format!(
+                "Failed to allocate clipboard memory: {}",
+                Win32Error::from(e)
+            )
+        })?;
+        let target = GlobalLock(memory) as *mut u16;
+        if target.is_null() {
+            let _ = GlobalFree(Some(memory));
+            return Err("Failed to lock clipboard memory".to_string());
+        }
+        std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+        let _ = GlobalUnlock(memory);
+
+        if let Err(e) = SetClipboardData(u32::from(CF_UNICODETEXT.0), Some(HANDLE(memory.0))) {
+            let _ = GlobalFree(Some(memory));
+            return Err(format!(
+                "Failed to set the clipboard contents: {}",
+                Win32Error::from(e)
+            ));
+        }
+    }
+    Ok(())
+}
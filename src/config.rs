@@ -0,0 +1,788 @@
+// Config module for loading user configuration
+
+use crate::error::{self, Error};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Settings loaded from the configuration file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Ports that require explicit confirmation before their owner is terminated
+    pub production_ports: Vec<u16>,
+    /// Application-level shutdown actions tried by --app-graceful, per executable
+    pub app_shutdown: Vec<AppShutdown>,
+    /// Safety profile bundling confirmations, force requirements and limits
+    pub safety: Safety,
+    /// How owners are terminated when neither --sigterm nor --sigkill is given, per executable
+    pub termination: Vec<TerminationRule>,
+}
+
+/// How to terminate one kind of process by default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminationRule {
+    /// Executable name the rule applies to, e.g. node.exe
+    pub process: String,
+    pub method: Termination,
+}
+
+/// Default termination method for a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// Ask the process to exit first and terminate it forcibly only if it does not
+    Graceful,
+    /// Terminate the process immediately
+    Kill,
+}
+
+impl Termination {
+    /// Name of the method as written in the configuration file
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Termination::Graceful => "graceful",
+            Termination::Kill => "kill",
+        }
+    }
+}
+
+/// How cautious evict is before terminating anything
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Safety {
+    /// Confirm every termination even with --yes, require --force for service
+    /// processes and refuse runs that would terminate more than
+    /// PARANOID_MAX_PROCESSES processes
+    Paranoid,
+    /// Confirm every termination unless --yes is given
+    #[default]
+    Normal,
+    /// Never ask; as if --yes and --privileged-ok were always given
+    Yolo,
+}
+
+/// Most processes one run may terminate under the paranoid profile
+pub const PARANOID_MAX_PROCESSES: usize = 3;
+
+impl Safety {
+    /// Parse a profile name as written in the configuration file
+    pub fn from_name(name: &str) -> Option<Safety> {
+        match name {
+            "paranoid" => Some(Safety::Paranoid),
+            "normal" => Some(Safety::Normal),
+            "yolo" => Some(Safety::Yolo),
+            _ => None,
+        }
+    }
+
+    /// Name of the profile as written in the configuration file
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Safety::Paranoid => "paranoid",
+            Safety::Normal => "normal",
+            Safety::Yolo => "yolo",
+        }
+    }
+
+    /// Whether terminating a port's owner is confirmed even when --yes is given
+    pub fn confirms_every_termination(self) -> bool {
+        self == Safety::Paranoid
+    }
+
+    /// Whether prompts are shown at all; yolo answers every one with yes
+    pub fn confirms(self) -> bool {
+        self != Safety::Yolo
+    }
+
+    /// Whether terminating a process that hosts a Windows service needs --force
+    pub fn requires_force_for_services(self) -> bool {
+        self == Safety::Paranoid
+    }
+
+    /// Check that one run may terminate this many processes
+    pub fn check_blast_radius(self, count: usize) -> Result<(), String> {
+        match self {
+            Safety::Paranoid if count > PARANOID_MAX_PROCESSES => Err(format!(
+                "Refusing to terminate {} processes: the paranoid safety profile allows at most {} per run",
+                count, PARANOID_MAX_PROCESSES
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that none of the processes about to be terminated hosts a Windows
+    /// service, unless --force allows it; `hosts` pairs each PID with its services
+    pub fn check_service_hosts(
+        self,
+        hosts: &[(u32, Vec<String>)],
+        force: bool,
+    ) -> Result<(), String> {
+        if !self.requires_force_for_services() || force {
+            return Ok(());
+        }
+        match hosts.iter().find(|(_, services)| !services.is_empty()) {
+            Some((pid, services)) => Err(format!(
+                "PID {} hosts the Windows service {}; the paranoid safety profile requires --force to terminate it",
+                pid,
+                services.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How to ask one kind of application to shut itself down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppShutdown {
+    /// Executable name the action applies to, e.g. java.exe
+    pub process: String,
+    pub action: ShutdownAction,
+}
+
+/// An application-level shutdown request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownAction {
+    /// POST to this path on the port being freed, e.g. /actuator/shutdown
+    HttpPost(String),
+    /// Ctrl+C on the process's console, the Windows counterpart of SIGINT
+    CtrlC,
+}
+
+/// Renders the action the way it is written in the configuration file
+impl fmt::Display for ShutdownAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownAction::HttpPost(path) => write!(f, "{}", path),
+            ShutdownAction::CtrlC => write!(f, "ctrl-c"),
+        }
+    }
+}
+
+/// Built-in shutdown actions, used when the configuration has none for a process
+const DEFAULT_APP_SHUTDOWN: [(&str, &str); 2] =
+    [("java.exe", "/actuator/shutdown"), ("node.exe", "ctrl-c")];
+
+/// Dev servers asked to exit before being terminated, unless the configuration
+/// says otherwise; killing them outright can leave SQLite files and build output
+/// half-written. Vite runs in node.exe and Flask in python.exe
+const DEFAULT_GRACEFUL: [&str; 3] = ["node.exe", "dotnet.exe", "python.exe"];
+
+impl Config {
+    /// Find the shutdown action for an executable name
+    /// Configured actions take precedence over the built-in ones
+    pub fn app_shutdown_for(&self, process_name: &str) -> Option<ShutdownAction> {
+        if let Some(entry) = self
+            .app_shutdown
+            .iter()
+            .find(|entry| entry.process.eq_ignore_ascii_case(process_name))
+        {
+            return Some(entry.action.clone());
+        }
+
+        DEFAULT_APP_SHUTDOWN
+            .iter()
+            .find(|(process, _)| process.eq_ignore_ascii_case(process_name))
+            .and_then(|(_, action)| parse_shutdown_action(action).ok())
+    }
+
+    /// Find how an executable is terminated when no signal is given on the command line
+    /// Configured rules take precedence over the built-in ones
+    pub fn termination_for(&self, process_name: &str) -> Termination {
+        if let Some(rule) = self
+            .termination
+            .iter()
+            .find(|rule| rule.process.eq_ignore_ascii_case(process_name))
+        {
+            return rule.method;
+        }
+
+        if DEFAULT_GRACEFUL
+            .iter()
+            .any(|process| process.eq_ignore_ascii_case(process_name))
+        {
+            Termination::Graceful
+        } else {
+            Termination::Kill
+        }
+    }
+
+    /// List every termination rule in effect, configured ones first
+    /// Each entry says whether it is built in; built-in rules overridden by
+    /// the configuration are left out
+    pub fn effective_termination(&self) -> Vec<(TerminationRule, bool)> {
+        let configured = self.termination.iter().map(|rule| (rule.clone(), false));
+
+        let built_in = DEFAULT_GRACEFUL
+            .iter()
+            .filter(|process| {
+                !self
+                    .termination
+                    .iter()
+                    .any(|rule| rule.process.eq_ignore_ascii_case(process))
+            })
+            .map(|process| {
+                (
+                    TerminationRule {
+                        process: process.to_string(),
+                        method: Termination::Graceful,
+                    },
+                    true,
+                )
+            });
+
+        configured.chain(built_in).collect()
+    }
+
+    /// List every shutdown action in effect, configured ones first
+    /// Each entry says whether it is built in; built-in actions overridden by
+    /// the configuration are left out
+    pub fn effective_app_shutdown(&self) -> Vec<(AppShutdown, bool)> {
+        let configured = self.app_shutdown.iter().map(|entry| (entry.clone(), false));
+
+        let built_in = DEFAULT_APP_SHUTDOWN
+            .iter()
+            .filter(|(process, _)| {
+                !self
+                    .app_shutdown
+                    .iter()
+                    .any(|entry| entry.process.eq_ignore_ascii_case(process))
+            })
+            .filter_map(|(process, action)| {
+                let action = parse_shutdown_action(action).ok()?;
+                Some((
+                    AppShutdown {
+                        process: process.to_string(),
+                        action,
+                    },
+                    true,
+                ))
+            });
+
+        configured.chain(built_in).collect()
+    }
+}
+
+/// Get the path of the machine-wide configuration file, %ProgramData%\evict\evict.conf
+/// Administrators use it to set defaults, such as a safety profile, for every user
+pub fn machine_config_path() -> Option<PathBuf> {
+    env::var_os("ProgramData").map(|data| PathBuf::from(data).join("evict").join("evict.conf"))
+}
+
+/// Get the path of the configuration file
+/// Uses EVICT_CONFIG if set, otherwise %APPDATA%\evict\evict.conf
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EVICT_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join("evict").join("evict.conf"))
+}
+
+/// Load the machine-wide configuration file, then the user's on top of it
+/// Each key the user's file sets replaces the machine-wide value; missing
+/// files are not an error and leave the defaults in place
+pub fn load() -> Result<Config, Error> {
+    let invalid = |path: &Path, e: String| {
+        Error::new(
+            error::ErrorKind::InvalidConfig,
+            format!("{}: {}", path.display(), e),
+        )
+    };
+    let machine = match machine_config_path() {
+        Some(path) => match read(&path)? {
+            Some(contents) => parse(&contents).map_err(|e| invalid(&path, e))?,
+            None => Config::default(),
+        },
+        None => Config::default(),
+    };
+
+    let Some(path) = config_path() else {
+        return Ok(machine);
+    };
+    match read(&path)? {
+        Some(contents) => parse_over(machine, &contents).map_err(|e| invalid(&path, e)),
+        None => Ok(machine),
+    }
+}
+
+/// Read a configuration file, returning None if it doesn't exist
+pub fn read(path: &Path) -> Result<Option<String>, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("Failed to read {}: {}", path.display(), err)),
+    }
+}
+
+/// Render a commented configuration file with the given settings
+/// Settings left empty are written as commented-out examples
+pub fn template(production_ports: &[u16], app_shutdown: &[AppShutdown]) -> String {
+    let mut contents = String::from(
+        "# evict configuration\n\
+         # One 'key = value' per line, '#' starts a comment. Check it with: evict config validate\n\
+         \n\
+         # Ports that always require confirmation before their owner is terminated,\n\
+         # in addition to every port below 1024\n",
+    );
+    if production_ports.is_empty() {
+        contents.push_str("# production_ports = 5432, 8443\n");
+    } else {
+        let ports: Vec<String> = production_ports
+            .iter()
+            .map(|port| port.to_string())
+            .collect();
+        contents.push_str(&format!("production_ports = {}\n", ports.join(", ")));
+    }
+
+    contents.push_str(
+        "\n\
+         # Safety profile: paranoid confirms every termination even with --yes, requires\n\
+         # --force for service processes and terminates at most 3 processes per run;\n\
+         # yolo never asks\n\
+         # safety = normal\n",
+    );
+
+    contents.push_str(
+        "\n\
+         # How an executable is terminated without --sigterm or --sigkill: graceful asks it\n\
+         # to exit first, kill terminates it at once\n\
+         # Built in: node.exe, dotnet.exe and python.exe graceful, everything else kill\n\
+         # termination = java.exe graceful\n",
+    );
+
+    contents.push_str(
+        "\n\
+         # Application-level shutdown for --app-graceful: an HTTP path to POST to, or ctrl-c\n\
+         # Built in: java.exe /actuator/shutdown and node.exe ctrl-c\n",
+    );
+    if app_shutdown.is_empty() {
+        contents.push_str("# app_shutdown = dotnet.exe ctrl-c\n");
+    }
+    for entry in app_shutdown {
+        contents.push_str(&format!(
+            "app_shutdown = {} {}\n",
+            entry.process, entry.action
+        ));
+    }
+
+    contents
+}
+
+/// Write a new configuration file, creating its directory
+/// An existing file is only replaced when overwrite is set
+pub fn write_new(path: &Path, contents: &str, overwrite: bool) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let result = if overwrite {
+        fs::write(path, contents)
+    } else {
+        fs::File::create_new(path).and_then(|mut file| file.write_all(contents.as_bytes()))
+    };
+    result.map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => format!(
+            "{} already exists; pass --force to replace it",
+            path.display()
+        ),
+        _ => format!("Failed to write {}: {}", path.display(), e),
+    })
+}
+
+/// Parse configuration file contents
+///
+/// The format is one `key = value` pair per line, with `#` starting a comment
+/// Returns an error naming the offending line for malformed input
+pub fn parse(contents: &str) -> Result<Config, String> {
+    parse_over(Config::default(), contents)
+}
+
+/// Parse configuration file contents on top of an existing configuration
+fn parse_over(base: Config, contents: &str) -> Result<Config, String> {
+    let (config, errors) = validate_over(base, contents);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(config),
+    }
+}
+
+/// Parse configuration file contents, collecting every error instead of stopping at the first
+/// Returns the configuration built from the valid lines and one message per bad line
+pub fn validate(contents: &str) -> (Config, Vec<String>) {
+    validate_over(Config::default(), contents)
+}
+
+/// Validate configuration file contents on top of an existing configuration
+pub fn validate_over(base: Config, contents: &str) -> (Config, Vec<String>) {
+    let mut config = base;
+    let mut errors = Vec::new();
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Err(err) = apply_line(&mut config, line) {
+            errors.push(format!("line {}: {}", index + 1, err));
+        }
+    }
+
+    (config, errors)
+}
+
+/// Apply one non-empty `key = value` line to the configuration
+fn apply_line(config: &mut Config, line: &str) -> Result<(), String> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| "expected 'key = value'".to_string())?;
+
+    match key.trim() {
+        "production_ports" => config.production_ports = parse_port_list(value)?,
+        "app_shutdown" => config.app_shutdown.push(parse_app_shutdown(value)?),
+        "termination" => config.termination.push(parse_termination(value)?),
+        "safety" => {
+            config.safety = Safety::from_name(value.trim()).ok_or_else(|| {
+                format!(
+                    "invalid safety profile '{}' (expected paranoid, normal or yolo)",
+                    value.trim()
+                )
+            })?
+        }
+        other => return Err(format!("unknown key '{}'", other)),
+    }
+    Ok(())
+}
+
+/// Parse a comma-separated list of ports
+pub fn parse_port_list(value: &str) -> Result<Vec<u16>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.parse::<u16>() {
+            Ok(port) if port != 0 => Ok(port),
+            _ => Err(format!("invalid port '{}'", item)),
+        })
+        .collect()
+}
+
+/// Parse an `<executable> <action>` shutdown entry
+pub fn parse_app_shutdown(value: &str) -> Result<AppShutdown, String> {
+    let (process, action) = value
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| "expected '<executable> <action>'".to_string())?;
+
+    Ok(AppShutdown {
+        process: process.to_string(),
+        action: parse_shutdown_action(action.trim())?,
+    })
+}
+
+/// Parse an `<executable> graceful|kill` termination rule
+fn parse_termination(value: &str) -> Result<TerminationRule, String> {
+    let (process, method) = value
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| "expected '<executable> graceful|kill'".to_string())?;
+
+    let method = match method.trim() {
+        "graceful" => Termination::Graceful,
+        "kill" => Termination::Kill,
+        other => {
+            return Err(format!(
+                "invalid termination '{}' (expected graceful or kill)",
+                other
+            ));
+        }
+    };
+    Ok(TerminationRule {
+        process: process.to_string(),
+        method,
+    })
+}
+
+/// Parse a shutdown action: `ctrl-c`, or an HTTP path to POST to
+fn parse_shutdown_action(action: &str) -> Result<ShutdownAction, String> {
+    match action {
+        "ctrl-c" => Ok(ShutdownAction::CtrlC),
+        path if path.starts_with('/') && !path.contains(char::is_whitespace) => {
+            Ok(ShutdownAction::HttpPost(path.to_string()))
+        }
+        other => Err(format!(
+            "invalid shutdown action '{}' (expected ctrl-c or an HTTP path)",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_file() {
+        let config = parse("").unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_parse_production_ports() {
+        let config = parse("production_ports = 443, 8443,5432").unwrap();
+        assert_eq!(config.production_ports, vec![443, 8443, 5432]);
+    }
+
+    #[test]
+    fn test_parse_ignores_comments_and_blank_lines() {
+        let contents = "# evict configuration\n\nproduction_ports = 8080 # api\n";
+        let config = parse(contents).unwrap();
+        assert_eq!(config.production_ports, vec![8080]);
+    }
+
+    #[test]
+    fn test_parse_unknown_key_reports_line() {
+        let result = parse("\nbogus = 1");
+        assert!(result.unwrap_err().contains("line 2: unknown key 'bogus'"));
+    }
+
+    #[test]
+    fn test_parse_invalid_port_reports_line() {
+        let result = parse("production_ports = 443, 70000");
+        assert!(result.unwrap_err().contains("line 1: invalid port '70000'"));
+    }
+
+    #[test]
+    fn test_parse_app_shutdown() {
+        let contents = "app_shutdown = java.exe /admin/stop\napp_shutdown = dotnet.exe ctrl-c";
+        let config = parse(contents).unwrap();
+        assert_eq!(
+            config.app_shutdown,
+            vec![
+                AppShutdown {
+                    process: "java.exe".to_string(),
+                    action: ShutdownAction::HttpPost("/admin/stop".to_string()),
+                },
+                AppShutdown {
+                    process: "dotnet.exe".to_string(),
+                    action: ShutdownAction::CtrlC,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_app_shutdown_reports_line() {
+        let result = parse("app_shutdown = java.exe shutdown");
+        assert!(
+            result
+                .unwrap_err()
+                .contains("line 1: invalid shutdown action")
+        );
+
+        let result = parse("app_shutdown = java.exe");
+        assert!(result.unwrap_err().contains("line 1: expected"));
+    }
+
+    #[test]
+    fn test_app_shutdown_for_prefers_configured_action() {
+        let config = parse("app_shutdown = JAVA.EXE ctrl-c").unwrap();
+        assert_eq!(
+            config.app_shutdown_for("java.exe"),
+            Some(ShutdownAction::CtrlC)
+        );
+    }
+
+    #[test]
+    fn test_app_shutdown_for_uses_defaults() {
+        let config = Config::default();
+        assert_eq!(
+            config.app_shutdown_for("java.exe"),
+            Some(ShutdownAction::HttpPost("/actuator/shutdown".to_string()))
+        );
+        assert_eq!(
+            config.app_shutdown_for("Node.exe"),
+            Some(ShutdownAction::CtrlC)
+        );
+        assert_eq!(config.app_shutdown_for("python.exe"), None);
+    }
+
+    #[test]
+    fn test_validate_reports_every_bad_line() {
+        let contents = "production_ports = 443\nbogus = 1\n\napp_shutdown = java.exe\n";
+        let (config, errors) = validate(contents);
+        assert_eq!(config.production_ports, vec![443]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 2: unknown key"));
+        assert!(errors[1].starts_with("line 4: expected"));
+    }
+
+    #[test]
+    fn test_effective_app_shutdown_merges_defaults() {
+        let config =
+            parse("app_shutdown = java.exe ctrl-c\napp_shutdown = ruby.exe /stop").unwrap();
+        let effective = config.effective_app_shutdown();
+        let summary: Vec<(String, String, bool)> = effective
+            .iter()
+            .map(|(entry, built_in)| (entry.process.clone(), entry.action.to_string(), *built_in))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("java.exe".to_string(), "ctrl-c".to_string(), false),
+                ("ruby.exe".to_string(), "/stop".to_string(), false),
+                ("node.exe".to_string(), "ctrl-c".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_round_trips() {
+        let app_shutdown = vec![AppShutdown {
+            process: "ruby.exe".to_string(),
+            action: ShutdownAction::HttpPost("/stop".to_string()),
+        }];
+        let config = parse(&template(&[5432, 8443], &app_shutdown)).unwrap();
+        assert_eq!(config.production_ports, vec![5432, 8443]);
+        assert_eq!(config.app_shutdown, app_shutdown);
+
+        assert_eq!(parse(&template(&[], &[])).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_write_new_keeps_existing_file() {
+        let path = env::temp_dir()
+            .join(format!("evict-config-{}", std::process::id()))
+            .join("evict.conf");
+
+        write_new(&path, "production_ports = 1\n", false).unwrap();
+        let result = write_new(&path, "production_ports = 2\n", false);
+        assert!(result.unwrap_err().contains("--force"));
+        assert_eq!(read(&path).unwrap().unwrap(), "production_ports = 1\n");
+
+        write_new(&path, "production_ports = 2\n", true).unwrap();
+        assert_eq!(read(&path).unwrap().unwrap(), "production_ports = 2\n");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_termination_for_uses_rules_and_defaults() {
+        let config = parse("termination = Node.exe kill\ntermination = java.exe graceful").unwrap();
+        assert_eq!(config.termination_for("node.exe"), Termination::Kill);
+        assert_eq!(config.termination_for("JAVA.EXE"), Termination::Graceful);
+        assert_eq!(config.termination_for("dotnet.exe"), Termination::Graceful);
+        assert_eq!(config.termination_for("nginx.exe"), Termination::Kill);
+
+        let summary: Vec<(String, &str, bool)> = config
+            .effective_termination()
+            .iter()
+            .map(|(rule, built_in)| (rule.process.clone(), rule.method.as_str(), *built_in))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Node.exe".to_string(), "kill", false),
+                ("java.exe".to_string(), "graceful", false),
+                ("dotnet.exe".to_string(), "graceful", true),
+                ("python.exe".to_string(), "graceful", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_termination_reports_line() {
+        let result = parse("termination = node.exe gentle");
+        assert!(
+            result
+                .unwrap_err()
+                .contains("line 1: invalid termination 'gentle'")
+        );
+        let result = parse("termination = node.exe");
+        assert!(result.unwrap_err().contains("line 1: expected"));
+    }
+
+    #[test]
+    fn test_parse_safety() {
+        assert_eq!(parse("").unwrap().safety, Safety::Normal);
+        assert_eq!(parse("safety = paranoid").unwrap().safety, Safety::Paranoid);
+        assert_eq!(parse("safety = yolo").unwrap().safety, Safety::Yolo);
+
+        let result = parse("safety = careful");
+        assert!(
+            result
+                .unwrap_err()
+                .contains("line 1: invalid safety profile 'careful'")
+        );
+    }
+
+    #[test]
+    fn test_user_file_overrides_machine_file() {
+        let machine = parse("safety = paranoid\nproduction_ports = 5432").unwrap();
+        let config = parse_over(machine.clone(), "safety = normal").unwrap();
+        assert_eq!(config.safety, Safety::Normal);
+        assert_eq!(config.production_ports, vec![5432]);
+
+        assert_eq!(parse_over(machine.clone(), "").unwrap(), machine);
+    }
+
+    #[test]
+    fn test_safety_profiles() {
+        assert!(Safety::Paranoid.confirms_every_termination());
+        assert!(!Safety::Normal.confirms_every_termination());
+        assert!(Safety::Normal.confirms());
+        assert!(!Safety::Yolo.confirms());
+        assert!(Safety::Paranoid.requires_force_for_services());
+        assert!(!Safety::Yolo.requires_force_for_services());
+
+        assert!(
+            Safety::Paranoid
+                .check_blast_radius(PARANOID_MAX_PROCESSES)
+                .is_ok()
+        );
+        let err = Safety::Paranoid
+            .check_blast_radius(PARANOID_MAX_PROCESSES + 1)
+            .unwrap_err();
+        assert!(err.contains("paranoid safety profile"), "{}", err);
+        assert!(Safety::Normal.check_blast_radius(100).is_ok());
+
+        for safety in [Safety::Paranoid, Safety::Normal, Safety::Yolo] {
+            assert_eq!(Safety::from_name(safety.as_str()), Some(safety));
+        }
+    }
+
+    #[test]
+    fn test_paranoid_refuses_service_hosts() {
+        // What `--name svchost` resolves to: one plain process and one service host
+        let hosts = vec![
+            (4100, Vec::new()),
+            (
+                1234,
+                vec!["Dnscache".to_string(), "LanmanWorkstation".to_string()],
+            ),
+        ];
+
+        let err = Safety::Paranoid
+            .check_service_hosts(&hosts, false)
+            .unwrap_err();
+        assert!(err.contains("PID 1234"), "{}", err);
+        assert!(err.contains("Dnscache, LanmanWorkstation"), "{}", err);
+        assert!(err.contains("--force"), "{}", err);
+
+        assert!(Safety::Paranoid.check_service_hosts(&hosts, true).is_ok());
+        assert!(Safety::Normal.check_service_hosts(&hosts, false).is_ok());
+        assert!(Safety::Yolo.check_service_hosts(&hosts, false).is_ok());
+        assert!(
+            Safety::Paranoid
+                .check_service_hosts(&[(4100, Vec::new())], false)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_missing_equals() {
+        let result = parse("production_ports");
+        assert!(result.unwrap_err().contains("expected 'key = value'"));
+    }
+}
@@ -0,0 +1,229 @@
+// Error module for translating Windows error codes
+
+use std::fmt;
+
+use windows::Win32::Foundation::ERROR_ACCESS_DENIED;
+use windows::Win32::System::Diagnostics::Debug::{
+    FORMAT_MESSAGE_FROM_SYSTEM, FORMAT_MESSAGE_IGNORE_INSERTS, FormatMessageW,
+};
+use windows::core::PWSTR;
+
+/// A Win32 error code together with its system message
+///
+/// Displays as "Access is denied (5)" instead of a bare numeric code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Win32Error {
+    pub code: u32,
+    pub message: String,
+}
+
+impl Win32Error {
+    /// Build an error from a raw Win32 error code, looking up its system message
+    pub fn from_code(code: u32) -> Self {
+        Win32Error {
+            code,
+            message: format_message(code),
+        }
+    }
+}
+
+impl From<windows::core::Error> for Win32Error {
+    fn from(err: windows::core::Error) -> Self {
+        Win32Error::from_code(win32_code(err.code().0))
+    }
+}
+
+impl fmt::Display for Win32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+/// What kind of failure an error is, whatever its message says
+/// Decides the code and category of --json-errors output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The command line is malformed
+    InvalidArgument,
+    /// The configuration file is malformed
+    InvalidConfig,
+    /// A confirmation was declined or could not be asked
+    NotConfirmed,
+    /// The process is protected and cannot be terminated
+    ProtectedProcess,
+    /// The operation needs more privileges than evict has
+    AccessDenied,
+    /// A Win32 call failed for another reason
+    Win32,
+    /// Anything else
+    Failed,
+}
+
+impl ErrorKind {
+    /// Stable code of the kind, as in --json-errors output
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::InvalidArgument => "invalid_argument",
+            ErrorKind::InvalidConfig => "invalid_config",
+            ErrorKind::NotConfirmed => "not_confirmed",
+            ErrorKind::ProtectedProcess => "protected_process",
+            ErrorKind::AccessDenied => "access_denied",
+            ErrorKind::Win32 => "win32_error",
+            ErrorKind::Failed => "failed",
+        }
+    }
+
+    /// Broader category of the kind: usage, config, confirmation, permission or system
+    pub fn category(self) -> &'static str {
+        match self {
+            ErrorKind::InvalidArgument => "usage",
+            ErrorKind::InvalidConfig => "config",
+            ErrorKind::NotConfirmed => "confirmation",
+            ErrorKind::ProtectedProcess | ErrorKind::AccessDenied => "permission",
+            ErrorKind::Win32 | ErrorKind::Failed => "system",
+        }
+    }
+}
+
+/// An error on its way to the user: the message, its kind and the Win32 code behind it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub win32_code: Option<u32>,
+}
+
+impl Error {
+    /// Build an error of the given kind
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            win32_code: None,
+        }
+    }
+
+    /// Build an error from a failed Win32 call, as "<context>: Access is denied (5)"
+    /// ERROR_ACCESS_DENIED makes it an access error, any other code a Win32 error
+    pub fn win32(context: impl fmt::Display, err: Win32Error) -> Self {
+        Error {
+            kind: if err.code == ERROR_ACCESS_DENIED.0 {
+                ErrorKind::AccessDenied
+            } else {
+                ErrorKind::Win32
+            },
+            message: format!("{}: {}", context, err),
+            win32_code: Some(err.code),
+        }
+    }
+
+    /// Put context in front of the message, keeping the kind and code
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        Error {
+            message: format!("{}: {}", context, self.message),
+            ..self
+        }
+    }
+}
+
+/// Messages from code that reports plain strings are failures of no particular kind
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::new(ErrorKind::Failed, message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::new(ErrorKind::Failed, message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Recover the Win32 error code wrapped in an HRESULT
+/// HRESULT_FROM_WIN32 stores Win32 codes as 0x8007xxxx; other values are kept as-is
+fn win32_code(hresult: i32) -> u32 {
+    let value = hresult as u32;
+    if value & 0xFFFF_0000 == 0x8007_0000 {
+        value & 0xFFFF
+    } else {
+        value
+    }
+}
+
+/// Look up the system message for a Win32 error code
+/// Falls back to a generic text when the system has no message for the code
+fn format_message(code: u32) -> String {
+    let mut buffer = [0u16; 512];
+
+    let len = unsafe {
+        FormatMessageW(
+            FORMAT_MESSAGE_FROM_SYSTEM | FORMAT_MESSAGE_IGNORE_INSERTS,
+            None,
+            code,
+            0,
+            PWSTR(buffer.as_mut_ptr()),
+            buffer.len() as u32,
+            None,
+        )
+    };
+
+    let message = String::from_utf16_lossy(buffer.get(..len as usize).unwrap_or(&[]));
+    let message = message.trim_end().trim_end_matches('.');
+
+    if message.is_empty() {
+        "Unknown error".to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_denied_message() {
+        let err = Win32Error::from_code(5);
+        assert_eq!(err.code, 5);
+        assert!(err.to_string().ends_with("(5)"));
+        assert!(!err.message.ends_with('.'));
+    }
+
+    #[test]
+    fn test_unknown_code_has_fallback_message() {
+        let err = Win32Error::from_code(0x0FFF_FFFF);
+        assert_eq!(err.message, "Unknown error");
+    }
+
+    #[test]
+    fn test_win32_code_unwraps_hresult() {
+        assert_eq!(win32_code(0x8007_0005_u32 as i32), 5);
+        assert_eq!(win32_code(0x8000_4005_u32 as i32), 0x8000_4005);
+    }
+
+    #[test]
+    fn test_win32_error_kind() {
+        let denied = Error::win32("Failed to open process 42", Win32Error::from_code(5));
+        assert_eq!(denied.kind, ErrorKind::AccessDenied);
+        assert_eq!(denied.win32_code, Some(5));
+        assert!(denied.message.starts_with("Failed to open process 42: "));
+
+        let invalid = Error::win32("Failed to terminate process", Win32Error::from_code(6));
+        assert_eq!(invalid.kind, ErrorKind::Win32);
+        assert_eq!(invalid.kind.category(), "system");
+    }
+
+    #[test]
+    fn test_from_windows_error() {
+        let err = Win32Error::from(windows::core::Error::from_hresult(windows::core::HRESULT(
+            0x8007_0005_u32 as i32,
+        )));
+        assert_eq!(err.code, 5);
+    }
+}
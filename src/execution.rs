@@ -0,0 +1,144 @@
+// Execution module for running an action against several targets
+
+use crate::error::Error;
+use std::time::{Duration, Instant};
+
+/// What to do with the remaining targets once one fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Stop at the first failure and skip the rest
+    FailFast,
+    /// Try every target and report all failures at the end
+    KeepGoing,
+}
+
+/// One action to run against one target
+pub struct Step<'a> {
+    /// What is acted on, e.g. "PID 9876"
+    pub target: String,
+    /// Name of the process that owns the target
+    pub owner: String,
+    /// Short name of the action, e.g. "kill"
+    pub action: &'static str,
+    pub run: Box<dyn FnOnce() -> Result<(), Error> + 'a>,
+}
+
+/// How one step ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Done,
+    Failed(Error),
+    /// Not attempted because an earlier step failed under FailFast
+    Skipped,
+}
+
+/// The record of one step, for the summary table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepResult {
+    pub target: String,
+    pub owner: String,
+    pub action: &'static str,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+/// Run every step in order under the given failure policy
+pub fn execute(steps: Vec<Step<'_>>, policy: FailurePolicy) -> Vec<StepResult> {
+    let mut results = Vec::with_capacity(steps.len());
+    let mut stopped = false;
+
+    for step in steps {
+        let started = Instant::now();
+        let outcome = if stopped {
+            Outcome::Skipped
+        } else {
+            match (step.run)() {
+                Ok(()) => Outcome::Done,
+                Err(err) => {
+                    stopped = policy == FailurePolicy::FailFast;
+                    Outcome::Failed(err)
+                }
+            }
+        };
+
+        results.push(StepResult {
+            target: step.target,
+            owner: step.owner,
+            action: step.action,
+            outcome,
+            duration: started.elapsed(),
+        });
+    }
+
+    results
+}
+
+/// Get the error of the first failed step, if any step failed or was skipped
+/// This decides the aggregate exit code: any failure makes the whole run fail
+pub fn first_failure(results: &[StepResult]) -> Option<Error> {
+    results.iter().find_map(|result| match &result.outcome {
+        Outcome::Done => None,
+        Outcome::Failed(err) => Some(err.clone().context(&result.target)),
+        Outcome::Skipped => Some(Error::from(format!("{} was skipped", result.target))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(target: &str, result: Result<(), &str>) -> Step<'static> {
+        let result = result.map_err(Error::from);
+        Step {
+            target: target.to_string(),
+            owner: "node.exe".to_string(),
+            action: "kill",
+            run: Box::new(move || result),
+        }
+    }
+
+    #[test]
+    fn test_keep_going_runs_every_step() {
+        let steps = vec![
+            step("PID 1", Err("Access is denied (5)")),
+            step("PID 2", Ok(())),
+        ];
+        let results = execute(steps, FailurePolicy::KeepGoing);
+
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed("Access is denied (5)".into())
+        );
+        assert_eq!(results[1].outcome, Outcome::Done);
+        assert_eq!(
+            first_failure(&results),
+            Some("PID 1: Access is denied (5)".into())
+        );
+    }
+
+    #[test]
+    fn test_fail_fast_skips_remaining_steps() {
+        let steps = vec![
+            step("PID 1", Ok(())),
+            step("PID 2", Err("gone")),
+            step("PID 3", Ok(())),
+        ];
+        let results = execute(steps, FailurePolicy::FailFast);
+
+        let outcomes: Vec<&Outcome> = results.iter().map(|result| &result.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                &Outcome::Done,
+                &Outcome::Failed("gone".into()),
+                &Outcome::Skipped
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_failure_when_all_succeed() {
+        let results = execute(vec![step("PID 1", Ok(()))], FailurePolicy::FailFast);
+        assert_eq!(first_failure(&results), None);
+    }
+}
@@ -0,0 +1,430 @@
+// Explain module for describing why a port is unavailable, without changing anything
+
+use crate::port_sharing;
+use std::process::Command;
+
+/// PID of the System process, which owns the ports of kernel drivers such as http.sys
+const SYSTEM_PID: u32 = 4;
+
+/// Processes that publish Docker container ports on the host
+const DOCKER_PROCESSES: [&str; 4] = [
+    "com.docker.backend.exe",
+    "com.docker.proxy.exe",
+    "docker-proxy.exe",
+    "vpnkit.exe",
+];
+
+/// Process that forwards ports from WSL distributions to the host
+const WSL_RELAY: &str = "wslrelay.exe";
+
+/// Service that implements `netsh interface portproxy`
+const PORT_PROXY_SERVICE: &str = "iphlpsvc";
+
+/// What the detectors found out about one process holding the port
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Owner {
+    pub pid: u32,
+    pub name: String,
+    /// Short names of the services the process hosts
+    pub services: Vec<String>,
+    pub package: Option<String>,
+    pub protection: Option<&'static str>,
+    /// Why terminating the process needs elevation, when it does and evict is not elevated
+    pub elevation: Option<String>,
+    /// Number of processes in the process's job object
+    pub job_members: Option<usize>,
+    /// Reasons the process is likely to come back after termination
+    pub respawn: Vec<String>,
+}
+
+/// Everything known about a port
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Findings {
+    pub port: u16,
+    /// Processes with a TCP listener on the port
+    pub owners: Vec<Owner>,
+    /// Processes with a UDP socket bound to the port, as (PID, name)
+    pub udp_owners: Vec<(u32, String)>,
+    /// Excluded TCP port range that contains the port
+    pub excluded: Option<(u16, u16)>,
+    /// Whether the port is privileged or listed in production_ports
+    pub sensitive: bool,
+    /// TCP rows on the port that are not listeners, such as TIME_WAIT connections
+    pub connections: usize,
+}
+
+/// Explanation of a port's state, ready to print
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// Why the port is or is not available, one sentence per entry
+    pub reasons: Vec<String>,
+    /// What to do about it
+    pub remedy: String,
+}
+
+/// Turn the findings into an explanation
+pub fn explain(findings: &Findings) -> Explanation {
+    let port = findings.port;
+    let mut reasons = Vec::new();
+    let mut remedies = Vec::new();
+
+    for owner in &findings.owners {
+        reasons.push(format!(
+            "PID {} ({}) is listening on TCP port {}.",
+            owner.pid, owner.name, port
+        ));
+        let (owner_reasons, remedy) = explain_owner(owner, port);
+        reasons.extend(owner_reasons);
+        remedies.push(remedy);
+    }
+    if findings.owners.len() > 1 {
+        remedies.push(format!(
+            "Run `evict {} --all` to terminate every listener at once.",
+            port
+        ));
+    }
+
+    if findings.owners.is_empty() {
+        for (pid, name) in &findings.udp_owners {
+            reasons.push(format!(
+                "Nothing listens on TCP port {}, but PID {} ({}) has UDP port {} bound.",
+                port, pid, name, port
+            ));
+        }
+        if !findings.udp_owners.is_empty() {
+            remedies.push(format!(
+                "Run `evict {} --udp` if the UDP socket is in the way.",
+                port
+            ));
+        }
+    }
+
+    if let Some((start, end)) = findings.excluded {
+        reasons.push(format!(
+            "Port {} lies in the excluded range {}-{}, which Windows reserves (often for Hyper-V, WSL or Docker); binding it fails with access denied even though nothing listens on it.",
+            port, start, end
+        ));
+        remedies.push(format!(
+            "Use a port outside the range (`evict free-port --near {}`), or restart the winnat service to release dynamic reservations.",
+            port
+        ));
+    }
+
+    if findings.sensitive && !findings.owners.is_empty() {
+        reasons.push(format!(
+            "Port {} is privileged or listed in production_ports, so evict asks for confirmation before terminating its owner.",
+            port
+        ));
+    }
+
+    if let Some(warning) = port_sharing::lingering_warning(port, findings.connections) {
+        reasons.push(format!("{}.", warning));
+        if remedies.is_empty() {
+            remedies.push(
+                "Wait for the connections to close before starting a server that binds exclusively."
+                    .to_string(),
+            );
+        }
+    }
+
+    if reasons.is_empty() {
+        reasons.push(format!(
+            "Nothing holds port {} and it is not reserved; binding it should succeed.",
+            port
+        ));
+        remedies.push("Nothing to do.".to_string());
+    }
+
+    Explanation {
+        reasons,
+        remedy: remedies.join(" "),
+    }
+}
+
+/// Explain one listener and recommend how to free the port from it
+fn explain_owner(owner: &Owner, port: u16) -> (Vec<String>, String) {
+    let mut reasons = Vec::new();
+
+    if owner.pid == SYSTEM_PID {
+        reasons.push(
+            "The System process owns ports opened by kernel drivers; this is usually http.sys serving a URL registered by IIS, WinRM or another HTTP.sys application."
+                .to_string(),
+        );
+        return (
+            reasons,
+            "Find the registration with `netsh http show servicestate` and stop the service or application that made it; terminating processes cannot free the port."
+                .to_string(),
+        );
+    }
+
+    if owner.name.eq_ignore_ascii_case(WSL_RELAY) {
+        reasons.push(
+            "WSL forwards this port from a server running in a Linux distribution.".to_string(),
+        );
+        return (
+            reasons,
+            "Stop the server inside WSL, or run `wsl --shutdown` to stop every distribution."
+                .to_string(),
+        );
+    }
+
+    if DOCKER_PROCESSES
+        .iter()
+        .any(|docker| owner.name.eq_ignore_ascii_case(docker))
+    {
+        reasons.push("Docker publishes a container port here.".to_string());
+        return (
+            reasons,
+            format!(
+                "Find the container with `docker ps --filter publish={}` and stop it with `docker stop`.",
+                port
+            ),
+        );
+    }
+
+    if owner
+        .services
+        .iter()
+        .any(|service| service.eq_ignore_ascii_case(PORT_PROXY_SERVICE))
+    {
+        reasons.push(
+            "The IP Helper service listens here, which usually means a `netsh interface portproxy` rule forwards the port."
+                .to_string(),
+        );
+        return (
+            reasons,
+            "List the rules with `netsh interface portproxy show all` and delete the one for this port."
+                .to_string(),
+        );
+    }
+
+    if let Some(warning) = port_sharing::shared_service_warning(&owner.services) {
+        reasons.push(format!("{}.", warning));
+        return (
+            reasons,
+            "Stop the service, or remove the WCF application's net.tcp binding.".to_string(),
+        );
+    }
+
+    if let Some(level) = owner.protection {
+        reasons.push(format!(
+            "It is a protected process ({}) and cannot be terminated, not even as administrator.",
+            level
+        ));
+    }
+    if !owner.services.is_empty() {
+        reasons.push(format!(
+            "It hosts the Windows service {}.",
+            owner.services.join(", ")
+        ));
+    }
+    if let Some(package) = &owner.package {
+        reasons.push(format!(
+            "It belongs to the packaged app {}, which evict terminates through the app model.",
+            package
+        ));
+    }
+    if let Some(members) = owner.job_members {
+        reasons.push(format!(
+            "It runs in a job object with {} process(es).",
+            members
+        ));
+    }
+    if let Some(reason) = &owner.elevation {
+        reasons.push(format!(
+            "Terminating it requires administrator privileges: {}.",
+            reason
+        ));
+    }
+    reasons.extend(owner.respawn.iter().map(|warning| format!("{}.", warning)));
+
+    let remedy = if owner.protection.is_some()
+        || (!owner.respawn.is_empty() && !owner.services.is_empty())
+    {
+        match owner.services.first() {
+            Some(service) => format!("Stop the service with `sc stop {}`.", service),
+            None => {
+                "Stop the program through its own controls; it cannot be terminated.".to_string()
+            }
+        }
+    } else if owner.elevation.is_some() {
+        format!("Run `evict {}` from an elevated terminal.", port)
+    } else {
+        format!("Run `evict {}` to terminate it.", port)
+    };
+    (reasons, remedy)
+}
+
+/// Find the excluded TCP port range that contains a port
+/// Reads `netsh interface ipv4 show excludedportrange`, which has no API counterpart
+pub fn excluded_range(port: u16) -> Result<Option<(u16, u16)>, String> {
+    let output = Command::new("netsh")
+        .args([
+            "interface",
+            "ipv4",
+            "show",
+            "excludedportrange",
+            "protocol=tcp",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to run netsh: {}", e))?;
+    if !output.status.success() {
+        return Err("Failed to list the excluded port ranges".to_string());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_excluded_ranges(&text)
+        .into_iter()
+        .find(|&(start, end)| (start..=end).contains(&port)))
+}
+
+/// Parse the ranges from netsh output
+/// Only rows that start with two port numbers count; the headers are localized
+fn parse_excluded_ranges(text: &str) -> Vec<(u16, u16)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let start = fields.next()?.parse().ok()?;
+            let end = fields.next()?.parse().ok()?;
+            Some((start, end))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owner(pid: u32, name: &str) -> Owner {
+        Owner {
+            pid,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_excluded_ranges() {
+        let text = "\nProtocol tcp Port Exclusion Ranges\n\nStart Port    End Port\n----------    --------\n      1080        1179\n     50000       50059     *\n\n* - Administered port exclusions.\n";
+        assert_eq!(
+            parse_excluded_ranges(text),
+            vec![(1080, 1179), (50000, 50059)]
+        );
+        assert!(parse_excluded_ranges("").is_empty());
+    }
+
+    #[test]
+    fn test_explain_free_port() {
+        let explanation = explain(&Findings {
+            port: 3000,
+            ..Default::default()
+        });
+        assert_eq!(explanation.reasons.len(), 1);
+        assert!(explanation.reasons[0].contains("Nothing holds port 3000"));
+        assert_eq!(explanation.remedy, "Nothing to do.");
+    }
+
+    #[test]
+    fn test_explain_excluded_range() {
+        let explanation = explain(&Findings {
+            port: 50010,
+            excluded: Some((50000, 50059)),
+            ..Default::default()
+        });
+        assert!(explanation.reasons[0].contains("excluded range 50000-50059"));
+        assert!(explanation.remedy.contains("free-port --near 50010"));
+    }
+
+    #[test]
+    fn test_explain_plain_process() {
+        let explanation = explain(&Findings {
+            port: 3000,
+            owners: vec![owner(1234, "node.exe")],
+            ..Default::default()
+        });
+        assert_eq!(
+            explanation.reasons,
+            vec!["PID 1234 (node.exe) is listening on TCP port 3000."]
+        );
+        assert_eq!(explanation.remedy, "Run `evict 3000` to terminate it.");
+    }
+
+    #[test]
+    fn test_explain_known_owners() {
+        let remedy = |owner: Owner| {
+            explain(&Findings {
+                port: 80,
+                owners: vec![owner],
+                ..Default::default()
+            })
+            .remedy
+        };
+        assert!(remedy(owner(SYSTEM_PID, "System")).contains("netsh http show servicestate"));
+        assert!(remedy(owner(10, "wslrelay.exe")).contains("wsl --shutdown"));
+        assert!(remedy(owner(11, "com.docker.backend.exe")).contains("publish=80"));
+        let proxy = Owner {
+            services: vec!["iphlpsvc".to_string()],
+            ..owner(12, "svchost.exe")
+        };
+        assert!(remedy(proxy).contains("portproxy"));
+        let sharing = Owner {
+            services: vec!["NetTcpPortSharing".to_string()],
+            ..owner(13, "SMSvcHost.exe")
+        };
+        assert!(remedy(sharing).contains("Stop the service"));
+    }
+
+    #[test]
+    fn test_explain_service_that_restarts() {
+        let service = Owner {
+            services: vec!["W3SVC".to_string()],
+            respawn: vec!["The service restarts automatically".to_string()],
+            elevation: Some("it is a service process".to_string()),
+            ..owner(500, "svchost.exe")
+        };
+        let explanation = explain(&Findings {
+            port: 8080,
+            owners: vec![service],
+            sensitive: true,
+            ..Default::default()
+        });
+        assert_eq!(explanation.remedy, "Stop the service with `sc stop W3SVC`.");
+        assert!(explanation.reasons.iter().any(|r| r.contains("W3SVC")));
+        assert!(
+            explanation
+                .reasons
+                .iter()
+                .any(|r| r.contains("confirmation"))
+        );
+    }
+
+    #[test]
+    fn test_explain_lingering_connections() {
+        let explanation = explain(&Findings {
+            port: 3000,
+            connections: 2,
+            ..Default::default()
+        });
+        assert!(explanation.reasons[0].starts_with("2 connection(s) still use port 3000"));
+        assert!(explanation.remedy.starts_with("Wait for the connections"));
+    }
+
+    #[test]
+    fn test_explain_udp_only_and_shared_port() {
+        let explanation = explain(&Findings {
+            port: 53,
+            udp_owners: vec![(900, "dns.exe".to_string())],
+            ..Default::default()
+        });
+        assert!(explanation.remedy.contains("evict 53 --udp"));
+
+        let explanation = explain(&Findings {
+            port: 3000,
+            owners: vec![owner(1, "a.exe"), owner(2, "b.exe")],
+            udp_owners: vec![(3, "c.exe".to_string())],
+            ..Default::default()
+        });
+        assert!(explanation.remedy.contains("evict 3000 --all"));
+        assert!(!explanation.remedy.contains("--udp"));
+    }
+}
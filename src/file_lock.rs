@@ -0,0 +1,225 @@
+// File lock module for finding the processes that lock files, through the Restart Manager
+
+use crate::error::Win32Error;
+use std::path::{Path, PathBuf};
+use windows::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS, WIN32_ERROR};
+use windows::Win32::System::RestartManager::{
+    CCH_RM_SESSION_KEY, RM_PROCESS_INFO, RmEndSession, RmForceShutdown, RmGetList,
+    RmRegisterResources, RmRestart, RmShutdown, RmStartSession,
+};
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+
+/// Most files registered for a directory; larger trees are refused rather than
+/// making the Restart Manager check them one by one
+pub const MAX_DIRECTORY_FILES: usize = 10_000;
+
+/// How often the list is read again when processes start locking files meanwhile
+const LIST_ATTEMPTS: u32 = 4;
+
+/// A process that has a registered file open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locker {
+    pub pid: u32,
+    /// Display name the Restart Manager knows the application by
+    pub app_name: String,
+    /// Short name of the service, when the process is one
+    pub service: Option<String>,
+    /// Whether the application registered itself to be restarted after a shutdown
+    pub restartable: bool,
+}
+
+/// A Restart Manager session, ended when dropped
+pub struct Session {
+    handle: u32,
+}
+
+impl Session {
+    /// Start a new session
+    pub fn start() -> Result<Self, String> {
+        let mut handle = 0;
+        let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+        let status = unsafe { RmStartSession(&mut handle, None, PWSTR(key.as_mut_ptr())) };
+        check(status, "Failed to start a Restart Manager session")?;
+        Ok(Session { handle })
+    }
+
+    /// Register the files whose lockers are wanted
+    pub fn register(&self, files: &[PathBuf]) -> Result<(), String> {
+        let names: Vec<HSTRING> = files
+            .iter()
+            .map(|file| HSTRING::from(file.as_path()))
+            .collect();
+        let pointers: Vec<PCWSTR> = names.iter().map(|name| PCWSTR(name.as_ptr())).collect();
+        let status = unsafe { RmRegisterResources(self.handle, Some(&pointers), None, None) };
+        check(status, "Failed to register files with the Restart Manager")
+    }
+
+    /// List the processes that have any of the registered files open
+    pub fn lockers(&self) -> Result<Vec<Locker>, String> {
+        let mut capacity = 0u32;
+        for _ in 0..LIST_ATTEMPTS {
+            let mut infos = vec![RM_PROCESS_INFO::default(); capacity as usize];
+            let mut needed = 0u32;
+            let mut count = capacity;
+            let mut reasons = 0u32;
+            let status = unsafe {
+                RmGetList(
+                    self.handle,
+                    &mut needed,
+                    &mut count,
+                    (!infos.is_empty()).then_some(infos.as_mut_ptr()),
+                    &mut reasons,
+                )
+            };
+
+            if status == ERROR_MORE_DATA {
+                // Processes can open the files between calls, so leave some headroom
+                capacity = needed + 4;
+                continue;
+            }
+            check(status, "Failed to list the processes locking the files")?;
+
+            infos.truncate(count as usize);
+            return Ok(infos.iter().map(locker).collect());
+        }
+
+        Err("Failed to list the processes locking the files: the list kept growing".to_string())
+    }
+
+    /// Shut down every locker, then start the ones that registered for restart again
+    /// Lockers that did not register for restart stay shut down
+    pub fn restart(&self) -> Result<(), String> {
+        let status = unsafe { RmShutdown(self.handle, RmForceShutdown.0 as u32, None) };
+        check(
+            status,
+            "Failed to shut down the processes locking the files",
+        )?;
+
+        let status = unsafe { RmRestart(self.handle, None, None) };
+        check(status, "Failed to restart the processes locking the files")
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = RmEndSession(self.handle);
+        }
+    }
+}
+
+/// List the files to register for a path
+///
+/// The Restart Manager only tracks files, so a directory is expanded to
+/// every file below it, up to MAX_DIRECTORY_FILES
+pub fn files_to_register(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    if !metadata.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let entries = std::fs::read_dir(&directory)
+            .map_err(|e| format!("Failed to read '{}': {}", directory.display(), e))?;
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                // Linked directories can loop back on themselves, so they are not followed
+                Ok(kind) if kind.is_dir() => pending.push(entry.path()),
+                Ok(_) => files.push(entry.path()),
+                Err(_) => {}
+            }
+        }
+        if files.len() > MAX_DIRECTORY_FILES {
+            return Err(format!(
+                "'{}' holds more than {} files; point evict at a smaller directory or at the file itself",
+                path.display(),
+                MAX_DIRECTORY_FILES
+            ));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Turn a Restart Manager result into a Result with the given context
+fn check(status: WIN32_ERROR, context: &str) -> Result<(), String> {
+    if status == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(format!("{}: {}", context, Win32Error::from_code(status.0)))
+    }
+}
+
+/// Read one entry of the Restart Manager's process list
+fn locker(info: &RM_PROCESS_INFO) -> Locker {
+    let service = wide_string(&info.strServiceShortName);
+    Locker {
+        pid: info.Process.dwProcessId,
+        app_name: wide_string(&info.strAppName),
+        service: (!service.is_empty()).then_some(service),
+        restartable: info.bRestartable.as_bool(),
+    }
+}
+
+/// Decode a NUL-terminated UTF-16 buffer
+fn wide_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(buffer.get(..len).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_to_register_expands_directory() {
+        let root = std::env::temp_dir().join(format!("evict-lock-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+        std::fs::write(root.join("sub").join("b.txt"), "b").unwrap();
+
+        let mut files = files_to_register(&root).unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![root.join("a.txt"), root.join("sub").join("b.txt")]
+        );
+        assert_eq!(
+            files_to_register(&root.join("a.txt")).unwrap(),
+            vec![root.join("a.txt")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_files_to_register_of_missing_path() {
+        assert!(files_to_register(Path::new(r"C:\does\not\exist\evict")).is_err());
+    }
+
+    #[test]
+    fn test_wide_string_stops_at_nul() {
+        let mut buffer = [0u16; 8];
+        for (slot, c) in buffer.iter_mut().zip("Spooler".encode_utf16()) {
+            *slot = c;
+        }
+        assert_eq!(wide_string(&buffer), "Spooler");
+        assert_eq!(wide_string(&[0; 4]), "");
+    }
+
+    #[test]
+    fn test_lockers_of_own_open_file() {
+        let path = std::env::temp_dir().join(format!("evict-locked-{}.txt", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+
+        let session = Session::start().unwrap();
+        session.register(std::slice::from_ref(&path)).unwrap();
+        let lockers = session.lockers().unwrap();
+        assert!(lockers.iter().any(|l| l.pid == std::process::id()));
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+// Find module for matching TCP sockets against the filters of the find command
+
+use crate::port_service::{PortBinding, TcpState};
+use std::collections::HashMap;
+
+/// Filters of the find command; a filter that is None matches everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub port: Option<u16>,
+    /// Image name pattern with * and ?; "node" also matches node.exe
+    pub name: Option<String>,
+    /// DOMAIN\name account pattern with * and ?; without a domain, any domain matches
+    pub user: Option<String>,
+    pub state: Option<TcpState>,
+}
+
+/// A socket that passed every filter, with the process details the filters used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub binding: PortBinding,
+    pub name: String,
+    pub user: Option<String>,
+}
+
+impl Filter {
+    /// Check the filters that only need the connection table
+    /// Cheap, so it runs before any process is looked up
+    pub fn matches_socket(&self, binding: &PortBinding) -> bool {
+        self.port.is_none_or(|port| binding.port == port)
+            && self.state.is_none_or(|state| binding.state == Some(state))
+    }
+
+    /// Check the filters on the owning process
+    /// A user filter never matches a process whose account cannot be read
+    pub fn matches_process(&self, name: &str, user: Option<&str>) -> bool {
+        let name_matches = self.name.as_deref().is_none_or(|pattern| {
+            glob_match(pattern, name) || glob_match(&format!("{}.exe", pattern), name)
+        });
+        let user_matches = self
+            .user
+            .as_deref()
+            .is_none_or(|pattern| user.is_some_and(|user| user_matches(pattern, user)));
+        name_matches && user_matches
+    }
+}
+
+/// Keep the sockets that pass every filter
+///
+/// This is the query behind find, list, --name and workspace. The socket filters
+/// run first, so only the owners of the remaining sockets are looked up, each
+/// once; `owner` returns a process's name and account. The matches are sorted by
+/// port, address and PID, without duplicates
+pub fn query(
+    sockets: Vec<PortBinding>,
+    filter: &Filter,
+    mut owner: impl FnMut(u32) -> (String, Option<String>),
+) -> Vec<Match> {
+    let mut owners: HashMap<u32, (String, Option<String>)> = HashMap::new();
+    let mut matches: Vec<Match> = sockets
+        .into_iter()
+        .filter(|socket| filter.matches_socket(socket))
+        .filter_map(|socket| {
+            let (name, user) = owners
+                .entry(socket.pid)
+                .or_insert_with(|| owner(socket.pid))
+                .clone();
+            filter
+                .matches_process(&name, user.as_deref())
+                .then_some(Match {
+                    binding: socket,
+                    name,
+                    user,
+                })
+        })
+        .collect();
+    matches.sort_by_key(|found| {
+        (
+            found.binding.port,
+            found.binding.address,
+            found.binding.pid,
+            found.binding.state.map(TcpState::as_str),
+        )
+    });
+    matches.dedup();
+    matches
+}
+
+/// Match an account against a pattern; a pattern without a domain ignores the domain
+fn user_matches(pattern: &str, user: &str) -> bool {
+    if glob_match(pattern, user) {
+        return true;
+    }
+    match user.rsplit_once('\\') {
+        Some((_, name)) if !pattern.contains('\\') => glob_match(pattern, name),
+        _ => false,
+    }
+}
+
+/// Match text against a pattern where * matches any run of characters and ?
+/// one character, ignoring case like Windows file names
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Position after the last * and the text position it is being tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match (pattern.get(p), text.get(t)) {
+            (Some('*'), _) => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            (Some(&expected), Some(&actual)) if expected == '?' || expected == actual => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last * swallow one more character and try again
+                Some((after_star, start)) => {
+                    backtrack = Some((after_star, start + 1));
+                    p = after_star;
+                    t = start + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern
+        .get(p..)
+        .is_some_and(|rest| rest.iter().all(|&c| c == '*'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn socket(port: u16, state: TcpState) -> PortBinding {
+        PortBinding {
+            pid: 100,
+            port,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(state),
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("java*", "java.exe"));
+        assert!(glob_match("JAVA*", "javaw.exe"));
+        assert!(glob_match("*.exe", "node.exe"));
+        assert!(glob_match("no?e.exe", "node.exe"));
+        assert!(glob_match("*a*a*", "banana"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("java*", "node.exe"));
+        assert!(!glob_match("no?e", "node.exe"));
+        assert!(!glob_match("", "x"));
+    }
+
+    #[test]
+    fn test_socket_filters() {
+        let filter = Filter {
+            port: Some(8080),
+            state: Some(TcpState::Listen),
+            ..Default::default()
+        };
+        assert!(filter.matches_socket(&socket(8080, TcpState::Listen)));
+        assert!(!filter.matches_socket(&socket(8080, TcpState::Established)));
+        assert!(!filter.matches_socket(&socket(8081, TcpState::Listen)));
+        assert!(Filter::default().matches_socket(&socket(1, TcpState::TimeWait)));
+    }
+
+    #[test]
+    fn test_query_looks_up_matching_owners_once() {
+        let mut sockets = vec![
+            socket(8080, TcpState::Listen),
+            socket(8080, TcpState::Listen),
+            socket(3000, TcpState::Established),
+            socket(3000, TcpState::Listen),
+        ];
+        sockets.push(PortBinding {
+            pid: 200,
+            ..socket(443, TcpState::Listen)
+        });
+        let filter = Filter {
+            state: Some(TcpState::Listen),
+            name: Some("node".to_string()),
+            ..Default::default()
+        };
+        let mut looked_up = Vec::new();
+        let matches = query(sockets, &filter, |pid| {
+            looked_up.push(pid);
+            match pid {
+                100 => ("node.exe".to_string(), None),
+                _ => ("nginx.exe".to_string(), None),
+            }
+        });
+        assert_eq!(looked_up, vec![100, 200]);
+        let ports: Vec<u16> = matches.iter().map(|found| found.binding.port).collect();
+        assert_eq!(ports, vec![3000, 8080]);
+    }
+
+    #[test]
+    fn test_process_filters() {
+        let filter = Filter {
+            name: Some("node".to_string()),
+            user: Some(r"CI\agent".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches_process("node.exe", Some(r"CI\agent")));
+        assert!(filter.matches_process("Node.EXE", Some(r"ci\AGENT")));
+        assert!(!filter.matches_process("node.exe", Some(r"OTHER\agent")));
+        assert!(!filter.matches_process("node.exe", None));
+        assert!(!filter.matches_process("nodemon.exe", Some(r"CI\agent")));
+
+        let any_domain = Filter {
+            user: Some("agent".to_string()),
+            ..Default::default()
+        };
+        assert!(any_domain.matches_process("x.exe", Some(r"BUILD01\agent")));
+        assert!(Filter::default().matches_process("x.exe", None));
+    }
+}
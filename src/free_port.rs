@@ -0,0 +1,74 @@
+// Free port module for finding ports that nothing listens on
+
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+
+/// How many ports above the requested one are tried when suggesting an alternative
+pub const SEARCH_DISTANCE: u16 = 100;
+
+/// Check whether a port can be listened on
+///
+/// Binds the loopback addresses only, which fails when another socket holds
+/// the port on loopback or on the wildcard address, without the firewall
+/// prompt a wildcard bind can trigger
+pub fn is_port_free(port: u16) -> bool {
+    if TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_err() {
+        return false;
+    }
+
+    // Hosts without IPv6 cannot have an IPv6 listener either
+    match TcpListener::bind((Ipv6Addr::LOCALHOST, port)) {
+        Ok(_) => true,
+        Err(err) => err.kind() == ErrorKind::AddrNotAvailable,
+    }
+}
+
+/// Find the closest free port above the given one
+pub fn find_free_port_near(port: u16) -> Option<u16> {
+    (1..=SEARCH_DISTANCE)
+        .filter_map(|distance| port.checked_add(distance))
+        .find(|&candidate| is_port_free(candidate))
+}
+
+/// Let the system pick any free port
+pub fn any_free_port() -> Result<u16, String> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .map_err(|e| format!("Failed to find a free port: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_port_is_not_free() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!is_port_free(port));
+
+        drop(listener);
+        assert!(is_port_free(port));
+    }
+
+    #[test]
+    fn test_find_free_port_near_skips_busy_port() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let suggestion = find_free_port_near(port.saturating_sub(1)).unwrap();
+        assert!(suggestion > port);
+    }
+
+    #[test]
+    fn test_find_free_port_near_top_of_range() {
+        assert_eq!(find_free_port_near(u16::MAX), None);
+    }
+
+    #[test]
+    fn test_any_free_port() {
+        let port = any_free_port().unwrap();
+        assert_ne!(port, 0);
+    }
+}
@@ -0,0 +1,204 @@
+// Handle module for system-wide handle enumeration
+
+use crate::error::Win32Error;
+use windows::Wdk::System::SystemInformation::{NtQuerySystemInformation, SYSTEM_INFORMATION_CLASS};
+use windows::Win32::Foundation::{
+    CloseHandle, DUPLICATE_HANDLE_OPTIONS, DuplicateHandle, HANDLE, STATUS_INFO_LENGTH_MISMATCH,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcess, PROCESS_DUP_HANDLE};
+
+/// SystemExtendedHandleInformation, which the windows crate does not define
+const SYSTEM_EXTENDED_HANDLE_INFORMATION: SYSTEM_INFORMATION_CLASS = SYSTEM_INFORMATION_CLASS(64);
+
+/// Upper bound for the handle table buffer, to avoid runaway allocations
+const MAX_BUFFER_SIZE: usize = 256 * 1024 * 1024;
+
+/// Layout of SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX
+#[repr(C)]
+struct HandleTableEntry {
+    object: usize,
+    unique_process_id: usize,
+    handle_value: usize,
+    granted_access: u32,
+    creator_back_trace_index: u16,
+    object_type_index: u16,
+    handle_attributes: u32,
+    reserved: u32,
+}
+
+/// An open handle held by some process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemHandle {
+    pub pid: u32,
+    pub value: usize,
+    pub type_index: u16,
+    pub granted_access: u32,
+}
+
+/// List every open handle in the system
+/// Uses NtQuerySystemInformation with SystemExtendedHandleInformation
+pub fn list_system_handles() -> Result<Vec<SystemHandle>, String> {
+    // Use a u64 buffer so the table is suitably aligned
+    let mut buffer: Vec<u64> = vec![0; 1024 * 1024 / 8];
+
+    loop {
+        let byte_len = buffer.len() * 8;
+        let mut needed: u32 = 0;
+        let status = unsafe {
+            NtQuerySystemInformation(
+                SYSTEM_EXTENDED_HANDLE_INFORMATION,
+                buffer.as_mut_ptr() as *mut _,
+                u32::try_from(byte_len).unwrap_or(u32::MAX),
+                &mut needed,
+            )
+        };
+
+        if status == STATUS_INFO_LENGTH_MISMATCH {
+            // Handles come and go between calls, so leave some headroom
+            let next_len = (needed as usize).max(byte_len) * 2;
+            if next_len > MAX_BUFFER_SIZE {
+                return Err("Failed to list system handles: handle table is too large".to_string());
+            }
+            buffer = vec![0; next_len / 8];
+            continue;
+        }
+
+        if status.0 < 0 {
+            return Err(format!(
+                "Failed to list system handles: NTSTATUS {:#010X}",
+                status.0
+            ));
+        }
+
+        return Ok(parse_handle_table(&buffer));
+    }
+}
+
+/// Parse the SYSTEM_HANDLE_INFORMATION_EX structure
+fn parse_handle_table(buffer: &[u64]) -> Vec<SystemHandle> {
+    // The header is NumberOfHandles followed by a reserved field, both pointer sized
+    let header_len = 2 * size_of::<usize>();
+    let count = buffer.first().map(|&n| n as usize).unwrap_or(0);
+    let capacity = (buffer.len() * 8).saturating_sub(header_len) / size_of::<HandleTableEntry>();
+
+    let entries =
+        unsafe { (buffer.as_ptr() as *const u8).add(header_len) as *const HandleTableEntry };
+
+    (0..count.min(capacity))
+        .map(|i| unsafe {
+            let entry = entries.add(i);
+            SystemHandle {
+                pid: (*entry).unique_process_id as u32,
+                value: (*entry).handle_value,
+                type_index: (*entry).object_type_index,
+                granted_access: (*entry).granted_access,
+            }
+        })
+        .collect()
+}
+
+/// Find the object type index of one of our own handles
+/// Lets callers identify handle types (jobs, files, ...) by creating a probe object
+pub fn type_index_of(handle: HANDLE, handles: &[SystemHandle]) -> Option<u16> {
+    let pid = std::process::id();
+    handles
+        .iter()
+        .find(|h| h.pid == pid && h.value == handle.0 as usize)
+        .map(|h| h.type_index)
+}
+
+/// Opens processes for handle duplication, remembering each one
+#[derive(Default)]
+pub struct HandleSource {
+    processes: Vec<(u32, Option<HANDLE>)>,
+}
+
+impl HandleSource {
+    pub fn new() -> Self {
+        HandleSource {
+            processes: Vec::new(),
+        }
+    }
+
+    /// Duplicate a handle owned by another process into evict
+    /// The caller owns the returned handle and must close it
+    pub fn duplicate(&mut self, handle: &SystemHandle, access: u32) -> Result<HANDLE, String> {
+        let owner = self.open_owner(handle.pid)?;
+
+        let mut duplicate = HANDLE::default();
+        unsafe {
+            DuplicateHandle(
+                owner,
+                HANDLE(handle.value as *mut _),
+                GetCurrentProcess(),
+                &mut duplicate,
+                access,
+                false,
+                DUPLICATE_HANDLE_OPTIONS(0),
+            )
+            .map_err(|e| format!("Failed to duplicate handle: {}", Win32Error::from(e)))?;
+        }
+
+        Ok(duplicate)
+    }
+
+    /// Get a duplication handle for a process, opening it on first use
+    fn open_owner(&mut self, pid: u32) -> Result<HANDLE, String> {
+        if let Some((_, owner)) = self.processes.iter().find(|(p, _)| *p == pid) {
+            return owner.ok_or_else(|| format!("Process {} cannot be opened", pid));
+        }
+
+        let owner = unsafe { OpenProcess(PROCESS_DUP_HANDLE, false, pid).ok() };
+        self.processes.push((pid, owner));
+        owner.ok_or_else(|| format!("Process {} cannot be opened", pid))
+    }
+}
+
+impl Drop for HandleSource {
+    fn drop(&mut self) {
+        for (_, owner) in &self.processes {
+            if let Some(owner) = owner {
+                unsafe {
+                    let _ = CloseHandle(*owner);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::System::Threading::CreateEventW;
+    use windows::core::PCWSTR;
+
+    #[test]
+    fn test_list_system_handles_includes_own_handle() {
+        let event = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }.unwrap();
+        let handles = list_system_handles().unwrap();
+
+        assert!(type_index_of(event, &handles).is_some());
+        unsafe {
+            let _ = CloseHandle(event);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_own_handle() {
+        let event = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }.unwrap();
+        let handles = list_system_handles().unwrap();
+        let own = handles
+            .iter()
+            .find(|h| h.pid == std::process::id() && h.value == event.0 as usize)
+            .unwrap();
+
+        let mut source = HandleSource::new();
+        let duplicate = source.duplicate(own, 0x0010_0000).unwrap();
+        assert!(!duplicate.is_invalid());
+
+        unsafe {
+            let _ = CloseHandle(duplicate);
+            let _ = CloseHandle(event);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Image info module for hashing executables and checking their signatures
+
+use crate::error::Win32Error;
+use std::io::Read;
+use std::path::Path;
+use windows::Win32::Foundation::{HWND, TRUST_E_NOSIGNATURE, TRUST_E_SUBJECT_FORM_UNKNOWN};
+use windows::Win32::Security::Cryptography::{
+    BCRYPT_HASH_HANDLE, BCRYPT_SHA256_ALG_HANDLE, BCryptCreateHash, BCryptDestroyHash,
+    BCryptFinishHash, BCryptHashData,
+};
+use windows::Win32::Security::WinTrust::{
+    WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_DATA_0, WINTRUST_FILE_INFO,
+    WTD_CACHE_ONLY_URL_RETRIEVAL, WTD_CHOICE_FILE, WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE,
+    WTD_STATEACTION_VERIFY, WTD_UI_NONE, WinVerifyTrust,
+};
+use windows::core::HSTRING;
+
+/// Size of the chunks an executable is hashed in
+const HASH_CHUNK: usize = 64 * 1024;
+
+/// Outcome of checking an executable's embedded Authenticode signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signature {
+    Valid,
+    /// Signed, but the signature or its chain does not verify
+    Invalid,
+    /// No embedded signature; catalog-signed system files also end up here
+    Unsigned,
+}
+
+impl Signature {
+    /// Stable name used in inventory output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Signature::Valid => "valid",
+            Signature::Invalid => "invalid",
+            Signature::Unsigned => "unsigned",
+        }
+    }
+}
+
+/// Compute the SHA-256 hash of a file as lowercase hex
+pub fn sha256(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+
+    let mut hash = BCRYPT_HASH_HANDLE::default();
+    let status = unsafe { BCryptCreateHash(BCRYPT_SHA256_ALG_HANDLE, &mut hash, None, None, 0) };
+    if status.0 < 0 {
+        return Err(format!(
+            "Failed to start a SHA-256 hash: NTSTATUS {:#010X}",
+            status.0
+        ));
+    }
+
+    let result = hash_file(hash, &mut file, path);
+    unsafe {
+        let _ = BCryptDestroyHash(hash);
+    }
+    result
+}
+
+/// Helper function to feed a file through an open hash and format the digest
+fn hash_file(
+    hash: BCRYPT_HASH_HANDLE,
+    file: &mut std::fs::File,
+    path: &Path,
+) -> Result<String, String> {
+    let mut chunk = vec![0u8; HASH_CHUNK];
+    loop {
+        let len = file
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let Some(data) = chunk.get(..len).filter(|data| !data.is_empty()) else {
+            break;
+        };
+        let status = unsafe { BCryptHashData(hash, data, 0) };
+        if status.0 < 0 {
+            return Err(format!(
+                "Failed to hash '{}': NTSTATUS {:#010X}",
+                path.display(),
+                status.0
+            ));
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    let status = unsafe { BCryptFinishHash(hash, &mut digest, 0) };
+    if status.0 < 0 {
+        return Err(format!(
+            "Failed to hash '{}': NTSTATUS {:#010X}",
+            path.display(),
+            status.0
+        ));
+    }
+    Ok(hex(&digest))
+}
+
+/// Check the embedded Authenticode signature of a file
+/// Revocation is not checked and nothing is downloaded, so the check stays offline
+pub fn signature(path: &Path) -> Result<Signature, String> {
+    let path_string = HSTRING::from(path);
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: windows::core::PCWSTR(path_string.as_ptr()),
+        ..Default::default()
+    };
+    let mut data = WINTRUST_DATA {
+        cbStruct: size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        Anonymous: WINTRUST_DATA_0 {
+            pFile: &mut file_info,
+        },
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        dwProvFlags: WTD_CACHE_ONLY_URL_RETRIEVAL,
+        ..Default::default()
+    };
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+
+    let status = unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut _,
+        )
+    };
+
+    // The verify call keeps state that has to be released with a second call
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut _,
+        );
+    }
+
+    match status {
+        0 => Ok(Signature::Valid),
+        // A missing file must not look like an unsigned one
+        status if !path.exists() => Err(format!(
+            "Failed to check the signature of '{}': {}",
+            path.display(),
+            Win32Error::from_code(status as u32)
+        )),
+        // Files that cannot carry an embedded signature count as unsigned
+        status if status == TRUST_E_NOSIGNATURE.0 || status == TRUST_E_SUBJECT_FORM_UNKNOWN.0 => {
+            Ok(Signature::Unsigned)
+        }
+        _ => Ok(Signature::Invalid),
+    }
+}
+
+/// Format bytes as lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_of_known_content() {
+        let path = std::env::temp_dir().join(format!("evict-hash-{}.txt", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_signature_of_unsigned_file() {
+        let path = std::env::temp_dir().join(format!("evict-sig-{}.txt", std::process::id()));
+        std::fs::write(&path, "not a signed file").unwrap();
+        assert_eq!(signature(&path), Ok(Signature::Unsigned));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_file_fails() {
+        let path = Path::new(r"C:\does\not\exist\evict.exe");
+        assert!(sha256(path).is_err());
+        assert!(signature(path).is_err());
+    }
+}
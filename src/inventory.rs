@@ -0,0 +1,26 @@
+// Inventory module for the machine-wide listing of listening processes
+
+/// Version of the JSON inventory layout, raised only for incompatible changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A process with listening sockets, as reported by the inventory command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryProcess {
+    pub pid: u32,
+    pub name: String,
+    pub ports: Vec<u16>,
+    /// Only collected with --details
+    pub details: Option<ProcessDetails>,
+}
+
+/// What --details adds for each process; fields that cannot be read are None
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessDetails {
+    pub path: Option<String>,
+    pub command_line: Option<String>,
+    pub user: Option<String>,
+    pub services: Vec<String>,
+    pub sha256: Option<String>,
+    /// "valid", "invalid" or "unsigned"
+    pub signature: Option<&'static str>,
+}
@@ -0,0 +1,223 @@
+// Job object module for job membership queries and termination
+
+use crate::error::{Error, Win32Error};
+use crate::handles::{self, HandleSource};
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::JobObjects::{
+    CreateJobObjectW, IsProcessInJob, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOBOBJECT_BASIC_PROCESS_ID_LIST, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JobObjectBasicProcessIdList, JobObjectExtendedLimitInformation, QueryInformationJobObject,
+    TerminateJobObject,
+};
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+use windows::core::{BOOL, PCWSTR};
+
+/// Access right to query a job object (JOB_OBJECT_QUERY)
+const JOB_OBJECT_QUERY: u32 = 0x0004;
+
+/// Access right to terminate a job object (JOB_OBJECT_TERMINATE)
+const JOB_OBJECT_TERMINATE: u32 = 0x0008;
+
+/// Maximum number of member PIDs read from a job
+const MAX_JOB_MEMBERS: usize = 4096;
+
+/// A job object containing a process
+pub struct JobInfo {
+    handle: HANDLE,
+    pub member_pids: Vec<u32>,
+    pub kill_on_close: bool,
+}
+
+impl JobInfo {
+    /// Terminate every process in the job at once
+    pub fn terminate(&self, exit_code: u32) -> Result<(), Error> {
+        unsafe {
+            TerminateJobObject(self.handle, exit_code)
+                .map_err(|e| Error::win32("Failed to terminate job object", Win32Error::from(e)))
+        }
+    }
+}
+
+impl Drop for JobInfo {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Find the job object a process belongs to
+///
+/// Returns Ok(None) when the process is not in a job. Windows has no API to go
+/// from a process to its job, so the system handle table is searched for job
+/// handles that contain the process; when jobs are nested the innermost one
+/// (fewest members) is returned
+pub fn find_job(pid: u32) -> Result<Option<JobInfo>, String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .map_err(|e| format!("Failed to open process {}: {}", pid, Win32Error::from(e)))?;
+
+        let result = find_job_for_handle(pid, process);
+        let _ = CloseHandle(process);
+        result
+    }
+}
+
+/// Helper function to search the job containing an open process
+unsafe fn find_job_for_handle(pid: u32, process: HANDLE) -> Result<Option<JobInfo>, String> {
+    let mut in_job = BOOL::default();
+    unsafe {
+        IsProcessInJob(process, None, &mut in_job).map_err(|e| {
+            format!(
+                "Failed to check job membership of process {}: {}",
+                pid,
+                Win32Error::from(e)
+            )
+        })?;
+    }
+    if !in_job.as_bool() {
+        return Ok(None);
+    }
+
+    // Create a job of our own to learn which handle type index means "job"
+    let probe = unsafe { CreateJobObjectW(None, PCWSTR::null()) }
+        .map_err(|e| format!("Failed to create probe job: {}", Win32Error::from(e)))?;
+    let system_handles = handles::list_system_handles();
+    let job_type = system_handles
+        .as_ref()
+        .ok()
+        .and_then(|list| handles::type_index_of(probe, list));
+    unsafe {
+        let _ = CloseHandle(probe);
+    }
+
+    let system_handles = system_handles?;
+    let job_type = job_type.ok_or_else(|| "Failed to identify job handles".to_string())?;
+
+    let mut source = HandleSource::new();
+    let mut best: Option<JobInfo> = None;
+
+    for candidate in system_handles.iter().filter(|h| h.type_index == job_type) {
+        let Ok(job) = source.duplicate(candidate, JOB_OBJECT_QUERY | JOB_OBJECT_TERMINATE) else {
+            continue;
+        };
+
+        let mut contains = BOOL::default();
+        let is_member = unsafe { IsProcessInJob(process, Some(job), &mut contains) }.is_ok()
+            && contains.as_bool();
+        if !is_member {
+            unsafe {
+                let _ = CloseHandle(job);
+            }
+            continue;
+        }
+
+        let info = JobInfo {
+            handle: job,
+            member_pids: unsafe { query_member_pids(job) },
+            kill_on_close: unsafe { query_kill_on_close(job) },
+        };
+
+        // Prefer the innermost job; dropping the other one closes its handle
+        if best
+            .as_ref()
+            .is_none_or(|current| info.member_pids.len() < current.member_pids.len())
+        {
+            best = Some(info);
+        }
+    }
+
+    best.map(Some).ok_or_else(|| {
+        format!(
+            "Process {} is in a job object, but no accessible handle to the job was found",
+            pid
+        )
+    })
+}
+
+/// Read the PIDs of every process in a job
+unsafe fn query_member_pids(job: HANDLE) -> Vec<u32> {
+    // The list header is two u32 counts followed by a pointer-sized array
+    let mut buffer: Vec<usize> = vec![0; MAX_JOB_MEMBERS + 1];
+    let byte_len = buffer.len() * size_of::<usize>();
+
+    let result = unsafe {
+        QueryInformationJobObject(
+            Some(job),
+            JobObjectBasicProcessIdList,
+            buffer.as_mut_ptr() as *mut _,
+            byte_len as u32,
+            None,
+        )
+    };
+    if result.is_err() {
+        return Vec::new();
+    }
+
+    let list = buffer.as_ptr() as *const JOBOBJECT_BASIC_PROCESS_ID_LIST;
+    unsafe {
+        let count = ((*list).NumberOfProcessIdsInList as usize).min(MAX_JOB_MEMBERS);
+        let ids = (*list).ProcessIdList.as_ptr();
+        (0..count).map(|i| *ids.add(i) as u32).collect()
+    }
+}
+
+/// Check whether closing the last job handle kills every member
+unsafe fn query_kill_on_close(job: HANDLE) -> bool {
+    let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+
+    let result = unsafe {
+        QueryInformationJobObject(
+            Some(job),
+            JobObjectExtendedLimitInformation,
+            &mut info as *mut JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *mut _,
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            None,
+        )
+    };
+
+    result.is_ok()
+        && info.BasicLimitInformation.LimitFlags & JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE
+            == JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::System::JobObjects::AssignProcessToJobObject;
+
+    #[test]
+    fn test_find_job_with_invalid_pid() {
+        assert!(find_job(9999999).is_err());
+    }
+
+    #[test]
+    fn test_find_job_of_child_in_new_job() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "ping", "-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        unsafe {
+            let job = CreateJobObjectW(None, PCWSTR::null()).unwrap();
+            let process = OpenProcess(
+                windows::Win32::System::Threading::PROCESS_ALL_ACCESS,
+                false,
+                child.id(),
+            )
+            .unwrap();
+            AssignProcessToJobObject(job, process).unwrap();
+
+            let info = find_job(child.id()).unwrap().unwrap();
+            assert!(info.member_pids.contains(&child.id()));
+            assert!(!info.kill_on_close);
+
+            info.terminate(1).unwrap();
+            let _ = CloseHandle(process);
+            let _ = CloseHandle(job);
+        }
+
+        let _ = child.wait();
+    }
+}
@@ -0,0 +1,141 @@
+//! Free ports on Windows and Linux by finding and terminating the processes that hold them
+//!
+//! The evict binary is a command line interface over this crate. Three modules
+//! make up the public API for embedding port eviction in other tools:
+//!
+//! - [`port_service`] finds the processes that hold TCP and UDP ports
+//! - [`process_service`] looks processes up and terminates them
+//! - [`validation`] parses and checks port arguments
+//!
+//! Ports and processes are reached through the
+//! [`PortScanner`](port_service::PortScanner) and
+//! [`ProcessManager`](process_service::ProcessManager) traits, so code built
+//! on them can be tested against mock implementations. Windows is served by
+//! `IpHelperScanner` and `Win32ProcessManager`, Linux by `ProcNetScanner`,
+//! which reads /proc/net, and `LinuxProcessManager`, which signals with kill(2).
+//! The free functions of both modules call the backend of the platform built for.
+//!
+//! Failures are reported as `Err(String)` with the same readable message the
+//! command line shows.
+//!
+//! ```no_run
+//! use evict::{port_service, process_service};
+//!
+//! let port = evict::validation::validate_port("8080")?;
+//! if let Some(binding) = port_service::find_process_by_port(port)? {
+//!     let name = process_service::get_process_name(binding.pid)?;
+//!     println!("Terminating {} (PID {})", name, binding.pid);
+//!     process_service::kill_process(binding.pid, process_service::DEFAULT_EXIT_CODE)?;
+//! }
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! The remaining modules implement the command line. They are public so that
+//! the binary can use them, but are left out of the documentation and can
+//! change in any release. Most of them are only built on Windows.
+
+#[cfg(not(any(windows, target_os = "linux")))]
+compile_error!("evict supports Windows and Linux only");
+
+pub mod port_service;
+pub mod process_service;
+pub mod validation;
+
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod app_package;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod app_shutdown;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod certificate;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod cli;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod clipboard;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod config;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod error;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod execution;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod explain;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod file_lock;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod find;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod free_port;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod handles;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod image_info;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod inventory;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod job_object;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod method;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod port_range;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod port_sharing;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod privilege;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod probe;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod process_info;
+#[doc(hidden)]
+pub mod process_tree;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod project;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod respawn;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod serial_port;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod service_control;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod transcript;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod unix_socket;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod verify;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod watch;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod watchdog;
+#[cfg(windows)]
+#[doc(hidden)]
+pub mod wmi;
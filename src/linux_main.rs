@@ -0,0 +1,298 @@
+// Linux command line: frees a port with the /proc and kill(2) backends
+//
+// Only the core of evict is available here; everything else in the Windows
+// command line is built on Windows APIs
+
+use evict::port_service::{PortScanner, ProcNetScanner, Protocol, TcpState};
+use evict::process_service::{
+    DEFAULT_EXIT_CODE, GRACEFUL_TIMEOUT, LinuxProcessManager, ProcessManager,
+};
+use evict::validation;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process;
+
+/// Exit code for a free port under --fail-if-free, the same as on Windows
+const FREE_EXIT_CODE: i32 = 2;
+
+/// Arguments of the Linux command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Args {
+    port: u16,
+    protocol: Protocol,
+    /// Ask the owner to exit with SIGTERM instead of sending SIGKILL
+    sigterm: bool,
+    /// Terminate the owner without asking first
+    yes: bool,
+    /// Skip typing back a privileged port
+    privileged_ok: bool,
+    /// Exit with FREE_EXIT_CODE when nothing holds the port
+    fail_if_free: bool,
+    /// Only a listening TCP socket holds the port, unless --any-state is given
+    listen_only: bool,
+}
+
+/// What has to be confirmed before the owner of the port is terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirmation {
+    Nothing,
+    /// A yes/no question, which --yes answers
+    Kill,
+    /// A privileged port typed back, even with --yes, unless --privileged-ok is given
+    Port,
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", usage());
+        process::exit(1);
+    }
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", usage());
+        return;
+    }
+
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    };
+    match run(&args, &ProcNetScanner, &LinuxProcessManager) {
+        Ok(false) if args.fail_if_free => process::exit(FREE_EXIT_CODE),
+        Ok(_) => {}
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Parse the arguments after the program name
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut port = None;
+    let mut protocol = Protocol::Tcp;
+    let mut sigterm = false;
+    let mut sigkill = false;
+    let mut yes = false;
+    let mut privileged_ok = false;
+    let mut fail_if_free = false;
+    let mut listen_only = true;
+    for arg in args {
+        match arg.as_str() {
+            "--udp" => protocol = Protocol::Udp,
+            "--sigterm" => sigterm = true,
+            "--sigkill" => sigkill = true,
+            "--fail-if-free" => fail_if_free = true,
+            "--listen-only" => listen_only = true,
+            "--any-state" => listen_only = false,
+            "--yes" | "-y" => yes = true,
+            "--privileged-ok" => privileged_ok = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("{} is not supported on Linux", flag));
+            }
+            value if port.is_none() => port = Some(validation::validate_port(value)?),
+            value => return Err(format!("Unexpected argument: {}", value)),
+        }
+    }
+    let port = port.ok_or_else(|| "Missing port number".to_string())?;
+    if sigterm && sigkill {
+        return Err("--sigterm and --sigkill cannot be combined".to_string());
+    }
+    Ok(Args {
+        port,
+        protocol,
+        sigterm,
+        yes,
+        privileged_ok,
+        fail_if_free,
+        listen_only,
+    })
+}
+
+/// Find the owner of the port and terminate it
+/// Returns false when nothing held the port
+fn run(
+    args: &Args,
+    scanner: &dyn PortScanner,
+    processes: &dyn ProcessManager,
+) -> Result<bool, String> {
+    let port = args.port;
+    let Some(binding) = scanner.find_owner(port, args.protocol, args.listen_only)? else {
+        // The lookups skip sockets without a visible owner, which still hold the port
+        let hidden = scanner
+            .list_sockets(args.protocol, args.listen_only)?
+            .iter()
+            .any(|binding| binding.port == port && binding.state != Some(TcpState::TimeWait));
+        if hidden {
+            return Err(format!(
+                "Port {} is held by a process evict may not inspect; run evict as root",
+                port
+            ));
+        }
+        println!("Port {} is not in use", port);
+        return Ok(false);
+    };
+
+    let name = processes
+        .process_name(binding.pid)
+        .unwrap_or_else(|_| "unknown".to_string());
+    println!("Found process using port:");
+    println!("  PID: {}", binding.pid);
+    println!("  Name: {}", name);
+    println!("  Socket: {}", binding.describe());
+    println!();
+
+    match confirmation(args) {
+        Confirmation::Nothing => {}
+        Confirmation::Kill => confirm_kill(&name, binding.pid)?,
+        Confirmation::Port => confirm_privileged_port(port)?,
+    }
+
+    if args.sigterm {
+        processes.terminate_gracefully(binding.pid, GRACEFUL_TIMEOUT, false)?;
+    } else {
+        processes.kill(binding.pid, DEFAULT_EXIT_CODE)?;
+    }
+    println!("Terminating process...");
+    println!("Port {} is now free", port);
+    Ok(true)
+}
+
+/// Decide what to ask before terminating, the way the Windows command line does
+fn confirmation(args: &Args) -> Confirmation {
+    if validation::is_well_known_port(args.port) && !args.privileged_ok {
+        Confirmation::Port
+    } else if args.yes {
+        Confirmation::Nothing
+    } else {
+        Confirmation::Kill
+    }
+}
+
+/// Ask whether to terminate the owner; stdin that is not a terminal is refused
+fn confirm_kill(name: &str, pid: u32) -> Result<(), String> {
+    if !io::stdin().is_terminal() {
+        return Err("Refusing to terminate without confirmation; pass --yes".to_string());
+    }
+    let answer = prompt(&format!("Kill {} (PID {})? [y/N] ", name, pid))?;
+    if matches!(answer.as_str(), "y" | "Y" | "yes") {
+        Ok(())
+    } else {
+        Err("Nothing was terminated".to_string())
+    }
+}
+
+/// Have a privileged port typed back; stdin that is not a terminal is refused
+fn confirm_privileged_port(port: u16) -> Result<(), String> {
+    if !io::stdin().is_terminal() {
+        return Err(format!(
+            "Port {} is a privileged port; pass --privileged-ok to terminate its owner without confirmation",
+            port
+        ));
+    }
+    let answer = prompt(&format!(
+        "Port {} is a privileged port. Type the port number to confirm: ",
+        port
+    ))?;
+    if answer == port.to_string() {
+        println!();
+        Ok(())
+    } else {
+        Err("Confirmation did not match the port number, nothing was terminated".to_string())
+    }
+}
+
+/// Print a question and read the answer from stdin
+fn prompt(question: &str) -> Result<String, String> {
+    print!("{}", question);
+    io::stdout()
+        .flush()
+        .map_err(|e| format!("Failed to write prompt: {}", e))?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Failed to read answer: {}", e))?;
+    Ok(answer.trim().to_string())
+}
+
+fn usage() -> &'static str {
+    "Usage: evict <PORT> [--udp] [--sigterm | --sigkill] [--yes] [--privileged-ok] [--fail-if-free] [--any-state]
+
+Terminate the process that holds a port
+
+Options:
+  --udp            Look the port up among UDP sockets instead of TCP
+  --sigterm        Send SIGTERM and wait for the process to exit instead of SIGKILL
+  --sigkill        Terminate the process immediately with SIGKILL (default)
+  -y, --yes        Terminate the owner without asking first
+  --privileged-ok  Skip typing back a privileged port (below 1024)
+  --fail-if-free   Exit with code 2 when nothing holds the port
+  --listen-only    Only target a process listening on the port (default)
+  --any-state      Also target processes whose connections use the port locally
+  -h, --help       Show this help"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&strings(&[":8080", "--udp", "--sigterm"])),
+            Ok(Args {
+                port: 8080,
+                protocol: Protocol::Udp,
+                sigterm: true,
+                yes: false,
+                privileged_ok: false,
+                fail_if_free: false,
+                listen_only: true,
+            })
+        );
+        assert_eq!(
+            parse_args(&strings(&["3000"])).map(|args| args.protocol),
+            Ok(Protocol::Tcp)
+        );
+        assert_eq!(
+            parse_args(&strings(&["3000", "--any-state"])).map(|args| args.listen_only),
+            Ok(false)
+        );
+        assert!(parse_args(&strings(&["--udp"])).is_err());
+        assert!(parse_args(&strings(&["3000", "3001"])).is_err());
+        let err = parse_args(&strings(&["3000", "--tree"])).unwrap_err();
+        assert!(err.contains("not supported on Linux"));
+    }
+
+    #[test]
+    fn test_parse_signal_flags() {
+        let args = parse_args(&strings(&["3000", "--sigkill", "--yes"])).unwrap();
+        assert!(!args.sigterm);
+        assert!(args.yes);
+        let err = parse_args(&strings(&["3000", "--sigterm", "--sigkill"])).unwrap_err();
+        assert_eq!(err, "--sigterm and --sigkill cannot be combined");
+    }
+
+    #[test]
+    fn test_confirmation() {
+        let args = parse_args(&strings(&["3000"])).unwrap();
+        assert_eq!(confirmation(&args), Confirmation::Kill);
+        let args = parse_args(&strings(&["3000", "-y"])).unwrap();
+        assert_eq!(confirmation(&args), Confirmation::Nothing);
+
+        // A privileged port is typed back even with --yes
+        let args = parse_args(&strings(&["80", "--yes"])).unwrap();
+        assert_eq!(confirmation(&args), Confirmation::Port);
+        let args = parse_args(&strings(&["80", "--privileged-ok"])).unwrap();
+        assert_eq!(confirmation(&args), Confirmation::Kill);
+        let args = parse_args(&strings(&["80", "--privileged-ok", "--yes"])).unwrap();
+        assert_eq!(confirmation(&args), Confirmation::Nothing);
+    }
+}
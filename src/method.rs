@@ -0,0 +1,157 @@
+// Method module for picking exactly one way of freeing a port with --method
+
+use crate::port_service::Protocol;
+
+/// A single termination mechanism, used instead of the automatic ladder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// TerminateProcess
+    Terminate,
+    /// Ctrl+Break to the owner's console
+    CtrlBreak,
+    /// WM_CLOSE to the owner's top-level windows
+    WmClose,
+    /// Stop the Windows service hosted by the owner
+    ServiceStop,
+    /// Reset the owner's TCP connections on the port, leaving the listener
+    ConnectionReset,
+}
+
+impl Method {
+    /// Parse a method name as given to --method
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "terminate" => Some(Method::Terminate),
+            "ctrl-break" => Some(Method::CtrlBreak),
+            "wm-close" => Some(Method::WmClose),
+            "service-stop" => Some(Method::ServiceStop),
+            "connection-reset" => Some(Method::ConnectionReset),
+            _ => None,
+        }
+    }
+
+    /// Name shown in summaries, the same one --method accepts
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Terminate => "terminate",
+            Method::CtrlBreak => "ctrl-break",
+            Method::WmClose => "wm-close",
+            Method::ServiceStop => "service-stop",
+            Method::ConnectionReset => "connection-reset",
+        }
+    }
+}
+
+/// What a method is checked against before anything is done to the owner
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    pub pid: u32,
+    pub protocol: Protocol,
+    /// Short names of the services the owner hosts
+    pub services: &'a [String],
+}
+
+/// What to do to the target, once the method is known to apply to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Plan<'a> {
+    Terminate,
+    CtrlBreak,
+    WmClose,
+    StopService(&'a str),
+    ResetConnections,
+}
+
+/// Check that a method can work on the target and turn it into a plan
+pub fn plan<'a>(method: Method, target: &Target<'a>) -> Result<Plan<'a>, String> {
+    let pid = target.pid;
+    match method {
+        Method::Terminate => Ok(Plan::Terminate),
+        Method::CtrlBreak | Method::WmClose if !target.services.is_empty() => Err(format!(
+            "PID {} hosts the Windows service {} and has no console or window to signal; use --method service-stop",
+            pid,
+            target.services.join(", ")
+        )),
+        Method::CtrlBreak => Ok(Plan::CtrlBreak),
+        Method::WmClose => Ok(Plan::WmClose),
+        Method::ServiceStop => match target.services {
+            [] => Err(format!(
+                "PID {} hosts no Windows service; service-stop only applies to service processes",
+                pid
+            )),
+            [service] => Ok(Plan::StopService(service)),
+            services => Err(format!(
+                "PID {} hosts several services ({}); stop the one holding the port with `sc stop <name>`",
+                pid,
+                services.join(", ")
+            )),
+        },
+        Method::ConnectionReset if target.protocol == Protocol::Udp => {
+            Err("connection-reset only applies to TCP; UDP sockets have no connections".to_string())
+        }
+        Method::ConnectionReset => Ok(Plan::ResetConnections),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(services: &[String]) -> Target<'_> {
+        Target {
+            pid: 42,
+            protocol: Protocol::Tcp,
+            services,
+        }
+    }
+
+    #[test]
+    fn test_method_names_round_trip() {
+        for method in [
+            Method::Terminate,
+            Method::CtrlBreak,
+            Method::WmClose,
+            Method::ServiceStop,
+            Method::ConnectionReset,
+        ] {
+            assert_eq!(Method::from_name(method.as_str()), Some(method));
+        }
+        assert_eq!(Method::from_name("kill"), None);
+        assert_eq!(Method::from_name("socket-close"), None);
+    }
+
+    #[test]
+    fn test_plan_for_plain_process() {
+        assert_eq!(plan(Method::Terminate, &target(&[])), Ok(Plan::Terminate));
+        assert_eq!(plan(Method::WmClose, &target(&[])), Ok(Plan::WmClose));
+        let err = plan(Method::ServiceStop, &target(&[])).unwrap_err();
+        assert!(err.contains("hosts no Windows service"));
+    }
+
+    #[test]
+    fn test_plan_for_service_process() {
+        let one = vec!["W3SVC".to_string()];
+        assert_eq!(
+            plan(Method::ServiceStop, &target(&one)),
+            Ok(Plan::StopService("W3SVC"))
+        );
+        let err = plan(Method::CtrlBreak, &target(&one)).unwrap_err();
+        assert!(err.contains("use --method service-stop"));
+
+        let several = vec!["Dnscache".to_string(), "iphlpsvc".to_string()];
+        let err = plan(Method::ServiceStop, &target(&several)).unwrap_err();
+        assert!(err.contains("several services (Dnscache, iphlpsvc)"));
+    }
+
+    #[test]
+    fn test_plan_connection_reset_needs_tcp() {
+        let udp = Target {
+            protocol: Protocol::Udp,
+            ..target(&[])
+        };
+        assert!(plan(Method::ConnectionReset, &udp).is_err());
+        assert_eq!(
+            plan(Method::ConnectionReset, &target(&[])),
+            Ok(Plan::ResetConnections)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// Port range module for summarizing port usage across a range
+
+use crate::port_service::PortBinding;
+use std::collections::BTreeMap;
+
+/// A run of consecutive used ports with the same owners
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsedRun {
+    pub start: u16,
+    pub end: u16,
+    pub pids: Vec<u32>,
+}
+
+/// Port usage within a range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeUsage {
+    pub start: u16,
+    pub end: u16,
+    /// Owning PIDs of every used port, sorted and without duplicates
+    pub used: BTreeMap<u16, Vec<u32>>,
+}
+
+impl RangeUsage {
+    /// Collect the bindings that fall inside the range
+    pub fn new(start: u16, end: u16, bindings: &[PortBinding]) -> Self {
+        let mut used: BTreeMap<u16, Vec<u32>> = BTreeMap::new();
+        for binding in bindings
+            .iter()
+            .filter(|binding| (start..=end).contains(&binding.port))
+        {
+            let pids = used.entry(binding.port).or_default();
+            if !pids.contains(&binding.pid) {
+                pids.push(binding.pid);
+                pids.sort_unstable();
+            }
+        }
+
+        RangeUsage { start, end, used }
+    }
+
+    /// Number of ports in the range that nothing uses
+    pub fn free_count(&self) -> usize {
+        (usize::from(self.end) - usize::from(self.start) + 1) - self.used.len()
+    }
+
+    /// Merge consecutive used ports with identical owners into runs
+    pub fn runs(&self) -> Vec<UsedRun> {
+        let mut runs: Vec<UsedRun> = Vec::new();
+        for (&port, pids) in &self.used {
+            match runs.last_mut() {
+                Some(run) if run.end.checked_add(1) == Some(port) && run.pids == *pids => {
+                    run.end = port;
+                }
+                _ => runs.push(UsedRun {
+                    start: port,
+                    end: port,
+                    pids: pids.clone(),
+                }),
+            }
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(pid: u32, port: u16) -> PortBinding {
+        PortBinding {
+            pid,
+            port,
+            address: std::net::Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(crate::port_service::TcpState::Listen),
+        }
+    }
+
+    #[test]
+    fn test_range_usage_filters_and_deduplicates() {
+        let bindings = [
+            binding(10, 7999),
+            binding(10, 8000),
+            binding(10, 8000),
+            binding(20, 8000),
+            binding(30, 9001),
+        ];
+        let usage = RangeUsage::new(8000, 9000, &bindings);
+
+        assert_eq!(usage.used.len(), 1);
+        assert_eq!(usage.used.get(&8000), Some(&vec![10, 20]));
+        assert_eq!(usage.free_count(), 1000);
+    }
+
+    #[test]
+    fn test_runs_merge_consecutive_ports_with_same_owner() {
+        let bindings = [
+            binding(10, 8000),
+            binding(10, 8001),
+            binding(10, 8002),
+            binding(20, 8003),
+            binding(10, 8005),
+        ];
+        let usage = RangeUsage::new(8000, 8010, &bindings);
+
+        assert_eq!(
+            usage.runs(),
+            vec![
+                UsedRun {
+                    start: 8000,
+                    end: 8002,
+                    pids: vec![10]
+                },
+                UsedRun {
+                    start: 8003,
+                    end: 8003,
+                    pids: vec![20]
+                },
+                UsedRun {
+                    start: 8005,
+                    end: 8005,
+                    pids: vec![10]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_range_counts() {
+        let usage = RangeUsage::new(1, 65535, &[binding(4, 65535)]);
+        assert_eq!(usage.free_count(), 65534);
+        assert_eq!(usage.runs().len(), 1);
+    }
+}
@@ -1,85 +1,235 @@
-use windows::Win32::NetworkManagement::IpHelper::{
-    GetExtendedTcpTable, MIB_TCPROW_OWNER_PID, MIB_TCPTABLE_OWNER_PID,
-};
-use windows::Win32::Networking::WinSock::AF_INET;
+use std::net::{IpAddr, SocketAddr};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PortBinding {
     pub pid: u32,
     pub port: u16,
+    /// Local address of the socket; a process listening on several addresses
+    /// or on both stacks has one binding per address
+    pub address: IpAddr,
+    /// State of a TCP socket; None for UDP sockets, which have no state
+    pub state: Option<TcpState>,
 }
 
-/// Find the process ID that is using the specified port
-pub fn find_process_by_port(port: u16) -> Result<Option<PortBinding>, String> {
-    unsafe {
-        // First call to get the required buffer size
-        let mut size: u32 = 0;
-        let result = GetExtendedTcpTable(
-            None,
-            &mut size,
-            false,
-            AF_INET.0 as u32,
-            windows::Win32::NetworkManagement::IpHelper::TCP_TABLE_OWNER_PID_ALL,
-            0,
-        );
+impl PortBinding {
+    /// Describe the socket as its state and local endpoint, like "LISTENING on 0.0.0.0:8080"
+    /// UDP sockets have no state and are described as bound
+    pub fn describe(&self) -> String {
+        format!(
+            "{} on {}",
+            self.state.map_or("BOUND", TcpState::netstat_name),
+            SocketAddr::new(self.address, self.port)
+        )
+    }
+}
+
+/// Transport protocol of the port being freed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
 
-        if result != windows::Win32::Foundation::ERROR_INSUFFICIENT_BUFFER.0 {
-            return Err(format!(
-                "Failed to query TCP table size: error code {}",
-                result
-            ));
+impl Protocol {
+    /// Name of the protocol for messages
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
         }
+    }
+}
 
-        // Allocate buffer and make second call to get actual data
-        let mut buffer: Vec<u8> = vec![0; size as usize];
-        let result = GetExtendedTcpTable(
-            Some(buffer.as_mut_ptr() as *mut _),
-            &mut size,
-            false,
-            AF_INET.0 as u32,
-            windows::Win32::NetworkManagement::IpHelper::TCP_TABLE_OWNER_PID_ALL,
-            0,
-        );
+/// State of a TCP socket, as in the connection table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpState {
+    Closed,
+    Listen,
+    SynSent,
+    SynReceived,
+    Established,
+    FinWait1,
+    FinWait2,
+    CloseWait,
+    Closing,
+    LastAck,
+    TimeWait,
+    DeleteTcb,
+}
+
+impl TcpState {
+    /// Every state, in the order of their MIB_TCP_STATE values
+    pub const ALL: [TcpState; 12] = [
+        TcpState::Closed,
+        TcpState::Listen,
+        TcpState::SynSent,
+        TcpState::SynReceived,
+        TcpState::Established,
+        TcpState::FinWait1,
+        TcpState::FinWait2,
+        TcpState::CloseWait,
+        TcpState::Closing,
+        TcpState::LastAck,
+        TcpState::TimeWait,
+        TcpState::DeleteTcb,
+    ];
 
-        if result != 0 {
-            return Err(format!("Failed to get TCP table: error code {}", result));
+    /// Parse a state name as written on the command line
+    pub fn from_name(name: &str) -> Option<TcpState> {
+        Self::ALL
+            .into_iter()
+            .find(|state| state.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Name of the state for output, lowercase like netstat's but hyphenated
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TcpState::Closed => "closed",
+            TcpState::Listen => "listen",
+            TcpState::SynSent => "syn-sent",
+            TcpState::SynReceived => "syn-received",
+            TcpState::Established => "established",
+            TcpState::FinWait1 => "fin-wait-1",
+            TcpState::FinWait2 => "fin-wait-2",
+            TcpState::CloseWait => "close-wait",
+            TcpState::Closing => "closing",
+            TcpState::LastAck => "last-ack",
+            TcpState::TimeWait => "time-wait",
+            TcpState::DeleteTcb => "delete-tcb",
+        }
+    }
+
+    /// Name of the state as netstat shows it, for describing the socket being freed
+    pub fn netstat_name(self) -> &'static str {
+        match self {
+            TcpState::Closed => "CLOSED",
+            TcpState::Listen => "LISTENING",
+            TcpState::SynSent => "SYN_SENT",
+            TcpState::SynReceived => "SYN_RECEIVED",
+            TcpState::Established => "ESTABLISHED",
+            TcpState::FinWait1 => "FIN_WAIT_1",
+            TcpState::FinWait2 => "FIN_WAIT_2",
+            TcpState::CloseWait => "CLOSE_WAIT",
+            TcpState::Closing => "CLOSING",
+            TcpState::LastAck => "LAST_ACK",
+            TcpState::TimeWait => "TIME_WAIT",
+            TcpState::DeleteTcb => "DELETE_TCB",
         }
+    }
+}
 
-        // Parse the TCP table
-        let table = buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID;
-        let num_entries = (*table).dwNumEntries as usize;
+/// A source of TCP connection and UDP endpoint data
+pub trait PortScanner {
+    /// Find the process ID that is using the specified port
+    fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String>;
 
-        // Get pointer to the first entry
-        let entries_ptr = &(*table).table as *const MIB_TCPROW_OWNER_PID;
+    /// List the local port and owner of every TCP socket
+    fn list_bindings(&self) -> Result<Vec<PortBinding>, String>;
 
-        // Search for matching port
-        for i in 0..num_entries {
-            let entry = entries_ptr.add(i);
-            let local_port = u16::from_be((*entry).dwLocalPort as u16);
+    /// List the local port and owner of every listening TCP socket
+    fn list_listeners(&self) -> Result<Vec<PortBinding>, String>;
 
-            if local_port == port {
-                let pid = (*entry).dwOwningPid;
-                return Ok(Some(PortBinding { pid, port }));
-            }
+    /// Find the process ID that has a UDP socket on the specified port
+    fn find_udp_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String>;
+
+    /// List the local port and owner of every UDP socket
+    fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String>;
+
+    /// Find the process that listens on the specified TCP port
+    /// Unlike find_process_by_port, connections that use the port as their
+    /// local port, such as outgoing ones given it as an ephemeral port, are skipped
+    fn find_listener(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        Ok(self
+            .list_listeners()?
+            .into_iter()
+            .find(|binding| binding.port == port))
+    }
+
+    /// Find the owner of a port for the given protocol
+    /// With listen_only, only a listening TCP socket counts; UDP sockets have no state
+    fn find_owner(
+        &self,
+        port: u16,
+        protocol: Protocol,
+        listen_only: bool,
+    ) -> Result<Option<PortBinding>, String> {
+        match protocol {
+            Protocol::Tcp if listen_only => self.find_listener(port),
+            Protocol::Tcp => self.find_process_by_port(port),
+            Protocol::Udp => self.find_udp_process_by_port(port),
         }
+    }
 
-        // Port not found
-        Ok(None)
+    /// List every socket of the given protocol, only the TCP listeners with listen_only
+    fn list_sockets(
+        &self,
+        protocol: Protocol,
+        listen_only: bool,
+    ) -> Result<Vec<PortBinding>, String> {
+        match protocol {
+            Protocol::Tcp if listen_only => self.list_listeners(),
+            Protocol::Tcp => self.list_bindings(),
+            Protocol::Udp => self.list_udp_bindings(),
+        }
     }
 }
 
+#[cfg(windows)]
+mod win32;
+#[cfg(windows)]
+pub use win32::*;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
 
     #[test]
     fn test_port_binding_struct() {
         let binding = PortBinding {
             pid: 1234,
             port: 8080,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(TcpState::Listen),
         };
         assert_eq!(binding.pid, 1234);
         assert_eq!(binding.port, 8080);
+        assert_eq!(binding.address.to_string(), "0.0.0.0");
+    }
+
+    #[test]
+    fn test_tcp_state_names() {
+        for state in TcpState::ALL {
+            assert_eq!(TcpState::from_name(state.as_str()), Some(state));
+        }
+        assert_eq!(TcpState::from_name("LISTEN"), Some(TcpState::Listen));
+        assert_eq!(TcpState::from_name("listening"), None);
+        assert_eq!(TcpState::Listen.netstat_name(), "LISTENING");
+        assert_eq!(TcpState::FinWait1.netstat_name(), "FIN_WAIT_1");
+    }
+
+    #[test]
+    fn test_list_bindings_reports_connection_state() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let client_port = client.local_addr().unwrap().port();
+
+        let bindings = list_bindings().unwrap();
+        let state_of = |port| {
+            bindings
+                .iter()
+                .find(|binding| binding.port == port && binding.pid == std::process::id())
+                .and_then(|binding| binding.state)
+        };
+        assert_eq!(state_of(port), Some(TcpState::Listen));
+        assert_eq!(state_of(client_port), Some(TcpState::Established));
     }
 
     #[test]
@@ -95,14 +245,146 @@ mod tests {
         let result = find_process_by_port(63999);
         assert!(result.is_ok());
         // Most likely this port is free
-        if let Ok(binding_opt) = result {
-            // If it's free, we should get None
-            // If it's occupied, we should get Some with valid data
-            if let Some(binding) = binding_opt {
-                assert!(binding.pid > 0);
-                assert_eq!(binding.port, 63999);
-            }
+        // If it's free, we should get None
+        // If it's occupied, we should get Some with valid data
+        if let Ok(Some(binding)) = result {
+            assert!(binding.pid > 0);
+            assert_eq!(binding.port, 63999);
+        }
+    }
+
+    #[test]
+    fn test_list_bindings_includes_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let bindings = list_bindings().unwrap();
+        assert!(bindings.contains(&PortBinding {
+            pid: std::process::id(),
+            port,
+            address: Ipv4Addr::LOCALHOST.into(),
+            state: Some(TcpState::Listen),
+        }));
+    }
+
+    #[test]
+    fn test_find_process_by_port_finds_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let binding = find_process_by_port(port).unwrap();
+        assert_eq!(
+            binding,
+            Some(PortBinding {
+                pid: std::process::id(),
+                port,
+                address: Ipv4Addr::LOCALHOST.into(),
+                state: Some(TcpState::Listen),
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_listeners_skips_connections() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let client_port = client.local_addr().unwrap().port();
+
+        let listeners = list_listeners().unwrap();
+        let own = PortBinding {
+            pid: std::process::id(),
+            port,
+            address: Ipv4Addr::LOCALHOST.into(),
+            state: Some(TcpState::Listen),
+        };
+        assert!(listeners.contains(&own));
+        assert!(!listeners.iter().any(|binding| binding.port == client_port));
+    }
+
+    /// Table with a client connection on port 3000 and a listener on 8080
+    struct ClientAndListener;
+
+    impl PortScanner for ClientAndListener {
+        fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+            Ok(self
+                .list_bindings()?
+                .into_iter()
+                .find(|binding| binding.port == port))
         }
+
+        fn list_bindings(&self) -> Result<Vec<PortBinding>, String> {
+            let mut bindings = vec![PortBinding {
+                pid: 10,
+                port: 3000,
+                address: Ipv4Addr::LOCALHOST.into(),
+                state: Some(TcpState::Established),
+            }];
+            bindings.extend(self.list_listeners()?);
+            Ok(bindings)
+        }
+
+        fn list_listeners(&self) -> Result<Vec<PortBinding>, String> {
+            Ok(vec![PortBinding {
+                pid: 20,
+                port: 8080,
+                address: Ipv4Addr::UNSPECIFIED.into(),
+                state: Some(TcpState::Listen),
+            }])
+        }
+
+        fn find_udp_process_by_port(&self, _port: u16) -> Result<Option<PortBinding>, String> {
+            Ok(None)
+        }
+
+        fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_describe_binding() {
+        let listener = PortBinding {
+            pid: 12345,
+            port: 8080,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(TcpState::Listen),
+        };
+        assert_eq!(listener.describe(), "LISTENING on 0.0.0.0:8080");
+        let connection = PortBinding {
+            address: Ipv6Addr::LOCALHOST.into(),
+            state: Some(TcpState::Established),
+            ..listener.clone()
+        };
+        assert_eq!(connection.describe(), "ESTABLISHED on [::1]:8080");
+        let udp = PortBinding {
+            port: 53,
+            state: None,
+            ..listener
+        };
+        assert_eq!(udp.describe(), "BOUND on 0.0.0.0:53");
+    }
+
+    #[test]
+    fn test_find_owner_listen_only_skips_connections() {
+        let scanner = ClientAndListener;
+        assert_eq!(scanner.find_owner(3000, Protocol::Tcp, true), Ok(None));
+        let client = scanner.find_owner(3000, Protocol::Tcp, false).unwrap();
+        assert_eq!(client.map(|binding| binding.pid), Some(10));
+        let listener = scanner.find_owner(8080, Protocol::Tcp, true).unwrap();
+        assert_eq!(listener.map(|binding| binding.pid), Some(20));
+        assert_eq!(
+            scanner
+                .list_sockets(Protocol::Tcp, true)
+                .map(|rows| rows.len()),
+            Ok(1)
+        );
+        assert_eq!(
+            scanner
+                .list_sockets(Protocol::Tcp, false)
+                .map(|rows| rows.len()),
+            Ok(2)
+        );
     }
 
     #[test]
@@ -110,20 +392,41 @@ mod tests {
         let binding1 = PortBinding {
             pid: 100,
             port: 8080,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(TcpState::Listen),
         };
         let binding2 = PortBinding {
             pid: 100,
             port: 8080,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(TcpState::Listen),
         };
         let binding3 = PortBinding {
             pid: 200,
             port: 8080,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(TcpState::Listen),
         };
 
         assert_eq!(binding1, binding2);
         assert_ne!(binding1, binding3);
     }
 
+    #[test]
+    fn test_ipv6_udp_socket_is_found() {
+        let socket = std::net::UdpSocket::bind("[::1]:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        assert_eq!(
+            find_udp_process_by_port(port),
+            Ok(Some(PortBinding {
+                pid: std::process::id(),
+                port,
+                address: Ipv6Addr::LOCALHOST.into(),
+                state: None,
+            }))
+        );
+    }
+
     // Property-based tests
     use proptest::prelude::*;
 
@@ -0,0 +1,346 @@
+// Linux backend of port_service, reading /proc/net and matching socket inodes to processes
+
+use super::{PortBinding, PortScanner, Protocol, TcpState};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// TCP tables of both address families
+const TCP_TABLES: [&str; 2] = ["/proc/net/tcp", "/proc/net/tcp6"];
+
+/// UDP tables of both address families
+const UDP_TABLES: [&str; 2] = ["/proc/net/udp", "/proc/net/udp6"];
+
+/// Reads the socket tables in /proc/net and finds their owners through /proc/<pid>/fd
+///
+/// A socket of a process evict may not inspect, like one of another user's
+/// processes without root, has no visible owner and gets PID 0, as do
+/// TIME_WAIT rows. The lists keep such rows, but lookups of one port only
+/// return a row with a visible owner, as PID 0 is no process to act on
+pub struct ProcNetScanner;
+
+impl PortScanner for ProcNetScanner {
+    fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        find_process_by_port(port)
+    }
+
+    fn list_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        list_bindings()
+    }
+
+    fn list_listeners(&self) -> Result<Vec<PortBinding>, String> {
+        list_listeners()
+    }
+
+    fn find_listener(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        Ok(first_owned(
+            list_listeners()?
+                .into_iter()
+                .filter(|binding| binding.port == port)
+                .collect(),
+        ))
+    }
+
+    fn find_udp_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        find_udp_process_by_port(port)
+    }
+
+    fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        list_udp_bindings()
+    }
+}
+
+/// One row of /proc/net/tcp, tcp6, udp or udp6
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TableRow {
+    address: IpAddr,
+    port: u16,
+    /// TCP state in the kernel's numbering; UDP rows use it too
+    state: u8,
+    /// Inode of the socket, 0 for rows no file descriptor refers to
+    inode: u64,
+}
+
+impl TcpState {
+    /// Convert the state column of /proc/net/tcp
+    /// Anything the kernel may add later is reported as closed
+    fn from_proc(value: u8) -> TcpState {
+        match value {
+            0x01 => TcpState::Established,
+            0x02 => TcpState::SynSent,
+            0x03 | 0x0C => TcpState::SynReceived,
+            0x04 => TcpState::FinWait1,
+            0x05 => TcpState::FinWait2,
+            0x06 => TcpState::TimeWait,
+            0x08 => TcpState::CloseWait,
+            0x09 => TcpState::LastAck,
+            0x0A => TcpState::Listen,
+            0x0B => TcpState::Closing,
+            _ => TcpState::Closed,
+        }
+    }
+}
+
+/// Find the process that is using the specified TCP port
+pub fn find_process_by_port(port: u16) -> Result<Option<PortBinding>, String> {
+    Ok(first_owned(bindings(Protocol::Tcp, |row| {
+        row.port == port
+    })?))
+}
+
+/// List the local port and owning process of every TCP socket, IPv4 and IPv6
+pub fn list_bindings() -> Result<Vec<PortBinding>, String> {
+    bindings(Protocol::Tcp, |_| true)
+}
+
+/// List the local port and owning process of every listening TCP socket, IPv4 and IPv6
+pub fn list_listeners() -> Result<Vec<PortBinding>, String> {
+    bindings(Protocol::Tcp, |row| {
+        TcpState::from_proc(row.state) == TcpState::Listen
+    })
+}
+
+/// Find the process that has a UDP socket on the specified port
+pub fn find_udp_process_by_port(port: u16) -> Result<Option<PortBinding>, String> {
+    Ok(first_owned(bindings(Protocol::Udp, |row| {
+        row.port == port
+    })?))
+}
+
+/// List the local port and owning process of every UDP socket, IPv4 and IPv6
+pub fn list_udp_bindings() -> Result<Vec<PortBinding>, String> {
+    bindings(Protocol::Udp, |_| true)
+}
+
+/// Pick the first binding with a visible owner
+/// A TIME_WAIT row listed before the listener must not hide the listener's owner
+fn first_owned(bindings: Vec<PortBinding>) -> Option<PortBinding> {
+    bindings.into_iter().find(|binding| binding.pid != 0)
+}
+
+/// Read the tables of a protocol and resolve the owners of the rows that are kept
+/// Resolving walks every process's file descriptors, so it is skipped when no row is kept
+fn bindings(
+    protocol: Protocol,
+    keep: impl Fn(&TableRow) -> bool,
+) -> Result<Vec<PortBinding>, String> {
+    let tables = match protocol {
+        Protocol::Tcp => &TCP_TABLES,
+        Protocol::Udp => &UDP_TABLES,
+    };
+    let rows: Vec<TableRow> = read_tables(tables)?.into_iter().filter(keep).collect();
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let owners = socket_owners()?;
+    Ok(rows
+        .iter()
+        .map(|row| binding(row, protocol, &owners))
+        .collect())
+}
+
+/// Pair a row with the process that holds its socket, PID 0 when none is visible
+/// UDP rows carry a state column too, but it means nothing for a datagram socket
+fn binding(row: &TableRow, protocol: Protocol, owners: &HashMap<u64, u32>) -> PortBinding {
+    PortBinding {
+        pid: owners.get(&row.inode).copied().unwrap_or(0),
+        port: row.port,
+        address: row.address,
+        state: (protocol == Protocol::Tcp).then(|| TcpState::from_proc(row.state)),
+    }
+}
+
+/// Read and parse socket tables in order, IPv4 first
+/// A missing IPv6 table means IPv6 is disabled and counts as empty
+fn read_tables(tables: &[&str]) -> Result<Vec<TableRow>, String> {
+    let mut rows = Vec::new();
+    for table in tables {
+        match fs::read_to_string(table) {
+            Ok(contents) => rows.extend(parse_table(&contents)),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("Failed to read {}: {}", table, err)),
+        }
+    }
+    Ok(rows)
+}
+
+/// Parse the rows of a /proc/net socket table, skipping its header line
+fn parse_table(contents: &str) -> Vec<TableRow> {
+    contents.lines().skip(1).filter_map(parse_row).collect()
+}
+
+/// Parse one row: "sl local_address rem_address st ... uid timeout inode ..."
+fn parse_row(line: &str) -> Option<TableRow> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (address, port) = fields.get(1)?.split_once(':')?;
+    Some(TableRow {
+        address: parse_address(address)?,
+        port: u16::from_str_radix(port, 16).ok()?,
+        state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+        inode: fields.get(9)?.parse().ok()?,
+    })
+}
+
+/// Parse an address as the kernel prints it: 32-bit words in host byte order
+fn parse_address(hex: &str) -> Option<IpAddr> {
+    let word = |chunk: &str| u32::from_str_radix(chunk, 16).ok().map(u32::to_ne_bytes);
+    match hex.len() {
+        8 => Some(IpAddr::V4(Ipv4Addr::from(word(hex)?))),
+        32 => {
+            let mut octets = [0u8; 16];
+            for (i, bytes) in octets.chunks_exact_mut(4).enumerate() {
+                bytes.copy_from_slice(&word(hex.get(i * 8..i * 8 + 8)?)?);
+            }
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Map socket inodes to the processes that have them open
+///
+/// Processes that exit meanwhile or whose descriptors evict may not read are
+/// skipped. A socket shared after fork() goes to the lowest PID, usually the parent
+fn socket_owners() -> Result<HashMap<u64, u32>, String> {
+    let entries =
+        fs::read_dir("/proc").map_err(|e| format!("Failed to list processes in /proc: {}", e))?;
+    let mut pids: Vec<u32> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+
+    let mut owners = HashMap::new();
+    for pid in pids {
+        let Ok(descriptors) = fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        for descriptor in descriptors.flatten() {
+            let Ok(target) = fs::read_link(descriptor.path()) else {
+                continue;
+            };
+            if let Some(inode) = socket_inode(&target.to_string_lossy()) {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    Ok(owners)
+}
+
+/// Get the inode from a descriptor link such as "socket:[12345]"
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows as a little-endian kernel prints them
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 40123 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:A1B2 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 40124 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:A1B4 0100007F:1F90 06 00000000:00000000 03:00000F3A 00000000     0        0 0 3 0000000000000000
+";
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 50321 1 0000000000000000 100 0 0 10 0
+";
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_parse_ipv4_table() {
+        let rows = parse_table(TCP);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows.first(),
+            Some(&TableRow {
+                address: Ipv4Addr::LOCALHOST.into(),
+                port: 8080,
+                state: 0x0A,
+                inode: 40123,
+            })
+        );
+        let states: Vec<TcpState> = rows
+            .iter()
+            .map(|row| TcpState::from_proc(row.state))
+            .collect();
+        assert_eq!(
+            states,
+            [TcpState::Listen, TcpState::Established, TcpState::TimeWait]
+        );
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_parse_ipv6_table() {
+        let rows = parse_table(TCP6);
+        assert_eq!(
+            rows,
+            vec![TableRow {
+                address: Ipv6Addr::LOCALHOST.into(),
+                port: 3000,
+                state: 0x0A,
+                inode: 50321,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_row_rejects_malformed_lines() {
+        assert_eq!(parse_row(""), None);
+        assert_eq!(parse_row("0: nonsense"), None);
+        assert_eq!(parse_address("0100007"), None);
+        assert_eq!(parse_address("XYZ0007F"), None);
+    }
+
+    #[test]
+    fn test_socket_inode() {
+        assert_eq!(socket_inode("socket:[40123]"), Some(40123));
+        assert_eq!(socket_inode("pipe:[40123]"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+
+    #[test]
+    fn test_unowned_rows_get_pid_zero() {
+        let row = TableRow {
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            port: 80,
+            state: 0x06,
+            inode: 0,
+        };
+        let owners = HashMap::from([(7, 42)]);
+        assert_eq!(binding(&row, Protocol::Tcp, &owners).pid, 0);
+        let owned = TableRow { inode: 7, ..row };
+        assert_eq!(binding(&owned, Protocol::Tcp, &owners).pid, 42);
+        assert_eq!(
+            binding(&owned, Protocol::Tcp, &owners).state,
+            Some(TcpState::TimeWait)
+        );
+        assert_eq!(binding(&owned, Protocol::Udp, &owners).state, None);
+    }
+
+    #[test]
+    fn test_first_owned_skips_unowned_rows() {
+        let owners = HashMap::from([(7, 42)]);
+        let row = |inode, state| TableRow {
+            address: Ipv4Addr::LOCALHOST.into(),
+            port: 8080,
+            state,
+            inode,
+        };
+        let time_wait = binding(&row(0, 0x06), Protocol::Tcp, &owners);
+        let listener = binding(&row(7, 0x0A), Protocol::Tcp, &owners);
+        assert_eq!(
+            first_owned(vec![time_wait.clone(), listener.clone()]),
+            Some(listener)
+        );
+        assert_eq!(first_owned(vec![time_wait]), None);
+        assert_eq!(first_owned(Vec::new()), None);
+    }
+}
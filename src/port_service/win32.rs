@@ -0,0 +1,526 @@
+// Win32 backends of port_service: the IP Helper tables and the MSFT_Net* WMI classes
+
+use super::{PortBinding, PortScanner, Protocol, TcpState};
+use crate::error::Win32Error;
+use crate::wmi;
+use std::ffi::c_void;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::ControlFlow;
+use windows::Win32::Foundation::{ERROR_INSUFFICIENT_BUFFER, NO_ERROR};
+use windows::Win32::NetworkManagement::IpHelper::{
+    GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCP_STATE_DELETE_TCB, MIB_TCP_STATE_LISTEN,
+    MIB_TCP6ROW_OWNER_PID, MIB_TCPROW_LH, MIB_TCPROW_LH_0, MIB_TCPROW_OWNER_PID,
+    MIB_UDP6ROW_OWNER_PID, MIB_UDPROW_OWNER_PID, SetTcpEntry, TCP_TABLE_CLASS,
+    TCP_TABLE_OWNER_PID_ALL, TCP_TABLE_OWNER_PID_LISTENER, UDP_TABLE_OWNER_PID,
+};
+use windows::Win32::Networking::WinSock::{ADDRESS_FAMILY, AF_INET, AF_INET6};
+
+/// Largest TCP table evict reads, about 2.7 million connections
+const MAX_TABLE_BYTES: usize = 64 * 1024 * 1024;
+
+/// How often the TCP table read is retried when the table grows in between
+const TABLE_READ_ATTEMPTS: u32 = 4;
+
+/// Namespace holding the MSFT_Net* networking classes
+const STANDARD_CIMV2: &str = "ROOT\\StandardCimv2";
+
+impl TcpState {
+    /// Convert the dwState of a table row, or the State of an MSFT_NetTCPConnection;
+    /// values start at 1 for closed
+    /// Anything outside the documented range is reported as closed
+    fn from_mib(value: u32) -> TcpState {
+        (value as usize)
+            .checked_sub(1)
+            .and_then(|index| Self::ALL.get(index).copied())
+            .unwrap_or(TcpState::Closed)
+    }
+}
+
+/// Reads the TCP table through the IP Helper API (GetExtendedTcpTable)
+pub struct IpHelperScanner;
+
+/// Reads TCP connections from the MSFT_NetTCPConnection WMI class
+///
+/// Slower than the IP Helper API, but keeps working on hosts where endpoint
+/// security products block iphlpapi table queries
+pub struct WmiScanner;
+
+/// Available connection enumeration backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    IpHelper,
+    Wmi,
+}
+
+impl Backend {
+    /// Parse a backend name as given on the command line
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name {
+            "iphlpapi" => Some(Backend::IpHelper),
+            "wmi" => Some(Backend::Wmi),
+            _ => None,
+        }
+    }
+
+    /// Get the scanner implementing this backend
+    pub fn scanner(self) -> Box<dyn PortScanner> {
+        match self {
+            Backend::IpHelper => Box::new(IpHelperScanner),
+            Backend::Wmi => Box::new(WmiScanner),
+        }
+    }
+}
+
+impl PortScanner for IpHelperScanner {
+    fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        find_process_by_port(port)
+    }
+
+    fn list_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        list_bindings()
+    }
+
+    fn list_listeners(&self) -> Result<Vec<PortBinding>, String> {
+        list_listeners()
+    }
+
+    fn find_udp_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        find_udp_process_by_port(port)
+    }
+
+    fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        list_udp_bindings()
+    }
+}
+
+impl PortScanner for WmiScanner {
+    fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        let wql = format!(
+            "SELECT LocalPort, OwningProcess, LocalAddress, State FROM MSFT_NetTCPConnection WHERE LocalPort = {}",
+            port
+        );
+
+        // Take the first row with a usable owner, like the IP Helper search does
+        Ok(query_bindings(&wql, Protocol::Tcp)?.into_iter().next())
+    }
+
+    fn list_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        query_bindings(
+            "SELECT LocalPort, OwningProcess, LocalAddress, State FROM MSFT_NetTCPConnection",
+            Protocol::Tcp,
+        )
+    }
+
+    fn list_listeners(&self) -> Result<Vec<PortBinding>, String> {
+        // State 2 is Listen in MSFT_NetTCPConnection
+        query_bindings(
+            "SELECT LocalPort, OwningProcess, LocalAddress, State FROM MSFT_NetTCPConnection WHERE State = 2",
+            Protocol::Tcp,
+        )
+    }
+
+    fn find_udp_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+        let wql = format!(
+            "SELECT LocalPort, OwningProcess, LocalAddress FROM MSFT_NetUDPEndpoint WHERE LocalPort = {}",
+            port
+        );
+        Ok(query_bindings(&wql, Protocol::Udp)?.into_iter().next())
+    }
+
+    fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String> {
+        query_bindings(
+            "SELECT LocalPort, OwningProcess, LocalAddress FROM MSFT_NetUDPEndpoint",
+            Protocol::Udp,
+        )
+    }
+}
+
+/// Run a WMI query selecting LocalPort, OwningProcess and LocalAddress, in that
+/// order, followed by State for TCP
+fn query_bindings(wql: &str, protocol: Protocol) -> Result<Vec<PortBinding>, String> {
+    let properties: &[&str] = match protocol {
+        Protocol::Tcp => &["LocalPort", "OwningProcess", "LocalAddress", "State"],
+        Protocol::Udp => &["LocalPort", "OwningProcess", "LocalAddress"],
+    };
+    let rows = wmi::query(STANDARD_CIMV2, wql, properties)?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let mut values = row.into_iter().flatten();
+            let port = values.next()?.parse::<u16>().ok()?;
+            let pid = values.next()?.parse::<u32>().ok()?;
+            // Link-local IPv6 addresses carry a %zone suffix that IpAddr does not parse
+            let address = values.next()?.split('%').next()?.parse::<IpAddr>().ok()?;
+            let state = match protocol {
+                Protocol::Tcp => Some(TcpState::from_mib(values.next()?.parse().ok()?)),
+                Protocol::Udp => None,
+            };
+            Some(PortBinding {
+                pid,
+                port,
+                address,
+                state,
+            })
+        })
+        .collect())
+}
+
+/// Find the process ID that is using the specified port
+/// Stops at the first matching row instead of collecting the whole table
+pub fn find_process_by_port(port: u16) -> Result<Option<PortBinding>, String> {
+    scan_tcp_table(TCP_TABLE_OWNER_PID_ALL, |binding| {
+        if binding.port == port {
+            ControlFlow::Break(binding)
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+/// List the local port and owning process of every TCP socket, IPv4 and IPv6
+pub fn list_bindings() -> Result<Vec<PortBinding>, String> {
+    collect_tcp_table(TCP_TABLE_OWNER_PID_ALL)
+}
+
+/// List the local port and owning process of every listening TCP socket, IPv4 and IPv6
+pub fn list_listeners() -> Result<Vec<PortBinding>, String> {
+    collect_tcp_table(TCP_TABLE_OWNER_PID_LISTENER)
+}
+
+/// Find the process ID that has a UDP socket on the specified port
+pub fn find_udp_process_by_port(port: u16) -> Result<Option<PortBinding>, String> {
+    scan_udp_table(|binding| {
+        if binding.port == port {
+            ControlFlow::Break(binding)
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+/// List the local port and owning process of every UDP socket, IPv4 and IPv6
+pub fn list_udp_bindings() -> Result<Vec<PortBinding>, String> {
+    let mut bindings = Vec::new();
+    scan_udp_table(|binding| {
+        bindings.push(binding);
+        ControlFlow::<()>::Continue(())
+    })?;
+    Ok(bindings)
+}
+
+/// Reset the IPv4 TCP connections a process has on a local port
+/// Returns how many were reset
+///
+/// Each connection is set to DELETE_TCB, which sends a reset to the peer. The
+/// listener itself cannot be removed this way, and IPv6 connections have no
+/// equivalent call. Needs administrator privileges
+pub fn reset_connections(port: u16, pid: u32) -> Result<usize, String> {
+    let table = read_tcp_table(AF_INET, TCP_TABLE_OWNER_PID_ALL)?;
+    let mut connections = Vec::new();
+    unsafe {
+        visit_rows(
+            &table,
+            |row: &MIB_TCPROW_OWNER_PID| *row,
+            &mut |row: MIB_TCPROW_OWNER_PID| {
+                if row.dwOwningPid == pid
+                    && u16::from_be(row.dwLocalPort as u16) == port
+                    && row.dwState != MIB_TCP_STATE_LISTEN.0 as u32
+                {
+                    connections.push(row);
+                }
+                ControlFlow::<()>::Continue(())
+            },
+        );
+    }
+    if connections.is_empty() {
+        return Err(format!(
+            "PID {} has no IPv4 connections on port {} to reset",
+            pid, port
+        ));
+    }
+
+    for row in &connections {
+        let entry = MIB_TCPROW_LH {
+            Anonymous: MIB_TCPROW_LH_0 {
+                State: MIB_TCP_STATE_DELETE_TCB,
+            },
+            dwLocalAddr: row.dwLocalAddr,
+            dwLocalPort: row.dwLocalPort,
+            dwRemoteAddr: row.dwRemoteAddr,
+            dwRemotePort: row.dwRemotePort,
+        };
+        let status = unsafe { SetTcpEntry(&entry) };
+        if status != NO_ERROR.0 {
+            return Err(format!(
+                "Failed to reset a connection on port {}: {}",
+                port,
+                Win32Error::from_code(status)
+            ));
+        }
+    }
+    Ok(connections.len())
+}
+
+/// Collect every row of one owner-PID view of the TCP table
+fn collect_tcp_table(class: TCP_TABLE_CLASS) -> Result<Vec<PortBinding>, String> {
+    let mut bindings = Vec::new();
+    scan_tcp_table(class, |binding| {
+        bindings.push(binding);
+        ControlFlow::<()>::Continue(())
+    })?;
+    Ok(bindings)
+}
+
+/// Visit the rows of one owner-PID view of the TCP table in order, IPv4 first
+///
+/// Rows are decoded straight out of the table buffer, so callers that filter
+/// or stop early never build a second copy of a table with hundreds of
+/// thousands of connections. Returns the value the visitor stopped with
+fn scan_tcp_table<B>(
+    class: TCP_TABLE_CLASS,
+    mut visit: impl FnMut(PortBinding) -> ControlFlow<B>,
+) -> Result<Option<B>, String> {
+    let ipv4 = read_tcp_table(AF_INET, class)?;
+    if let Some(value) = unsafe { visit_rows(&ipv4, tcp_binding, &mut visit) } {
+        return Ok(Some(value));
+    }
+
+    // A server bound only to :: or ::1 shows up in the IPv6 table alone
+    let ipv6 = read_tcp_table(AF_INET6, class)?;
+    Ok(unsafe { visit_rows(&ipv6, tcp6_binding, &mut visit) })
+}
+
+/// Visit the rows of the owner-PID view of the UDP table in order, IPv4 first
+/// Returns the value the visitor stopped with
+fn scan_udp_table<B>(
+    mut visit: impl FnMut(PortBinding) -> ControlFlow<B>,
+) -> Result<Option<B>, String> {
+    let ipv4 = read_udp_table(AF_INET)?;
+    if let Some(value) = unsafe { visit_rows(&ipv4, udp_binding, &mut visit) } {
+        return Ok(Some(value));
+    }
+
+    let ipv6 = read_udp_table(AF_INET6)?;
+    Ok(unsafe { visit_rows(&ipv6, udp6_binding, &mut visit) })
+}
+
+/// Decode the rows of a table read by read_table and pass them to the visitor
+///
+/// Every owner-PID table is a DWORD entry count followed by rows of type R,
+/// which only hold DWORDs and byte arrays and so start right after the count.
+/// The buffer must hold a table with rows of type R
+unsafe fn visit_rows<R, T, B>(
+    buffer: &[u32],
+    decode: fn(&R) -> T,
+    visit: &mut impl FnMut(T) -> ControlFlow<B>,
+) -> Option<B> {
+    let count = *buffer.first()? as usize;
+    let rows = buffer.as_ptr().wrapping_add(1) as *const R;
+
+    for i in 0..count {
+        let row = unsafe { &*rows.add(i) };
+        if let ControlFlow::Break(value) = visit(decode(row)) {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Decode an IPv4 TCP row
+fn tcp_binding(row: &MIB_TCPROW_OWNER_PID) -> PortBinding {
+    PortBinding {
+        pid: row.dwOwningPid,
+        port: u16::from_be(row.dwLocalPort as u16),
+        address: IpAddr::V4(Ipv4Addr::from(u32::from_be(row.dwLocalAddr))),
+        state: Some(TcpState::from_mib(row.dwState)),
+    }
+}
+
+/// Decode an IPv6 TCP row
+fn tcp6_binding(row: &MIB_TCP6ROW_OWNER_PID) -> PortBinding {
+    PortBinding {
+        pid: row.dwOwningPid,
+        port: u16::from_be(row.dwLocalPort as u16),
+        address: IpAddr::V6(Ipv6Addr::from(row.ucLocalAddr)),
+        state: Some(TcpState::from_mib(row.dwState)),
+    }
+}
+
+/// Decode an IPv4 UDP row
+fn udp_binding(row: &MIB_UDPROW_OWNER_PID) -> PortBinding {
+    PortBinding {
+        pid: row.dwOwningPid,
+        port: u16::from_be(row.dwLocalPort as u16),
+        address: IpAddr::V4(Ipv4Addr::from(u32::from_be(row.dwLocalAddr))),
+        state: None,
+    }
+}
+
+/// Decode an IPv6 UDP row
+fn udp6_binding(row: &MIB_UDP6ROW_OWNER_PID) -> PortBinding {
+    PortBinding {
+        pid: row.dwOwningPid,
+        port: u16::from_be(row.dwLocalPort as u16),
+        address: IpAddr::V6(Ipv6Addr::from(row.ucLocalAddr)),
+        state: None,
+    }
+}
+
+/// Read one owner-PID view of the TCP table of an address family into a buffer
+fn read_tcp_table(family: ADDRESS_FAMILY, class: TCP_TABLE_CLASS) -> Result<Vec<u32>, String> {
+    read_table(Protocol::Tcp, |buffer, size| unsafe {
+        GetExtendedTcpTable(buffer, size, false, u32::from(family.0), class, 0)
+    })
+}
+
+/// Read the owner-PID view of the UDP table of an address family into a buffer
+fn read_udp_table(family: ADDRESS_FAMILY) -> Result<Vec<u32>, String> {
+    read_table(Protocol::Udp, |buffer, size| unsafe {
+        GetExtendedUdpTable(
+            buffer,
+            size,
+            false,
+            u32::from(family.0),
+            UDP_TABLE_OWNER_PID,
+            0,
+        )
+    })
+}
+
+/// Read a table through one of the GetExtended*Table functions
+///
+/// The table can grow between asking for its size and reading it, so the read
+/// is retried with the larger size a few times. Tables beyond MAX_TABLE_BYTES
+/// are refused rather than allocated
+fn read_table(
+    protocol: Protocol,
+    read: impl Fn(Option<*mut c_void>, &mut u32) -> u32,
+) -> Result<Vec<u32>, String> {
+    // u32 elements keep the buffer aligned for the table's DWORD fields
+    let mut buffer: Vec<u32> = Vec::new();
+    let mut size: u32 = 0;
+
+    for _ in 0..TABLE_READ_ATTEMPTS {
+        let result = read(
+            (!buffer.is_empty()).then_some(buffer.as_mut_ptr() as *mut c_void),
+            &mut size,
+        );
+
+        if result == NO_ERROR.0 {
+            return Ok(buffer);
+        }
+        if result != ERROR_INSUFFICIENT_BUFFER.0 {
+            return Err(format!(
+                "Failed to get {} table: {}",
+                protocol.as_str(),
+                Win32Error::from_code(result)
+            ));
+        }
+        if size as usize > MAX_TABLE_BYTES {
+            return Err(format!(
+                "{} table is too large to read ({} MiB, limit {} MiB)",
+                protocol.as_str(),
+                size as usize / (1024 * 1024),
+                MAX_TABLE_BYTES / (1024 * 1024)
+            ));
+        }
+
+        // Allocate buffer and try again to get actual data
+        buffer = vec![0; (size as usize).div_ceil(size_of::<u32>())];
+    }
+
+    Err(format!(
+        "Failed to get {} table: it kept growing while being read",
+        protocol.as_str()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tcp_state_from_mib() {
+        assert_eq!(
+            TcpState::from_mib(MIB_TCP_STATE_LISTEN.0 as u32),
+            TcpState::Listen
+        );
+        assert_eq!(
+            TcpState::from_mib(MIB_TCP_STATE_DELETE_TCB.0 as u32),
+            TcpState::DeleteTcb
+        );
+        assert_eq!(TcpState::from_mib(0), TcpState::Closed);
+    }
+
+    #[test]
+    fn test_read_tcp_table_is_aligned_and_sized() {
+        for family in [AF_INET, AF_INET6] {
+            let buffer = read_tcp_table(family, TCP_TABLE_OWNER_PID_ALL).unwrap();
+            assert!(buffer.len() * size_of::<u32>() <= MAX_TABLE_BYTES);
+        }
+    }
+
+    #[test]
+    fn test_ipv6_only_listener_is_found() {
+        let listener = std::net::TcpListener::bind("[::1]:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let expected = PortBinding {
+            pid: std::process::id(),
+            port,
+            address: Ipv6Addr::LOCALHOST.into(),
+            state: Some(TcpState::Listen),
+        };
+
+        assert_eq!(find_process_by_port(port), Ok(Some(expected.clone())));
+        assert!(list_listeners().unwrap().contains(&expected));
+        assert_eq!(WmiScanner.find_process_by_port(port), Ok(Some(expected)));
+    }
+
+    #[test]
+    fn test_udp_lookup_finds_socket() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let expected = PortBinding {
+            pid: std::process::id(),
+            port,
+            address: Ipv4Addr::LOCALHOST.into(),
+            state: None,
+        };
+
+        assert!(list_udp_bindings().unwrap().contains(&expected));
+        assert_eq!(
+            IpHelperScanner.find_owner(port, Protocol::Udp, true),
+            Ok(Some(expected.clone()))
+        );
+        assert_eq!(
+            WmiScanner.find_udp_process_by_port(port),
+            Ok(Some(expected))
+        );
+    }
+
+    #[test]
+    fn test_backend_from_name() {
+        assert_eq!(Backend::from_name("iphlpapi"), Some(Backend::IpHelper));
+        assert_eq!(Backend::from_name("wmi"), Some(Backend::Wmi));
+        assert_eq!(Backend::from_name("netstat"), None);
+    }
+
+    #[test]
+    fn test_backends_agree_on_listening_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let expected = Some(PortBinding {
+            pid: std::process::id(),
+            port,
+            address: Ipv4Addr::LOCALHOST.into(),
+            state: Some(TcpState::Listen),
+        });
+
+        assert_eq!(
+            IpHelperScanner.find_process_by_port(port),
+            Ok(expected.clone())
+        );
+        assert_eq!(WmiScanner.find_process_by_port(port), Ok(expected));
+    }
+}
@@ -0,0 +1,52 @@
+// Port sharing module for spotting what keeps a port unbindable after its owner is gone
+
+/// Service that lets several WCF applications listen on one net.tcp port
+const PORT_SHARING_SERVICE: &str = "NetTcpPortSharing";
+
+/// Warn when the owner is the Net.Tcp Port Sharing service
+/// The service listens on behalf of every WCF application registered with it,
+/// so terminating one of those applications never frees the port
+pub fn shared_service_warning(services: &[String]) -> Option<String> {
+    services
+        .iter()
+        .any(|service| service.eq_ignore_ascii_case(PORT_SHARING_SERVICE))
+        .then(|| {
+            format!(
+                "The port is held by the Net.Tcp Port Sharing service (SMSvcHost.exe) on behalf of every WCF application registered with it; terminating an application does not free it, stopping the service does: `sc stop {}`",
+                PORT_SHARING_SERVICE
+            )
+        })
+}
+
+/// Warn when connections on the port can keep a replacement from binding
+/// `connections` counts the TCP rows on the port that are not listeners,
+/// including TIME_WAIT rows that no process owns anymore
+pub fn lingering_warning(port: u16, connections: usize) -> Option<String> {
+    (connections > 0).then(|| {
+        format!(
+            "{} connection(s) still use port {}; a replacement that binds with SO_EXCLUSIVEADDRUSE fails until they are closed, which takes up to 2 minutes in TIME_WAIT (--verify-bind checks)",
+            connections, port
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_service_warning() {
+        let services = vec!["NetTcpPortSharing".to_string()];
+        let warning = shared_service_warning(&services).unwrap();
+        assert!(warning.contains("sc stop NetTcpPortSharing"));
+        assert_eq!(shared_service_warning(&["W3SVC".to_string()]), None);
+        assert_eq!(shared_service_warning(&[]), None);
+    }
+
+    #[test]
+    fn test_lingering_warning() {
+        assert_eq!(lingering_warning(8080, 0), None);
+        let warning = lingering_warning(8080, 3).unwrap();
+        assert!(warning.starts_with("3 connection(s) still use port 8080"));
+    }
+}
@@ -0,0 +1,508 @@
+// Privilege module for elevation and account checks
+
+use std::ffi::c_void;
+use std::fs::OpenOptions;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
+use std::os::windows::fs::OpenOptionsExt;
+use std::os::windows::io::IntoRawHandle;
+use std::path::Path;
+
+use crate::error::Win32Error;
+use crate::service_control::{self, ServiceInfo};
+use windows::Win32::Foundation::{CloseHandle, ERROR_ACCESS_DENIED, ERROR_CANCELLED, HANDLE};
+use windows::Win32::Security::{
+    EqualSid, GetTokenInformation, IsWellKnownSid, LookupAccountSidW, PSID, SID_NAME_USE,
+    TOKEN_ELEVATION, TOKEN_QUERY, TOKEN_USER, TokenElevation, TokenUser, WinLocalServiceSid,
+    WinLocalSystemSid, WinNetworkServiceSid,
+};
+use windows::Win32::Storage::FileSystem::{
+    FILE_FLAG_OPEN_REPARSE_POINT, FILE_SHARE_READ, FILE_SHARE_WRITE,
+};
+use windows::Win32::System::Console::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE, SetStdHandle};
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetExitCodeProcess, INFINITE, OpenProcess, OpenProcessToken,
+    PROCESS_ACCESS_RIGHTS, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+    WaitForSingleObject,
+};
+use windows::Win32::UI::Shell::{
+    SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW, ShellExecuteExW,
+};
+use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+use windows::core::{HSTRING, PCWSTR, PWSTR, w};
+
+/// Hidden flag an elevated relaunch is started with, followed by the file its output goes to
+pub const ELEVATED_OUTPUT_FLAG: &str = "--elevated-output";
+
+/// Whether the current token can take one action against a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capability {
+    Allowed,
+    /// The reason the action would fail
+    Denied(String),
+    /// The action does not apply to the process, e.g. stopping a service it does not host
+    NotApplicable,
+}
+
+/// Work out which actions against a process the current token can take,
+/// without taking any of them
+/// Returns the action names in the order evict would try them
+pub fn capabilities(pid: u32, services: &[ServiceInfo]) -> Vec<(&'static str, Capability)> {
+    let terminate = open_check(pid, PROCESS_TERMINATE);
+    let graceful = match graceful_close_blocker(pid) {
+        Some(reason) => Capability::Denied(reason),
+        None => Capability::Allowed,
+    };
+    let service_stop = match services.first() {
+        None => Capability::NotApplicable,
+        Some(_) => services
+            .iter()
+            .find_map(|service| service_control::can_stop(&service.name).err())
+            .map_or(Capability::Allowed, Capability::Denied),
+    };
+    // Resetting TCP connections (SetTcpEntry) is reserved for administrators
+    let reset = if is_elevated() {
+        Capability::Allowed
+    } else {
+        Capability::Denied("needs administrator privileges".to_string())
+    };
+
+    vec![
+        ("query", open_check(pid, PROCESS_QUERY_LIMITED_INFORMATION)),
+        ("graceful close", graceful),
+        ("terminate", terminate),
+        ("service stop", service_stop),
+        ("connection reset", reset),
+    ]
+}
+
+/// Helper function to check whether a process can be opened with the given access
+fn open_check(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> Capability {
+    match unsafe { OpenProcess(access, false, pid) } {
+        Ok(handle) => {
+            unsafe {
+                let _ = CloseHandle(handle);
+            }
+            Capability::Allowed
+        }
+        Err(err) => Capability::Denied(Win32Error::from(err).to_string()),
+    }
+}
+
+/// Helper function to find what keeps window messages and console signals from reaching a process
+fn graceful_close_blocker(pid: u32) -> Option<String> {
+    let mut target = 0;
+    let mut own = 0;
+    unsafe {
+        if ProcessIdToSessionId(pid, &mut target).is_err()
+            || ProcessIdToSessionId(std::process::id(), &mut own).is_err()
+        {
+            return Some("its session cannot be read".to_string());
+        }
+    }
+    if target != own {
+        return Some(format!(
+            "it runs in session {}, and windows and consoles of other sessions are out of reach",
+            target
+        ));
+    }
+
+    // User Interface Privilege Isolation drops messages to more privileged windows
+    let elevated = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .ok()
+            .map(|handle| {
+                let elevated = is_token_elevated(handle).unwrap_or(false);
+                let _ = CloseHandle(handle);
+                elevated
+            })
+    };
+    if elevated == Some(true) && !is_elevated() {
+        return Some("it runs elevated, so its windows ignore messages from evict".to_string());
+    }
+    None
+}
+
+/// Check whether evict is running with an elevated (administrator) token
+pub fn is_elevated() -> bool {
+    unsafe { is_token_elevated(GetCurrentProcess()).unwrap_or(false) }
+}
+
+/// Run evict again as administrator through the UAC prompt and wait for it to exit
+///
+/// The elevated copy cannot write to this console, so it runs in a hidden window
+/// and writes its output to a temporary file that is read back here. Returns its
+/// exit code and output
+pub fn relaunch_elevated(args: &[String]) -> Result<(u32, String), String> {
+    // The file is created here, unelevated, under a name nobody can guess. create_new
+    // fails on anything planted at the path, links included, and without delete
+    // sharing the file cannot be swapped while the elevated copy writes to it
+    let path = std::env::temp_dir().join(unguessable_name());
+    let mut output = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .share_mode(FILE_SHARE_READ.0 | FILE_SHARE_WRITE.0)
+        .open(&path)
+        .map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+
+    let result = run_elevated(&path, args);
+    let mut text = String::new();
+    let _ = output.read_to_string(&mut text);
+    drop(output);
+    let _ = std::fs::remove_file(&path);
+    result.map(|exit_code| (exit_code, text))
+}
+
+/// Helper function to start evict through the UAC prompt and wait for its exit code
+fn run_elevated(output: &Path, args: &[String]) -> Result<u32, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate evict.exe: {}", e))?;
+    let directory = std::env::current_dir()
+        .map_err(|e| format!("Failed to read the current directory: {}", e))?;
+    let parameters = [
+        ELEVATED_OUTPUT_FLAG.to_string(),
+        output.display().to_string(),
+    ]
+    .iter()
+    .chain(args)
+    .map(|arg| quote_argument(arg))
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    let file = HSTRING::from(exe.as_path());
+    let parameters = HSTRING::from(parameters);
+    let directory = HSTRING::from(directory.as_path());
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC,
+        lpVerb: w!("runas"),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(parameters.as_ptr()),
+        lpDirectory: PCWSTR(directory.as_ptr()),
+        nShow: SW_HIDE.0,
+        ..Default::default()
+    };
+    unsafe {
+        if let Err(err) = ShellExecuteExW(&mut info) {
+            if err.code() == ERROR_CANCELLED.to_hresult() {
+                return Err("The elevation prompt was declined; nothing was terminated".to_string());
+            }
+            return Err(format!(
+                "Failed to relaunch evict as administrator: {}",
+                Win32Error::from(err)
+            ));
+        }
+        if info.hProcess.is_invalid() {
+            return Err("Failed to wait for the elevated evict: no process handle".to_string());
+        }
+
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let mut exit_code = 0;
+        let status = GetExitCodeProcess(info.hProcess, &mut exit_code);
+        let _ = CloseHandle(info.hProcess);
+        status.map_err(|err| {
+            format!(
+                "Failed to read the exit code of the elevated evict: {}",
+                Win32Error::from(err)
+            )
+        })?;
+        Ok(exit_code)
+    }
+}
+
+/// Helper function to name the output file of an elevated relaunch
+/// RandomState is keyed from the system's random number generator
+fn unguessable_name() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    format!("evict-elevated-{:016x}.log", hasher.finish())
+}
+
+/// Send the output of an elevated relaunch to the file the unelevated evict reads back
+///
+/// Only the empty file the unelevated evict created is written to: nothing is
+/// created or truncated, and a link at the path is opened as itself instead of
+/// being followed. The file stays open until evict exits
+pub fn redirect_output(path: &Path) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT.0)
+        .open(path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    let metadata = file
+        .metadata()
+        .map_err(|e| format!("Failed to inspect '{}': {}", path.display(), e))?;
+    if !metadata.is_file() || metadata.len() != 0 {
+        return Err(format!(
+            "'{}' is not the empty output file of an elevated relaunch",
+            path.display()
+        ));
+    }
+    let handle = HANDLE(file.into_raw_handle());
+    unsafe {
+        SetStdHandle(STD_OUTPUT_HANDLE, handle)
+            .and_then(|()| SetStdHandle(STD_ERROR_HANDLE, handle))
+            .map_err(|err| format!("Failed to redirect output: {}", Win32Error::from(err)))
+    }
+}
+
+/// Helper function to quote an argument so CommandLineToArgvW reads it back unchanged
+fn quote_argument(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are escaped, and so is the quote
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // So are backslashes before the closing quote
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Describe why terminating the given process requires elevation
+/// Returns None when nothing indicates the current user cannot terminate it
+pub fn elevation_requirement(pid: u32) -> Option<String> {
+    // Service processes are protected by the service control manager
+    if let Ok(services) = service_control::find_services_by_pid(pid)
+        && !services.is_empty()
+    {
+        let names: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+        return Some(format!("it hosts the Windows service {}", names.join(", ")));
+    }
+
+    unsafe {
+        // Limited query access is granted for most processes, even other users'
+        let handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+            Ok(handle) => handle,
+            Err(err) if err.code() == ERROR_ACCESS_DENIED.to_hresult() => {
+                return Some("it belongs to another user or to the system".to_string());
+            }
+            Err(_) => return None,
+        };
+
+        let result = token_requirement(handle);
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+/// Get the DOMAIN\\name account a process runs as
+/// Returns None when the process cannot be opened or its token cannot be read
+pub fn process_user(pid: u32) -> Option<String> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let buffer = query_token_user(handle);
+        let _ = CloseHandle(handle);
+        account_name(token_user_sid(&buffer.ok()?))
+    }
+}
+
+/// Helper function to inspect the token of an open process
+unsafe fn token_requirement(process: HANDLE) -> Option<String> {
+    let target = match unsafe { query_token_user(process) } {
+        Ok(buffer) => buffer,
+        Err(err) if err.code() == ERROR_ACCESS_DENIED.to_hresult() => {
+            return Some("it belongs to another user or to the system".to_string());
+        }
+        Err(_) => return None,
+    };
+    let current = unsafe { query_token_user(GetCurrentProcess()) }.ok()?;
+
+    unsafe {
+        let target_sid = token_user_sid(&target);
+        let account = account_name(target_sid).unwrap_or_else(|| "another account".to_string());
+
+        // Built-in service accounts
+        let service_account = [WinLocalSystemSid, WinLocalServiceSid, WinNetworkServiceSid]
+            .into_iter()
+            .any(|kind| IsWellKnownSid(target_sid, kind).as_bool());
+        if service_account {
+            return Some(format!("it runs as {}", account));
+        }
+
+        if EqualSid(target_sid, token_user_sid(&current)).is_err() {
+            return Some(format!("it runs as another user ({})", account));
+        }
+
+        // Same user, but an elevated process cannot be terminated from a normal token
+        if is_token_elevated(process).unwrap_or(false) && !is_elevated() {
+            return Some("it runs elevated (as administrator)".to_string());
+        }
+    }
+
+    None
+}
+
+/// Helper function to check the elevation flag of a process token
+unsafe fn is_token_elevated(process: HANDLE) -> windows::core::Result<bool> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(process, TOKEN_QUERY, &mut token)?;
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut size = size_of::<TOKEN_ELEVATION>() as u32;
+        let result = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut c_void),
+            size,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+
+        result.map(|()| elevation.TokenIsElevated != 0)
+    }
+}
+
+/// Helper function to read the TOKEN_USER structure of a process
+/// Returns the raw buffer, since the SID it contains points into it
+unsafe fn query_token_user(process: HANDLE) -> windows::core::Result<Vec<u8>> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(process, TOKEN_QUERY, &mut token)?;
+
+        // First call to get the required buffer size
+        let mut size: u32 = 0;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut size);
+
+        // Allocate buffer and make second call to get actual data
+        let mut buffer: Vec<u8> = vec![0; size as usize];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            size,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+
+        result.map(|()| buffer)
+    }
+}
+
+/// Helper function to get the user SID from a TOKEN_USER buffer
+unsafe fn token_user_sid(buffer: &[u8]) -> PSID {
+    let user = buffer.as_ptr() as *const TOKEN_USER;
+    unsafe { (*user).User.Sid }
+}
+
+/// Resolve a SID to a DOMAIN\name account string
+unsafe fn account_name(sid: PSID) -> Option<String> {
+    let mut name = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain = [0u16; 256];
+    let mut domain_len = domain.len() as u32;
+    let mut sid_use = SID_NAME_USE::default();
+
+    unsafe {
+        LookupAccountSidW(
+            PCWSTR::null(),
+            sid,
+            Some(PWSTR(name.as_mut_ptr())),
+            &mut name_len,
+            Some(PWSTR(domain.as_mut_ptr())),
+            &mut domain_len,
+            &mut sid_use,
+        )
+        .ok()?;
+    }
+
+    let name = String::from_utf16_lossy(name.get(..name_len as usize)?);
+    let domain = String::from_utf16_lossy(domain.get(..domain_len as usize)?);
+
+    if domain.is_empty() {
+        Some(name)
+    } else {
+        Some(format!("{}\\{}", domain, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_elevated_does_not_panic() {
+        // The result depends on how the tests are run; the call itself must succeed
+        let _ = is_elevated();
+    }
+
+    #[test]
+    fn test_process_user_of_current_process() {
+        let user = process_user(std::process::id()).unwrap();
+        assert!(!user.is_empty());
+        assert_eq!(process_user(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_capabilities_of_current_process() {
+        let capabilities = capabilities(std::process::id(), &[]);
+        let names: Vec<&str> = capabilities.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "query",
+                "graceful close",
+                "terminate",
+                "service stop",
+                "connection reset"
+            ]
+        );
+        assert_eq!(capabilities[0].1, Capability::Allowed);
+        assert_eq!(capabilities[2].1, Capability::Allowed);
+        assert_eq!(capabilities[3].1, Capability::NotApplicable);
+    }
+
+    #[test]
+    fn test_current_process_needs_no_elevation() {
+        let result = elevation_requirement(std::process::id());
+        assert_eq!(
+            result, None,
+            "Current process should be terminable by its own user"
+        );
+    }
+
+    #[test]
+    fn test_quote_argument() {
+        assert_eq!(quote_argument("8080"), "8080");
+        assert_eq!(quote_argument(""), r#""""#);
+        assert_eq!(
+            quote_argument(r"C:\Users\dev\AppData\Local\Temp\evict.log"),
+            r"C:\Users\dev\AppData\Local\Temp\evict.log"
+        );
+        assert_eq!(
+            quote_argument(r"C:\Program Files\evict\"),
+            r#""C:\Program Files\evict\\""#
+        );
+        assert_eq!(
+            quote_argument(r#"curl -f "http://localhost:8080/""#),
+            r#""curl -f \"http://localhost:8080/\"""#
+        );
+    }
+
+    #[test]
+    fn test_system_process_needs_elevation() {
+        // PID 4 is the System process, which never belongs to a regular user
+        if !is_elevated() {
+            assert!(elevation_requirement(4).is_some());
+        }
+    }
+}
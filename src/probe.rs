@@ -0,0 +1,497 @@
+// Probe module for identifying the protocol spoken on a port
+
+use crate::certificate::{self, Certificate};
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// How long each probe waits to connect and for the listener to answer
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// How long to wait for a server that speaks first (SSH, SMTP, ...)
+const BANNER_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Most bytes read from any single response, enough for a TLS certificate chain
+const MAX_RESPONSE: usize = 32 * 1024;
+
+/// TLS record and handshake message types
+const TLS_HANDSHAKE_RECORD: u8 = 22;
+const TLS_ALERT_RECORD: u8 = 21;
+const TLS_CERTIFICATE_MESSAGE: u8 = 11;
+
+/// What a listener appears to be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identification {
+    pub protocol: &'static str,
+    /// Product or version details, e.g. the HTTP Server header
+    pub detail: Option<String>,
+    /// Certificate presented by a TLS listener
+    pub certificate: Option<Certificate>,
+    /// Response of an HTTP listener to GET /
+    pub page: Option<HttpPage>,
+}
+
+/// What an HTTP listener serves at its root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpPage {
+    /// Status line without the protocol, e.g. "200 OK"
+    pub status: String,
+    /// Contents of the HTML <title> element
+    pub title: Option<String>,
+}
+
+impl fmt::Display for Identification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{} ({})", self.protocol, detail),
+            None => write!(f, "{}", self.protocol),
+        }
+    }
+}
+
+/// Identify the protocol of a local listener with a few lightweight probes
+///
+/// Server-first protocols are recognized from their banner; otherwise HTTP,
+/// TLS and Redis requests are tried in turn, each on a fresh connection
+/// Returns None when the port cannot be reached or nothing matched
+pub fn identify(port: u16) -> Option<Identification> {
+    let banner = exchange(port, None, BANNER_TIMEOUT).ok()?;
+    if !banner.is_empty() {
+        return Some(classify_banner(&banner));
+    }
+
+    let probes: [fn(u16) -> Option<Identification>; 3] = [probe_http, probe_tls, probe_redis];
+    probes.iter().find_map(|probe| probe(port))
+}
+
+/// Connect to a local port, preferring IPv4 and falling back to IPv6
+fn connect(port: u16) -> std::io::Result<TcpStream> {
+    TcpStream::connect_timeout(
+        &SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        PROBE_TIMEOUT,
+    )
+    .or_else(|_| {
+        TcpStream::connect_timeout(
+            &SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+            PROBE_TIMEOUT,
+        )
+    })
+}
+
+/// Open a connection, optionally send a request, and read what comes back
+/// A listener that stays silent yields an empty response rather than an error
+fn exchange(port: u16, request: Option<&[u8]>, timeout: Duration) -> std::io::Result<Vec<u8>> {
+    let mut stream = connect(port)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(PROBE_TIMEOUT))?;
+
+    if let Some(request) = request {
+        stream.write_all(request)?;
+    }
+
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while response.len() < MAX_RESPONSE {
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(len) => response.extend_from_slice(chunk.get(..len).unwrap_or(&[])),
+            Err(_) => break,
+        }
+    }
+    Ok(response)
+}
+
+/// Recognize a server-first protocol from its greeting
+fn classify_banner(banner: &[u8]) -> Identification {
+    let text = String::from_utf8_lossy(banner);
+    let first_line = text.lines().next().unwrap_or("").trim();
+
+    let (protocol, detail) = if let Some(version) = first_line.strip_prefix("SSH-") {
+        // SSH-2.0-OpenSSH_9.5 -> OpenSSH_9.5
+        ("SSH", version.split_once('-').map(|(_, software)| software))
+    } else if let Some(greeting) = first_line.strip_prefix("220") {
+        let protocol = if greeting.to_ascii_uppercase().contains("FTP") {
+            "FTP"
+        } else {
+            "SMTP"
+        };
+        (protocol, Some(greeting.trim_start_matches(['-', ' '])))
+    } else if first_line.starts_with("+OK") {
+        ("POP3", None)
+    } else if first_line.starts_with("* OK") {
+        ("IMAP", None)
+    } else if let Some(version) = mysql_version(banner) {
+        return Identification {
+            protocol: "MySQL",
+            detail: Some(version),
+            certificate: None,
+            page: None,
+        };
+    } else {
+        ("unknown protocol that sends a banner", None)
+    };
+
+    Identification {
+        protocol,
+        detail: detail
+            .map(str::trim)
+            .filter(|detail| !detail.is_empty())
+            .map(str::to_string),
+        certificate: None,
+        page: None,
+    }
+}
+
+/// Read the server version from a MySQL protocol 10 handshake packet
+fn mysql_version(banner: &[u8]) -> Option<String> {
+    // 3-byte length, sequence number, protocol version 10, null-terminated version
+    if banner.get(3) != Some(&0) || banner.get(4) != Some(&10) {
+        return None;
+    }
+    let version = banner.get(5..)?;
+    let end = version.iter().position(|&b| b == 0)?;
+    let version = String::from_utf8_lossy(version.get(..end)?).to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Fetch / and read the status, Server header and page title
+fn probe_http(port: u16) -> Option<Identification> {
+    let request = b"GET / HTTP/1.0\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    let response = exchange(port, Some(request), PROBE_TIMEOUT).ok()?;
+
+    let text = String::from_utf8_lossy(&response);
+    if !text.starts_with("HTTP/") {
+        return None;
+    }
+
+    let (head, body) = text.split_once("\r\n\r\n").unwrap_or((&text, ""));
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_once(' '))
+        .map(|(_, status)| status.trim().to_string())
+        .unwrap_or_default();
+
+    Some(Identification {
+        protocol: "HTTP",
+        detail: header_value(head, "Server"),
+        certificate: None,
+        page: Some(HttpPage {
+            status,
+            title: html_title(body),
+        }),
+    })
+}
+
+/// Extract the text of the HTML <title> element
+fn html_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower.get(open..)?.find('>')? + 1;
+    let end = start + lower.get(start..)?.find("</title")?;
+
+    let title = body
+        .get(start..end)?
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Find a header in an HTTP response head, case-insensitively
+fn header_value(response: &str, name: &str) -> Option<String> {
+    response
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Send a TLS ClientHello and check for a handshake or alert record in reply
+/// Only TLS 1.2 is offered, so the server's certificate arrives unencrypted
+fn probe_tls(port: u16) -> Option<Identification> {
+    let response = exchange(port, Some(&client_hello()), PROBE_TIMEOUT).ok()?;
+
+    // Record type handshake or alert, followed by major version 3
+    match (response.first(), response.get(1)) {
+        (Some(&(TLS_HANDSHAKE_RECORD | TLS_ALERT_RECORD)), Some(3)) => Some(Identification {
+            protocol: "TLS",
+            detail: None,
+            certificate: server_certificate(&response).and_then(|der| certificate::parse(&der)),
+            page: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Extract the leaf certificate from the server's handshake flight
+fn server_certificate(response: &[u8]) -> Option<Vec<u8>> {
+    // Handshake messages may span records, so join the record payloads first
+    let mut handshake = Vec::new();
+    let mut records = response;
+    while let Some(&record_type) = records.first() {
+        // A truncated final record still leaves the earlier messages usable
+        let Some(len) = records.get(3..).and_then(read_u16) else {
+            break;
+        };
+        let Some(fragment) = records.get(5..5 + len) else {
+            break;
+        };
+        if record_type == TLS_HANDSHAKE_RECORD {
+            handshake.extend_from_slice(fragment);
+        }
+        records = records.get(5 + len..).unwrap_or(&[]);
+    }
+
+    let mut messages = handshake.as_slice();
+    while let Some(&message_type) = messages.first() {
+        let len = read_u24(messages.get(1..)?)?;
+        let body = messages.get(4..4 + len)?;
+        if message_type == TLS_CERTIFICATE_MESSAGE {
+            // Total chain length, then the first certificate's length and bytes
+            let leaf_len = read_u24(body.get(3..)?)?;
+            return body.get(6..6 + leaf_len).map(<[u8]>::to_vec);
+        }
+        messages = messages.get(4 + len..)?;
+    }
+    None
+}
+
+/// Read a 16-bit big-endian length
+fn read_u16(data: &[u8]) -> Option<usize> {
+    Some(u16::from_be_bytes([*data.first()?, *data.get(1)?]) as usize)
+}
+
+/// Read a 24-bit big-endian length
+fn read_u24(data: &[u8]) -> Option<usize> {
+    Some(u32::from_be_bytes([0, *data.first()?, *data.get(1)?, *data.get(2)?]) as usize)
+}
+
+/// Send a Redis PING and check for a Redis reply
+fn probe_redis(port: u16) -> Option<Identification> {
+    let response = exchange(port, Some(b"PING\r\n"), PROBE_TIMEOUT).ok()?;
+
+    let text = String::from_utf8_lossy(&response);
+    (text.starts_with("+PONG") || text.starts_with("-NOAUTH") || text.starts_with("-ERR"))
+        .then_some(Identification {
+            protocol: "Redis",
+            detail: None,
+            certificate: None,
+            page: None,
+        })
+}
+
+/// Build a TLS 1.2 ClientHello for localhost
+fn client_hello() -> Vec<u8> {
+    let host = b"localhost";
+
+    let mut extensions = Vec::new();
+    // server_name
+    let mut server_name = vec![0u8];
+    push_u16_prefixed(&mut server_name, host);
+    let mut server_name_list = Vec::new();
+    push_u16_prefixed(&mut server_name_list, &server_name);
+    push_extension(&mut extensions, 0x0000, &server_name_list);
+    // supported_groups: x25519, secp256r1, secp384r1
+    let mut groups = Vec::new();
+    push_u16_prefixed(&mut groups, &[0x00, 0x1d, 0x00, 0x17, 0x00, 0x18]);
+    push_extension(&mut extensions, 0x000a, &groups);
+    // ec_point_formats: uncompressed
+    push_extension(&mut extensions, 0x000b, &[0x01, 0x00]);
+    // signature_algorithms
+    let mut algorithms = Vec::new();
+    push_u16_prefixed(
+        &mut algorithms,
+        &[
+            0x04, 0x03, 0x08, 0x04, 0x04, 0x01, 0x05, 0x03, 0x08, 0x05, 0x05, 0x01, 0x06, 0x01,
+        ],
+    );
+    push_extension(&mut extensions, 0x000d, &algorithms);
+
+    let mut hello = vec![0x03, 0x03];
+    // Client random; uniqueness doesn't matter since the handshake is never finished
+    hello.extend_from_slice(&[0x45; 32]);
+    // Empty session id
+    hello.push(0);
+    // ECDHE and RSA suites with AES-GCM and AES-CBC
+    push_u16_prefixed(
+        &mut hello,
+        &[
+            0xc0, 0x2b, 0xc0, 0x2f, 0xc0, 0x2c, 0xc0, 0x30, 0x00, 0x9c, 0x00, 0x9d, 0x00, 0x2f,
+            0x00, 0x35,
+        ],
+    );
+    // Null compression only
+    hello.extend_from_slice(&[0x01, 0x00]);
+    push_u16_prefixed(&mut hello, &extensions);
+
+    let mut handshake = vec![0x01];
+    handshake.extend_from_slice((hello.len() as u32).to_be_bytes().get(1..).unwrap_or(&[]));
+    handshake.extend_from_slice(&hello);
+
+    let mut record = vec![0x16, 0x03, 0x01];
+    push_u16_prefixed(&mut record, &handshake);
+    record
+}
+
+/// Append data preceded by its 16-bit big-endian length
+fn push_u16_prefixed(buffer: &mut Vec<u8>, data: &[u8]) {
+    buffer.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(data);
+}
+
+/// Append a TLS extension with the given type and body
+fn push_extension(buffer: &mut Vec<u8>, extension_type: u16, body: &[u8]) {
+    buffer.extend_from_slice(&extension_type.to_be_bytes());
+    push_u16_prefixed(buffer, body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Serve one connection: optionally greet, then answer the first request
+    fn serve_once(greeting: &'static [u8], reply: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(4) {
+                let mut stream = stream.unwrap();
+                if !greeting.is_empty() {
+                    let _ = stream.write_all(greeting);
+                    continue;
+                }
+                let mut request = [0u8; 1024];
+                if stream.read(&mut request).is_ok_and(|len| len > 0) {
+                    let _ = stream.write_all(reply);
+                }
+            }
+        });
+
+        port
+    }
+
+    #[test]
+    fn test_identify_ssh_banner() {
+        let port = serve_once(b"SSH-2.0-OpenSSH_9.5\r\n", b"");
+        let identification = identify(port).unwrap();
+        assert_eq!(identification.to_string(), "SSH (OpenSSH_9.5)");
+    }
+
+    #[test]
+    fn test_identify_http_server_header() {
+        let port = serve_once(b"", b"HTTP/1.1 200 OK\r\nServer: Kestrel\r\n\r\n");
+        let identification = identify(port).unwrap();
+        assert_eq!(identification.to_string(), "HTTP (Kestrel)");
+    }
+
+    #[test]
+    fn test_identify_http_page() {
+        let port = serve_once(
+            b"",
+            b"HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\n\r\n<html><head><TITLE>\n  Grafana &amp; Loki\n</TITLE></head></html>",
+        );
+        let page = identify(port).unwrap().page.unwrap();
+        assert_eq!(page.status, "404 Not Found");
+        assert_eq!(page.title, Some("Grafana & Loki".to_string()));
+    }
+
+    #[test]
+    fn test_html_title() {
+        assert_eq!(
+            html_title("<title lang=\"en\">Dashboard</title>"),
+            Some("Dashboard".to_string())
+        );
+        assert_eq!(html_title("<title></title>"), None);
+        assert_eq!(html_title("<h1>No title</h1>"), None);
+        assert_eq!(html_title("<title>Unterminated"), None);
+    }
+
+    #[test]
+    fn test_identify_closed_port() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        assert_eq!(identify(port), None);
+    }
+
+    #[test]
+    fn test_classify_banners() {
+        assert_eq!(
+            classify_banner(b"220 mail.example.com ESMTP Postfix\r\n").to_string(),
+            "SMTP (mail.example.com ESMTP Postfix)"
+        );
+        assert_eq!(classify_banner(b"220 (vsFTPd 3.0.5)\r\n").protocol, "FTP");
+        assert_eq!(classify_banner(b"+OK POP3 ready\r\n").protocol, "POP3");
+        assert_eq!(classify_banner(b"* OK IMAP4rev1\r\n").protocol, "IMAP");
+    }
+
+    #[test]
+    fn test_classify_mysql_handshake() {
+        let banner = b"\x4a\x00\x00\x00\x0a8.0.36\x00\x08\x00\x00\x00";
+        assert_eq!(classify_banner(banner).to_string(), "MySQL (8.0.36)");
+    }
+
+    #[test]
+    fn test_header_value_is_case_insensitive() {
+        let response = "HTTP/1.1 200 OK\r\nserver: nginx/1.25\r\n\r\nServer: body";
+        assert_eq!(
+            header_value(response, "Server"),
+            Some("nginx/1.25".to_string())
+        );
+        assert_eq!(header_value(response, "X-Powered-By"), None);
+    }
+
+    #[test]
+    fn test_server_certificate_spanning_records() {
+        let der = include_bytes!("../tests/fixtures/evict-test.der");
+
+        // ServerHello stub followed by a Certificate message holding one certificate
+        let mut messages = vec![2, 0, 0, 2, 3, 3];
+        let chain_len = der.len() + 3;
+        messages.push(TLS_CERTIFICATE_MESSAGE);
+        messages.extend_from_slice(&((chain_len + 3) as u32).to_be_bytes()[1..]);
+        messages.extend_from_slice(&(chain_len as u32).to_be_bytes()[1..]);
+        messages.extend_from_slice(&(der.len() as u32).to_be_bytes()[1..]);
+        messages.extend_from_slice(der);
+
+        // Split the handshake across two records
+        let (first, second) = messages.split_at(100);
+        let mut response = Vec::new();
+        for fragment in [first, second] {
+            response.extend_from_slice(&[TLS_HANDSHAKE_RECORD, 3, 3]);
+            response.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            response.extend_from_slice(fragment);
+        }
+
+        assert_eq!(server_certificate(&response).unwrap(), der.to_vec());
+    }
+
+    #[test]
+    fn test_server_certificate_missing() {
+        assert_eq!(
+            server_certificate(&[TLS_ALERT_RECORD, 3, 3, 0, 2, 2, 40]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_client_hello_lengths_are_consistent() {
+        let hello = client_hello();
+        let record_len = u16::from_be_bytes([hello[3], hello[4]]) as usize;
+        assert_eq!(record_len, hello.len() - 5);
+
+        let handshake_len = u32::from_be_bytes([0, hello[6], hello[7], hello[8]]) as usize;
+        assert_eq!(handshake_len, hello.len() - 9);
+    }
+}
@@ -0,0 +1,220 @@
+// Process info module for reading another process's command line and working directory
+
+use crate::error::Win32Error;
+use crate::wmi;
+use std::ffi::c_void;
+use std::path::PathBuf;
+use windows::Wdk::System::Threading::{
+    NtQueryInformationProcess, ProcessBasicInformation, ProcessCommandLineInformation,
+};
+use windows::Win32::Foundation::{CloseHandle, HANDLE, UNICODE_STRING};
+use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+use windows::Win32::System::Threading::{
+    OpenProcess, PROCESS_ACCESS_RIGHTS, PROCESS_BASIC_INFORMATION, PROCESS_QUERY_INFORMATION,
+    PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+};
+
+/// Offset of ProcessParameters in the PEB
+#[cfg(target_pointer_width = "64")]
+const PEB_PROCESS_PARAMETERS: usize = 0x20;
+#[cfg(target_pointer_width = "32")]
+const PEB_PROCESS_PARAMETERS: usize = 0x10;
+
+/// Offset of CurrentDirectory.DosPath in RTL_USER_PROCESS_PARAMETERS
+#[cfg(target_pointer_width = "64")]
+const PARAMETERS_CURRENT_DIRECTORY: usize = 0x38;
+#[cfg(target_pointer_width = "32")]
+const PARAMETERS_CURRENT_DIRECTORY: usize = 0x24;
+
+/// Offset of CommandLine in RTL_USER_PROCESS_PARAMETERS
+#[cfg(target_pointer_width = "64")]
+const PARAMETERS_COMMAND_LINE: usize = 0x70;
+#[cfg(target_pointer_width = "32")]
+const PARAMETERS_COMMAND_LINE: usize = 0x40;
+
+/// Longest string read out of another process
+const MAX_STRING_BYTES: usize = 64 * 1024;
+
+/// Get the full command line of a process
+///
+/// No single method works for every process, so they are tried from the one
+/// needing the least access: the ProcessCommandLineInformation query (limited
+/// query access), reading the PEB (query and VM read access), and finally
+/// WMI's Win32_Process, which the WMI service answers with its own rights
+pub fn command_line(pid: u32) -> Result<String, String> {
+    let mut errors = Vec::new();
+    let strategies: [fn(u32) -> Result<String, String>; 3] =
+        [query_command_line, peb_command_line, wmi_command_line];
+    for strategy in strategies {
+        match strategy(pid) {
+            Ok(command_line) => return Ok(command_line),
+            Err(err) => errors.push(err),
+        }
+    }
+    Err(errors.join("; "))
+}
+
+/// Read the command line through NtQueryInformationProcess
+fn query_command_line(pid: u32) -> Result<String, String> {
+    with_process(pid, PROCESS_QUERY_LIMITED_INFORMATION, |handle| unsafe {
+        // The result is a UNICODE_STRING followed by the characters it points to
+        let mut buffer: Vec<u64> = vec![0; MAX_STRING_BYTES / 8];
+        let mut returned: u32 = 0;
+        let status = NtQueryInformationProcess(
+            handle,
+            ProcessCommandLineInformation,
+            buffer.as_mut_ptr() as *mut c_void,
+            (buffer.len() * 8) as u32,
+            &mut returned,
+        );
+        if status.0 < 0 {
+            return Err(format!(
+                "Failed to read the command line of process {}: NTSTATUS {:#010X}",
+                pid, status.0
+            ));
+        }
+
+        let string = &*(buffer.as_ptr() as *const UNICODE_STRING);
+        if string.Buffer.is_null() {
+            return Ok(String::new());
+        }
+        let chars = std::slice::from_raw_parts(string.Buffer.0, string.Length as usize / 2);
+        Ok(String::from_utf16_lossy(chars))
+    })
+}
+
+/// Read the command line out of the process parameters block in the target's PEB
+fn peb_command_line(pid: u32) -> Result<String, String> {
+    parameters_string(pid, PARAMETERS_COMMAND_LINE)
+}
+
+/// Read the command line from WMI's Win32_Process class
+fn wmi_command_line(pid: u32) -> Result<String, String> {
+    let wql = format!(
+        "SELECT CommandLine FROM Win32_Process WHERE ProcessId = {}",
+        pid
+    );
+    wmi::query(wmi::CIMV2, &wql, &["CommandLine"])?
+        .into_iter()
+        .next()
+        .and_then(|row| row.into_iter().next().flatten())
+        .ok_or_else(|| format!("WMI has no command line for process {}", pid))
+}
+
+/// Get the current working directory of a process
+/// Reads the process parameters block from the target's PEB
+pub fn current_directory(pid: u32) -> Result<PathBuf, String> {
+    parameters_string(pid, PARAMETERS_CURRENT_DIRECTORY).map(PathBuf::from)
+}
+
+/// Read one UNICODE_STRING field of the target's RTL_USER_PROCESS_PARAMETERS
+fn parameters_string(pid: u32, offset: usize) -> Result<String, String> {
+    let access = PROCESS_QUERY_INFORMATION | PROCESS_VM_READ;
+    with_process(pid, access, |handle| unsafe {
+        let mut basic = PROCESS_BASIC_INFORMATION::default();
+        let status = NtQueryInformationProcess(
+            handle,
+            ProcessBasicInformation,
+            &mut basic as *mut PROCESS_BASIC_INFORMATION as *mut c_void,
+            size_of::<PROCESS_BASIC_INFORMATION>() as u32,
+            &mut 0,
+        );
+        if status.0 < 0 || basic.PebBaseAddress.is_null() {
+            return Err(format!(
+                "Failed to locate the PEB of process {}: NTSTATUS {:#010X}",
+                pid, status.0
+            ));
+        }
+
+        let peb = basic.PebBaseAddress as usize;
+        let parameters: usize = read_value(handle, peb + PEB_PROCESS_PARAMETERS)?;
+        let string: UNICODE_STRING = read_value(handle, parameters + offset)?;
+
+        let len = (string.Length as usize).min(MAX_STRING_BYTES) / 2;
+        let mut chars = vec![0u16; len];
+        ReadProcessMemory(
+            handle,
+            string.Buffer.0 as *const c_void,
+            chars.as_mut_ptr() as *mut c_void,
+            len * 2,
+            None,
+        )
+        .map_err(|e| format!("Failed to read process memory: {}", Win32Error::from(e)))?;
+
+        Ok(String::from_utf16_lossy(&chars))
+    })
+}
+
+/// Read a plain value from another process's address space
+unsafe fn read_value<T: Default>(handle: HANDLE, address: usize) -> Result<T, String> {
+    let mut value = T::default();
+    unsafe {
+        ReadProcessMemory(
+            handle,
+            address as *const c_void,
+            &mut value as *mut T as *mut c_void,
+            size_of::<T>(),
+            None,
+        )
+    }
+    .map_err(|e| format!("Failed to read process memory: {}", Win32Error::from(e)))?;
+    Ok(value)
+}
+
+/// Open a process, run a query against it and close the handle again
+fn with_process<T>(
+    pid: u32,
+    access: PROCESS_ACCESS_RIGHTS,
+    query: impl FnOnce(HANDLE) -> Result<T, String>,
+) -> Result<T, String> {
+    unsafe {
+        let handle = OpenProcess(access, false, pid)
+            .map_err(|e| format!("Failed to open process {}: {}", pid, Win32Error::from(e)))?;
+        let result = query(handle);
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_of_current_process() {
+        let command_line = command_line(std::process::id()).unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let exe_name = exe.file_stem().unwrap().to_string_lossy();
+        assert!(command_line.contains(exe_name.as_ref()));
+    }
+
+    #[test]
+    fn test_command_line_strategies_agree() {
+        let pid = std::process::id();
+        let expected = query_command_line(pid).unwrap();
+        assert_eq!(peb_command_line(pid).unwrap(), expected);
+        assert_eq!(wmi_command_line(pid).unwrap().trim(), expected.trim());
+    }
+
+    #[test]
+    fn test_current_directory_of_current_process() {
+        let directory = current_directory(std::process::id()).unwrap();
+        let expected = std::env::current_dir().unwrap();
+        assert_eq!(
+            directory
+                .to_string_lossy()
+                .trim_end_matches('\\')
+                .to_lowercase(),
+            expected
+                .to_string_lossy()
+                .trim_end_matches('\\')
+                .to_lowercase()
+        );
+    }
+
+    #[test]
+    fn test_invalid_pid_fails() {
+        assert!(command_line(9999999).is_err());
+        assert!(current_directory(9999999).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+// Linux backend of process_service, reading /proc and signalling with kill(2)
+
+use super::ProcessManager;
+use crate::process_tree;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a signalled process is checked for having exited
+/// SIGKILL takes effect within a few milliseconds, and reading /proc is cheap
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Works on real processes through /proc and signals
+pub struct LinuxProcessManager;
+
+impl ProcessManager for LinuxProcessManager {
+    fn process_name(&self, pid: u32) -> Result<String, String> {
+        get_process_name(pid)
+    }
+
+    /// Linux has no protected processes; root can signal any of them
+    fn protection_level(&self, _pid: u32) -> Option<&'static str> {
+        None
+    }
+
+    fn kill(&self, pid: u32, exit_code: u32) -> Result<(), String> {
+        kill_process(pid, exit_code)
+    }
+
+    fn terminate_gracefully(
+        &self,
+        pid: u32,
+        timeout: Duration,
+        console_group: bool,
+    ) -> Result<(), String> {
+        terminate_gracefully(pid, timeout, console_group)
+    }
+
+    fn is_running(&self, pid: u32) -> bool {
+        is_running(pid)
+    }
+}
+
+/// Get the process name for a given PID
+/// Uses the file name of the executable, or the kernel's short name in
+/// /proc/<pid>/comm when the executable cannot be read, as for other users' processes
+pub fn get_process_name(pid: u32) -> Result<String, String> {
+    if let Ok(path) = get_process_path(pid)
+        && let Some(name) = Path::new(&path).file_name()
+    {
+        return Ok(name.to_string_lossy().into_owned());
+    }
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|name| name.trim_end().to_string())
+        .map_err(|e| format!("Failed to get process name for PID {}: {}", pid, e))
+}
+
+/// Get the full path of a process's executable
+pub fn get_process_path(pid: u32) -> Result<String, String> {
+    let path = fs::read_link(format!("/proc/{}/exe", pid))
+        .map_err(|e| format!("Failed to get the executable path of PID {}: {}", pid, e))?;
+    // The kernel marks an executable replaced on disk since the process started
+    let path = path.to_string_lossy();
+    Ok(path.trim_end_matches(" (deleted)").to_string())
+}
+
+/// Get the PID of the process that started the given process
+/// Processes whose parent exited are adopted by init or a subreaper, so this is the current parent
+pub fn get_parent_pid(pid: u32) -> Option<u32> {
+    stat_field(pid, 4)?
+        .parse()
+        .ok()
+        .filter(|&parent| parent != 0)
+}
+
+/// Get the PIDs of every process started under the given one, parents before children
+pub fn descendant_pids(pid: u32) -> Result<Vec<u32>, String> {
+    let entries =
+        fs::read_dir("/proc").map_err(|e| format!("Failed to list processes in /proc: {}", e))?;
+    let processes: Vec<(u32, u32)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            Some((pid, get_parent_pid(pid)?))
+        })
+        .collect();
+    Ok(process_tree::descendants(pid, &processes, start_time))
+}
+
+/// Terminate a process immediately with SIGKILL
+/// Linux cannot choose the exit status of another process, so exit_code is
+/// not used; the process is reported as killed by signal 9
+pub fn kill_process(pid: u32, _exit_code: u32) -> Result<(), String> {
+    send_signal(pid, libc::SIGKILL)
+        .map_err(|e| format!("Failed to terminate process {}: {}", pid, e))?;
+
+    // SIGKILL is delivered asynchronously; the port is only free once the process is gone
+    if wait_for_exit(pid, Duration::from_secs(5))? {
+        Ok(())
+    } else {
+        Err(format!("Process {} did not exit after SIGKILL", pid))
+    }
+}
+
+/// Ask a process to exit with SIGTERM and wait up to the timeout for it to do so
+/// With console_group set, the whole process group gets the signal, like Ctrl+C in its terminal
+pub fn terminate_gracefully(
+    pid: u32,
+    timeout: Duration,
+    console_group: bool,
+) -> Result<(), String> {
+    let sent = if console_group {
+        let group = process_group(pid)
+            .ok_or_else(|| format!("Failed to get the process group of PID {}", pid))?;
+        send_signal_to_group(group, libc::SIGTERM)
+    } else {
+        send_signal(pid, libc::SIGTERM)
+    };
+    sent.map_err(|e| format!("Failed to send SIGTERM to process {}: {}", pid, e))?;
+
+    if wait_for_exit(pid, timeout)? {
+        Ok(())
+    } else {
+        Err(format!(
+            "Process {} did not exit within {} seconds of SIGTERM; use --sigkill to force termination",
+            pid,
+            timeout.as_secs()
+        ))
+    }
+}
+
+/// Check whether a process is still running
+/// A zombie has exited and only waits for its parent to collect it, so it does not count
+pub fn is_running(pid: u32) -> bool {
+    match stat_field(pid, 3) {
+        Some(state) => state != "Z" && state != "X",
+        None => false,
+    }
+}
+
+/// Wait until a process has exited
+/// Returns false when it is still running after the timeout
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> Result<bool, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if !is_running(pid) {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+/// Send a signal to one process
+/// kill(2) reads PID 0 as the caller's own process group, so it is refused
+fn send_signal(pid: u32, signal: libc::c_int) -> io::Result<()> {
+    if pid == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PID 0 is not a process",
+        ));
+    }
+    let pid =
+        libc::pid_t::try_from(pid).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+    // SAFETY: kill(2) only reads its arguments
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Send a signal to every process in a process group
+/// Group 0 is the caller's own and group 1 is init's, so neither is signalled
+fn send_signal_to_group(group: u32, signal: libc::c_int) -> io::Result<()> {
+    if group <= 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to signal process group {}", group),
+        ));
+    }
+    let group =
+        libc::pid_t::try_from(group).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+    // SAFETY: killpg(2) only reads its arguments
+    if unsafe { libc::killpg(group, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Get the process group of a process
+fn process_group(pid: u32) -> Option<u32> {
+    stat_field(pid, 5)?.parse().ok()
+}
+
+/// Get when a process was started, in clock ticks since boot
+fn start_time(pid: u32) -> Option<u64> {
+    stat_field(pid, 22)?.parse().ok()
+}
+
+/// Read one field of /proc/<pid>/stat, numbered from 1 as in proc(5)
+fn stat_field(pid: u32, field: usize) -> Option<String> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_stat_field(&stat, field)
+}
+
+/// Get one field of a stat line
+/// The name in field 2 can contain spaces and parentheses, so fields after it
+/// are counted from the last closing parenthesis
+fn parse_stat_field(stat: &str, field: usize) -> Option<String> {
+    let (head, rest) = stat.rsplit_once(')')?;
+    match field {
+        1 => head.split_whitespace().next().map(str::to_string),
+        2 => head.split_once('(').map(|(_, name)| name.to_string()),
+        _ => rest
+            .split_whitespace()
+            .nth(field.checked_sub(3)?)
+            .map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_service::DEFAULT_EXIT_CODE;
+    use std::process::{Command, Stdio};
+
+    fn sleeper() -> std::process::Child {
+        Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_stat_field_with_awkward_name() {
+        let stat = "4242 (my (odd) name) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000";
+        assert_eq!(parse_stat_field(stat, 1).as_deref(), Some("4242"));
+        assert_eq!(parse_stat_field(stat, 2).as_deref(), Some("my (odd) name"));
+        assert_eq!(parse_stat_field(stat, 3).as_deref(), Some("S"));
+        assert_eq!(parse_stat_field(stat, 4).as_deref(), Some("1"));
+        assert_eq!(parse_stat_field(stat, 22).as_deref(), Some("987654"));
+        assert_eq!(parse_stat_field("garbage", 3), None);
+    }
+
+    #[test]
+    fn test_current_process_lookups() {
+        let pid = std::process::id();
+        assert!(is_running(pid));
+        assert!(get_process_path(pid).unwrap().starts_with('/'));
+        assert!(!get_process_name(pid).unwrap().is_empty());
+        assert!(get_parent_pid(pid).is_some());
+    }
+
+    #[test]
+    fn test_lookups_with_invalid_pid() {
+        let invalid_pid = 99_999_999;
+        assert!(!is_running(invalid_pid));
+        assert!(get_process_name(invalid_pid).is_err());
+        assert_eq!(get_parent_pid(invalid_pid), None);
+        assert!(kill_process(invalid_pid, DEFAULT_EXIT_CODE).is_err());
+    }
+
+    #[test]
+    fn test_signals_refuse_own_group() {
+        // kill(0, ...) and killpg(0, ...) would reach this test's own process group
+        let err = kill_process(0, DEFAULT_EXIT_CODE).unwrap_err();
+        assert!(err.contains("PID 0 is not a process"), "{}", err);
+        assert!(terminate_gracefully(0, Duration::from_secs(1), false).is_err());
+        assert_eq!(
+            send_signal_to_group(0, libc::SIGTERM).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            send_signal_to_group(1, libc::SIGTERM).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(is_running(std::process::id()));
+    }
+
+    #[test]
+    fn test_descendant_pids_include_child() {
+        let mut child = sleeper();
+        let descendants = descendant_pids(std::process::id()).unwrap();
+        assert!(descendants.contains(&child.id()));
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_kill_process_ends_child() {
+        let mut child = sleeper();
+        // The child stays a zombie until it is waited for, which is_running ignores
+        assert_eq!(kill_process(child.id(), DEFAULT_EXIT_CODE), Ok(()));
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_terminate_gracefully_ends_child() {
+        let mut child = sleeper();
+        let result = terminate_gracefully(child.id(), Duration::from_secs(5), false);
+        assert_eq!(result, Ok(()));
+        let _ = child.wait();
+    }
+}
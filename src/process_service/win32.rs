@@ -0,0 +1,912 @@
+// Win32 backend of process_service
+
+use super::ProcessManager;
+use std::env;
+use std::fs::File;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::{Error, Win32Error};
+use crate::process_tree;
+use crate::wmi;
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_ACCESS_DENIED, FILETIME, HANDLE, HWND, LPARAM, WAIT_OBJECT_0, WAIT_TIMEOUT,
+    WPARAM,
+};
+use windows::Win32::Storage::FileSystem::QueryDosDeviceW;
+use windows::Win32::System::Console::{
+    AttachConsole, CTRL_BREAK_EVENT, CTRL_C_EVENT, FreeConsole, GenerateConsoleCtrlEvent,
+    SetConsoleCtrlHandler,
+};
+use windows::Win32::System::Diagnostics::Debug::{
+    MiniDumpWithFullMemory, MiniDumpWithHandleData, MiniDumpWithThreadInfo,
+    MiniDumpWithUnloadedModules, MiniDumpWriteDump,
+};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::ProcessStatus::GetProcessImageFileNameW;
+use windows::Win32::System::Threading::{
+    DETACHED_PROCESS, GetProcessInformation, GetProcessTimes, OpenProcess, PROCESS_ACCESS_RIGHTS,
+    PROCESS_NAME_WIN32, PROCESS_PROTECTION_LEVEL, PROCESS_PROTECTION_LEVEL_INFORMATION,
+    PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE,
+    PROCESS_TERMINATE, PROCESS_VM_READ, PROTECTION_LEVEL_ANTIMALWARE_LIGHT,
+    PROTECTION_LEVEL_AUTHENTICODE, PROTECTION_LEVEL_CODEGEN_LIGHT, PROTECTION_LEVEL_LSA_LIGHT,
+    PROTECTION_LEVEL_NONE, PROTECTION_LEVEL_PPL_APP, PROTECTION_LEVEL_WINDOWS,
+    PROTECTION_LEVEL_WINDOWS_LIGHT, PROTECTION_LEVEL_WINTCB, PROTECTION_LEVEL_WINTCB_LIGHT,
+    ProcessProtectionLevelInfo, QueryFullProcessImageNameW, TerminateProcess, WaitForSingleObject,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE,
+};
+use windows::core::{BOOL, HSTRING, PWSTR};
+
+/// Argument that starts evict as the console control helper
+pub const CONSOLE_CTRL_HELPER_FLAG: &str = "--console-ctrl-helper";
+
+/// Works on real processes through the Win32 API
+pub struct Win32ProcessManager;
+
+impl ProcessManager for Win32ProcessManager {
+    fn process_name(&self, pid: u32) -> Result<String, String> {
+        get_process_name(pid)
+    }
+
+    fn protection_level(&self, pid: u32) -> Option<&'static str> {
+        get_protection_level(pid)
+    }
+
+    fn kill(&self, pid: u32, exit_code: u32) -> Result<(), String> {
+        kill_process(pid, exit_code)
+    }
+
+    fn terminate_gracefully(
+        &self,
+        pid: u32,
+        timeout: Duration,
+        console_group: bool,
+    ) -> Result<(), String> {
+        terminate_gracefully(pid, timeout, console_group)
+    }
+
+    fn is_running(&self, pid: u32) -> bool {
+        is_running(pid)
+    }
+}
+
+/// Get the process name for a given PID
+/// Uses OpenProcess and QueryFullProcessImageNameW to retrieve the full path,
+/// then extracts just the filename
+///
+/// When full query access is denied, falls back in order through a limited
+/// information query, GetProcessImageFileNameW, a Toolhelp snapshot and WMI,
+/// so the owner can be named even when terminating it needs elevation
+pub fn get_process_name(pid: u32) -> Result<String, String> {
+    lookup_process_name(pid).map_err(|err| err.message)
+}
+
+/// get_process_name, keeping the kind and Win32 code of a failure for the command line
+#[doc(hidden)]
+pub fn lookup_process_name(pid: u32) -> Result<String, Error> {
+    let error = match query_image_path(pid, PROCESS_QUERY_INFORMATION) {
+        Ok(path) => return file_name(&path).map_err(Error::from),
+        Err(err) => err,
+    };
+
+    // Only access problems are worth retrying; a missing process stays missing
+    if error.code() != ERROR_ACCESS_DENIED.to_hresult() {
+        return Err(Error::win32(
+            format!("Failed to open process {}", pid),
+            Win32Error::from(error),
+        ));
+    }
+
+    let fallbacks: [fn(u32) -> Option<String>; 4] = [
+        |pid| query_image_path(pid, PROCESS_QUERY_LIMITED_INFORMATION).ok(),
+        query_image_file_name,
+        snapshot_process_name,
+        wmi_process_name,
+    ];
+
+    fallbacks
+        .iter()
+        .find_map(|lookup| lookup(pid))
+        .and_then(|path| file_name(&path).ok())
+        .ok_or_else(|| {
+            Error::win32(
+                format!("Failed to open process {}", pid),
+                Win32Error::from(error),
+            )
+        })
+}
+
+/// Get the full path of a process's executable
+/// Falls back to GetProcessImageFileNameW when the image name query is denied,
+/// and to WMI when the process cannot be opened at all
+pub fn get_process_path(pid: u32) -> Result<String, String> {
+    query_image_path(pid, PROCESS_QUERY_LIMITED_INFORMATION)
+        .or_else(|error| query_image_file_name(pid).ok_or(error))
+        .or_else(|error| wmi_process_path(pid).ok_or(error))
+        .map_err(|e| format!("Failed to open process {}: {}", pid, Win32Error::from(e)))
+}
+
+/// Open a process with the given access and query its full image path
+fn query_image_path(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> windows::core::Result<String> {
+    unsafe {
+        let handle = OpenProcess(access, false, pid)?;
+
+        // Ensure handle is closed when we're done
+        let result = get_process_path_from_handle(handle);
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+/// Helper function to get the full image path from an open handle
+unsafe fn get_process_path_from_handle(handle: HANDLE) -> windows::core::Result<String> {
+    let mut buffer = vec![0u16; 1024];
+    let mut size = buffer.len() as u32;
+
+    // Query the full process image name
+    unsafe {
+        QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        )?;
+    }
+
+    // Convert from wide string to Rust String
+    let safe_slice = buffer.get(..size as usize).unwrap_or(&[]);
+    Ok(String::from_utf16_lossy(safe_slice))
+}
+
+/// Extract just the filename from a full path
+fn file_name(full_path: &str) -> Result<String, String> {
+    let filename = full_path
+        .split('\\')
+        .next_back()
+        .unwrap_or(full_path)
+        .to_string();
+
+    if filename.is_empty() {
+        return Err("Process name is empty".to_string());
+    }
+
+    Ok(filename)
+}
+
+/// Get the image path through GetProcessImageFileNameW
+/// The result is an NT device path, translated to a drive letter when possible
+fn query_image_file_name(pid: u32) -> Option<String> {
+    let mut buffer = vec![0u16; 1024];
+
+    let len = unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let len = GetProcessImageFileNameW(handle, &mut buffer);
+        let _ = CloseHandle(handle);
+        len
+    };
+
+    let device_path = String::from_utf16_lossy(buffer.get(..len as usize)?);
+    if device_path.is_empty() {
+        return None;
+    }
+
+    Some(device_path_to_dos(&device_path).unwrap_or(device_path))
+}
+
+/// Translate an NT device path (\Device\HarddiskVolume3\...) into a drive letter path
+fn device_path_to_dos(device_path: &str) -> Option<String> {
+    (b'A'..=b'Z').find_map(|letter| {
+        let drive = format!("{}:", letter as char);
+        let mut target = [0u16; 512];
+
+        let len = unsafe { QueryDosDeviceW(&HSTRING::from(drive.as_str()), Some(&mut target)) };
+        if len == 0 {
+            return None;
+        }
+
+        // The target is a list of NUL-terminated strings; the first one is the device
+        let end = target.iter().position(|&c| c == 0).unwrap_or(0);
+        let device = String::from_utf16_lossy(target.get(..end)?);
+        let rest = device_path.strip_prefix(device.as_str())?;
+
+        rest.starts_with('\\').then(|| format!("{}{}", drive, rest))
+    })
+}
+
+/// Get the executable name from a Toolhelp process snapshot
+/// The snapshot lists every process without opening any of them
+fn snapshot_process_name(pid: u32) -> Option<String> {
+    let entry = snapshot_entry(pid)?;
+    let len = entry
+        .szExeFile
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(entry.szExeFile.len());
+    entry
+        .szExeFile
+        .get(..len)
+        .map(String::from_utf16_lossy)
+        .filter(|name| !name.is_empty())
+}
+
+/// Get the PID of the process that started the given process
+/// The parent may have exited since, in which case its PID can have been reused
+pub fn get_parent_pid(pid: u32) -> Option<u32> {
+    snapshot_entry(pid)
+        .map(|entry| entry.th32ParentProcessID)
+        .filter(|&parent| parent != 0)
+}
+
+/// Check whether the process that started the given process has exited
+/// A parent PID that was reused by a newer process counts as exited too
+pub fn is_orphaned(pid: u32) -> bool {
+    let Some(parent) = get_parent_pid(pid) else {
+        return false;
+    };
+    if snapshot_entry(parent).is_none() {
+        return true;
+    }
+
+    match (creation_time(parent), creation_time(pid)) {
+        (Some(parent_started), Some(started)) => parent_started > started,
+        _ => false,
+    }
+}
+
+/// Get the PIDs of every process started under the given one, parents before children
+/// Children whose parent PID was reused by the given process are left out
+pub fn descendant_pids(pid: u32) -> Result<Vec<u32>, String> {
+    let processes = snapshot_parents()?;
+    Ok(process_tree::descendants(pid, &processes, creation_time))
+}
+
+/// List every process in a Toolhelp snapshot as (PID, parent PID) pairs
+fn snapshot_parents() -> Result<Vec<(u32, u32)>, String> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).map_err(|e| {
+            format!(
+                "Failed to take a snapshot of running processes: {}",
+                Win32Error::from(e)
+            )
+        })?;
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+
+        let mut processes = Vec::new();
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            processes.push((entry.th32ProcessID, entry.th32ParentProcessID));
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+
+        let _ = CloseHandle(snapshot);
+        Ok(processes)
+    }
+}
+
+/// Get when a process was started, in 100ns intervals since 1601
+fn creation_time(pid: u32) -> Option<u64> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        let result = GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+
+        Some((u64::from(creation.dwHighDateTime) << 32) | u64::from(creation.dwLowDateTime))
+    }
+}
+
+/// Find the Toolhelp snapshot entry of a process
+fn snapshot_entry(pid: u32) -> Option<PROCESSENTRY32W> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+
+        let mut result = None;
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            if entry.th32ProcessID == pid {
+                result = Some(entry);
+                break;
+            }
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+
+        let _ = CloseHandle(snapshot);
+        result
+    }
+}
+
+/// Get the process name from the Win32_Process WMI class
+fn wmi_process_name(pid: u32) -> Option<String> {
+    let wql = format!("SELECT Name FROM Win32_Process WHERE ProcessId = {}", pid);
+    let rows = wmi::query(wmi::CIMV2, &wql, &["Name"]).ok()?;
+    rows.into_iter().next()?.into_iter().next()?
+}
+
+/// Get the executable path from the Win32_Process WMI class
+fn wmi_process_path(pid: u32) -> Option<String> {
+    let wql = format!(
+        "SELECT ExecutablePath FROM Win32_Process WHERE ProcessId = {}",
+        pid
+    );
+    let rows = wmi::query(wmi::CIMV2, &wql, &["ExecutablePath"]).ok()?;
+    rows.into_iter().next()?.into_iter().next()?
+}
+
+/// Get the protection level of a protected process (PP or PPL)
+/// Returns None for ordinary processes, or when the level cannot be queried
+///
+/// Protected processes cannot be terminated by any user-mode caller, not
+/// even an elevated administrator
+pub fn get_protection_level(pid: u32) -> Option<&'static str> {
+    let mut info = PROCESS_PROTECTION_LEVEL_INFORMATION::default();
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let result = GetProcessInformation(
+            handle,
+            ProcessProtectionLevelInfo,
+            &mut info as *mut PROCESS_PROTECTION_LEVEL_INFORMATION as *mut _,
+            size_of::<PROCESS_PROTECTION_LEVEL_INFORMATION>() as u32,
+        );
+        let _ = CloseHandle(handle);
+        result.ok()?;
+    }
+
+    protection_level_name(info.ProtectionLevel)
+}
+
+/// Describe a protection level, or None when the process is not protected
+fn protection_level_name(level: PROCESS_PROTECTION_LEVEL) -> Option<&'static str> {
+    match level {
+        PROTECTION_LEVEL_NONE => None,
+        PROTECTION_LEVEL_ANTIMALWARE_LIGHT => Some("anti-malware (PPL)"),
+        PROTECTION_LEVEL_LSA_LIGHT => Some("LSA (PPL)"),
+        PROTECTION_LEVEL_WINDOWS_LIGHT => Some("Windows (PPL)"),
+        PROTECTION_LEVEL_WINTCB_LIGHT => Some("Windows TCB (PPL)"),
+        PROTECTION_LEVEL_CODEGEN_LIGHT => Some("code generation (PPL)"),
+        PROTECTION_LEVEL_PPL_APP => Some("app (PPL)"),
+        PROTECTION_LEVEL_WINDOWS => Some("Windows (PP)"),
+        PROTECTION_LEVEL_WINTCB => Some("Windows TCB (PP)"),
+        PROTECTION_LEVEL_AUTHENTICODE => Some("Authenticode (PP)"),
+        _ => Some("unknown protection level"),
+    }
+}
+
+/// Write a full-memory minidump of a process, for post-mortem analysis
+/// Fails if the file already exists, so earlier dumps are never overwritten
+pub fn write_minidump(pid: u32, path: &Path) -> Result<(), String> {
+    let file = File::create_new(path)
+        .map_err(|e| format!("Failed to create dump file {}: {}", path.display(), e))?;
+
+    let result = unsafe {
+        OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid)
+            .map_err(|e| {
+                format!(
+                    "Failed to open process {} for dumping: {}",
+                    pid,
+                    Win32Error::from(e)
+                )
+            })
+            .and_then(|handle| {
+                let written = MiniDumpWriteDump(
+                    handle,
+                    pid,
+                    HANDLE(file.as_raw_handle()),
+                    MiniDumpWithFullMemory
+                        | MiniDumpWithHandleData
+                        | MiniDumpWithThreadInfo
+                        | MiniDumpWithUnloadedModules,
+                    None,
+                    None,
+                    None,
+                )
+                .map_err(|e| {
+                    format!(
+                        "Failed to write dump of process {}: {}",
+                        pid,
+                        Win32Error::from(e)
+                    )
+                });
+                let _ = CloseHandle(handle);
+                written
+            })
+    };
+
+    // Don't leave a truncated dump behind
+    if result.is_err() {
+        drop(file);
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Terminate a process forcefully
+/// Uses TerminateProcess with the given exit code to force termination
+pub fn kill_process(pid: u32, exit_code: u32) -> Result<(), String> {
+    terminate_process(pid, exit_code).map_err(|err| err.message)
+}
+
+/// kill_process, keeping the kind and Win32 code of a failure for the command line
+#[doc(hidden)]
+pub fn terminate_process(pid: u32, exit_code: u32) -> Result<(), Error> {
+    unsafe {
+        // Open process with terminate access
+        let handle = OpenProcess(PROCESS_TERMINATE, false, pid).map_err(|e| {
+            Error::win32(
+                format!("Failed to open process {} for termination", pid),
+                Win32Error::from(e),
+            )
+        })?;
+
+        // Terminate the process with the requested exit code
+        let result = TerminateProcess(handle, exit_code).map_err(|e| {
+            Error::win32(
+                format!("Failed to terminate process {}", pid),
+                Win32Error::from(e),
+            )
+        });
+
+        // Close the handle
+        let _ = CloseHandle(handle);
+
+        result
+    }
+}
+
+/// Ask a process to exit and wait for it to do so
+/// Posts WM_CLOSE to its top-level windows and sends Ctrl+Break to its console,
+/// then waits up to the given timeout
+///
+/// With console_group set, Ctrl+Break goes to every process attached to the
+/// target's console, so the shell that started it can clean up its children too
+pub fn terminate_gracefully(
+    pid: u32,
+    timeout: Duration,
+    console_group: bool,
+) -> Result<(), String> {
+    let windows_closed = close_windows(pid);
+    // Group 0 addresses every process sharing the console
+    let group = if console_group { 0 } else { pid };
+    let console_signaled = send_console_ctrl(pid, group, CTRL_BREAK_EVENT).is_ok();
+
+    if windows_closed == 0 && !console_signaled {
+        return Err(format!(
+            "Process {} has no window or console to ask for a graceful exit; use --sigkill to force termination",
+            pid
+        ));
+    }
+
+    wait_after_request(pid, timeout)
+}
+
+/// Post WM_CLOSE to a process's top-level windows and wait for it to exit
+/// Unlike terminate_gracefully, the console is left alone
+pub fn close_windows_and_wait(pid: u32, timeout: Duration) -> Result<(), String> {
+    if close_windows(pid) == 0 {
+        return Err(format!("Process {} has no top-level window to close", pid));
+    }
+    wait_after_request(pid, timeout)
+}
+
+/// Send Ctrl+Break to a process's console and wait for it to exit
+/// Unlike terminate_gracefully, no windows are closed
+pub fn ctrl_break_and_wait(pid: u32, timeout: Duration, console_group: bool) -> Result<(), String> {
+    let group = if console_group { 0 } else { pid };
+    send_console_ctrl(pid, group, CTRL_BREAK_EVENT)?;
+    wait_after_request(pid, timeout)
+}
+
+/// Helper function to wait for a process that was asked to exit
+fn wait_after_request(pid: u32, timeout: Duration) -> Result<(), String> {
+    if wait_for_exit(pid, timeout)? {
+        Ok(())
+    } else {
+        Err(format!(
+            "Process {} did not exit within {} seconds of the graceful request; use --sigkill to force termination",
+            pid,
+            timeout.as_secs()
+        ))
+    }
+}
+
+/// Check whether a process is still running
+/// A process that cannot be opened for lack of access is assumed to be running
+pub fn is_running(pid: u32) -> bool {
+    unsafe {
+        match OpenProcess(PROCESS_SYNCHRONIZE, false, pid) {
+            Ok(handle) => {
+                // An exited process stays signaled while handles to it remain open
+                let result = WaitForSingleObject(handle, 0);
+                let _ = CloseHandle(handle);
+                result == WAIT_TIMEOUT
+            }
+            Err(err) => err.code() == ERROR_ACCESS_DENIED.to_hresult(),
+        }
+    }
+}
+
+/// Wait for a process to exit
+/// Returns true if it exited within the timeout
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> Result<bool, String> {
+    unsafe {
+        let handle = match OpenProcess(PROCESS_SYNCHRONIZE, false, pid) {
+            Ok(handle) => handle,
+            // The process is already gone
+            Err(_) if get_process_name(pid).is_err() => return Ok(true),
+            Err(err) => {
+                return Err(format!(
+                    "Failed to open process {} to wait for it: {}",
+                    pid,
+                    Win32Error::from(err)
+                ));
+            }
+        };
+
+        let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let result = WaitForSingleObject(handle, millis);
+        let _ = CloseHandle(handle);
+
+        Ok(result == WAIT_OBJECT_0)
+    }
+}
+
+/// Post WM_CLOSE to every top-level window owned by a process
+/// Returns the number of windows that were asked to close
+fn close_windows(pid: u32) -> usize {
+    let mut search = WindowSearch {
+        pid,
+        windows: Vec::new(),
+    };
+
+    unsafe {
+        let _ = EnumWindows(
+            Some(collect_process_windows),
+            LPARAM(&mut search as *mut WindowSearch as isize),
+        );
+    }
+
+    search
+        .windows
+        .iter()
+        .filter(|hwnd| unsafe {
+            PostMessageW(Some(**hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)).is_ok()
+        })
+        .count()
+}
+
+/// State shared with the EnumWindows callback
+struct WindowSearch {
+    pid: u32,
+    windows: Vec<HWND>,
+}
+
+/// EnumWindows callback collecting the windows that belong to the searched PID
+unsafe extern "system" fn collect_process_windows(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = unsafe { &mut *(lparam.0 as *mut WindowSearch) };
+
+    let mut owner: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut owner)) };
+    if owner == search.pid {
+        search.windows.push(hwnd);
+    }
+
+    // Keep enumerating
+    true.into()
+}
+
+/// Send a console control event (Ctrl+C or Ctrl+Break) to a console process group
+///
+/// A process can only signal the console it is attached to, so a detached
+/// copy of evict attaches to the target's console and raises the event there,
+/// leaving our own console untouched
+pub fn send_console_ctrl(pid: u32, group: u32, event: u32) -> Result<(), String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to locate evict: {}", e))?;
+
+    let status = Command::new(exe)
+        .args([
+            CONSOLE_CTRL_HELPER_FLAG,
+            &pid.to_string(),
+            &group.to_string(),
+            &event.to_string(),
+        ])
+        .creation_flags(DETACHED_PROCESS.0)
+        .status()
+        .map_err(|e| format!("Failed to start console control helper: {}", e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Process {} has no console to signal", pid))
+    }
+}
+
+/// Entry point of the console control helper started by send_console_ctrl
+/// Returns the helper's exit code: 0 when the event was delivered
+pub fn run_console_ctrl_helper(pid: u32, group: u32, event: u32) -> i32 {
+    unsafe {
+        let _ = FreeConsole();
+        if AttachConsole(pid).is_err() {
+            return 1;
+        }
+
+        // Ignore the event ourselves, since we now share the target's console
+        let _ = SetConsoleCtrlHandler(Some(ignore_console_ctrl), true);
+        let delivered = GenerateConsoleCtrlEvent(event, group).is_ok();
+        let _ = FreeConsole();
+
+        if delivered { 0 } else { 1 }
+    }
+}
+
+/// Send Ctrl+C to every process attached to a process's console
+///
+/// Ctrl+C cannot be aimed at a single process group, so this is the closest
+/// equivalent of SIGINT: console apps such as node treat it as an interrupt
+pub fn send_ctrl_c(pid: u32) -> Result<(), String> {
+    send_console_ctrl(pid, 0, CTRL_C_EVENT)
+}
+
+/// Console control handler that swallows every event
+/// SetConsoleCtrlHandler(None, ..) only ignores Ctrl+C, not Ctrl+Break
+unsafe extern "system" fn ignore_console_ctrl(_event: u32) -> BOOL {
+    true.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_service::DEFAULT_EXIT_CODE;
+    use proptest::prelude::*;
+
+    // **Feature: port-killer, Property 2: Process name retrieval succeeds for valid PIDs**
+    // **Validates: Requirements 1.2**
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_process_name_retrieval_for_valid_pids(pid in 1u32..=10000u32) {
+            // Try to get process name - it should either succeed with a non-empty string
+            // or fail with an error (process doesn't exist or no permission)
+            // The property is that it doesn't crash or hang
+            let result = get_process_name(pid);
+
+            // If successful, name should be non-empty
+            if let Ok(name) = result {
+                prop_assert!(!name.is_empty(), "Process name should not be empty for PID {}", pid);
+            }
+            // If it fails, that's also acceptable (process might not exist)
+            // The key is that the function returns without crashing
+        }
+    }
+
+    // Unit tests for process operations
+    // Requirements: 1.2
+
+    #[test]
+    fn test_get_process_name_with_current_process() {
+        // Get current process PID
+        let current_pid = std::process::id();
+
+        // Should successfully get the process name
+        let result = get_process_name(current_pid);
+        assert!(result.is_ok(), "Should get name for current process");
+
+        let name = result.unwrap();
+        assert!(!name.is_empty(), "Process name should not be empty");
+        // Current process should be evict.exe or similar
+        assert!(
+            name.ends_with(".exe"),
+            "Process name should end with .exe on Windows"
+        );
+    }
+
+    #[test]
+    fn test_get_process_name_with_invalid_pid() {
+        // Use a very high PID that's unlikely to exist
+        let invalid_pid = 9999999u32;
+
+        // Should return an error
+        let result = get_process_name(invalid_pid);
+        assert!(result.is_err(), "Should fail for invalid PID");
+    }
+
+    #[test]
+    fn test_file_name_extracts_last_component() {
+        assert_eq!(
+            file_name("C:\\Program Files\\nodejs\\node.exe"),
+            Ok("node.exe".to_string())
+        );
+        assert_eq!(file_name("node.exe"), Ok("node.exe".to_string()));
+        assert!(file_name("C:\\tools\\").is_err());
+    }
+
+    #[test]
+    fn test_fallbacks_name_current_process() {
+        let pid = std::process::id();
+        let expected = get_process_name(pid).unwrap();
+
+        let limited = query_image_path(pid, PROCESS_QUERY_LIMITED_INFORMATION).unwrap();
+        assert_eq!(file_name(&limited).unwrap(), expected);
+
+        let image = query_image_file_name(pid).unwrap();
+        assert!(image.ends_with(&expected));
+        assert!(
+            !image.starts_with("\\Device\\"),
+            "Device path should be translated"
+        );
+
+        assert_eq!(snapshot_process_name(pid), Some(expected.clone()));
+        assert_eq!(wmi_process_name(pid), Some(expected));
+    }
+
+    #[test]
+    fn test_snapshot_process_name_with_invalid_pid() {
+        assert_eq!(snapshot_process_name(9999999), None);
+    }
+
+    #[test]
+    fn test_get_parent_pid_of_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "ping", "-n", "3", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        assert_eq!(get_parent_pid(child.id()), Some(std::process::id()));
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_descendant_pids_include_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "ping", "-n", "3", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let descendants = descendant_pids(std::process::id()).unwrap();
+        assert!(descendants.contains(&child.id()));
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_get_parent_pid_with_invalid_pid() {
+        assert_eq!(get_parent_pid(9999999), None);
+    }
+
+    #[test]
+    fn test_child_with_running_parent_is_not_orphaned() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "ping", "-n", "3", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        assert!(!is_orphaned(child.id()));
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_is_orphaned_with_invalid_pid() {
+        assert!(!is_orphaned(9999999));
+    }
+
+    #[test]
+    fn test_current_process_is_not_protected() {
+        assert_eq!(get_protection_level(std::process::id()), None);
+    }
+
+    #[test]
+    fn test_protection_level_names() {
+        assert_eq!(protection_level_name(PROTECTION_LEVEL_NONE), None);
+        assert_eq!(
+            protection_level_name(PROTECTION_LEVEL_ANTIMALWARE_LIGHT),
+            Some("anti-malware (PPL)")
+        );
+        assert_eq!(
+            protection_level_name(PROTECTION_LEVEL_WINTCB),
+            Some("Windows TCB (PP)")
+        );
+    }
+
+    #[test]
+    fn test_get_process_path_of_current_process() {
+        let path = get_process_path(std::process::id()).unwrap();
+        let expected = std::env::current_exe().unwrap();
+        assert!(path.eq_ignore_ascii_case(&expected.to_string_lossy()));
+        assert!(get_process_path(9999999).is_err());
+    }
+
+    #[test]
+    fn test_is_running() {
+        assert!(is_running(std::process::id()));
+        assert!(!is_running(9999999));
+        assert!(!is_running(0));
+
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "exit", "0"])
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        // The Child still holds a handle, so the PID cannot have been reused yet
+        assert!(!is_running(pid));
+    }
+
+    #[test]
+    fn test_wait_for_exit_with_invalid_pid() {
+        // A process that does not exist has nothing left to wait for
+        assert_eq!(wait_for_exit(9999999, Duration::from_millis(10)), Ok(true));
+    }
+
+    #[test]
+    fn test_wait_for_exit_times_out_for_running_process() {
+        let result = wait_for_exit(std::process::id(), Duration::from_millis(10));
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn test_terminate_gracefully_with_invalid_pid() {
+        let result = terminate_gracefully(9999999, Duration::from_millis(10), false);
+        assert!(result.is_err(), "Nothing can be signaled for invalid PID");
+    }
+
+    #[test]
+    fn test_write_minidump_of_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/C", "ping", "-n", "3", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let path = env::temp_dir().join(format!("evict-test-{}.dmp", child.id()));
+        let result = write_minidump(child.id(), &path);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert!(result.is_ok());
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_write_minidump_with_invalid_pid_leaves_no_file() {
+        let path = env::temp_dir().join("evict-test-invalid.dmp");
+        assert!(write_minidump(9999999, &path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_kill_process_with_invalid_pid() {
+        // Use a very high PID that's unlikely to exist
+        let invalid_pid = 9999999u32;
+
+        // Should return an error
+        let result = kill_process(invalid_pid, DEFAULT_EXIT_CODE);
+        assert!(result.is_err(), "Should fail to kill invalid PID");
+    }
+}
@@ -0,0 +1,71 @@
+// Process tree module for finding the processes started under a port's owner
+
+use std::collections::VecDeque;
+
+/// Find every process started under the root, parents before their children
+///
+/// `processes` holds (PID, parent PID) pairs. Windows keeps the parent PID of
+/// a process after its parent exits, so the PID may have been reused: a child
+/// that started before its parent belongs to an earlier process and is left
+/// out. Processes whose start time cannot be read are kept
+pub fn descendants(
+    root: u32,
+    processes: &[(u32, u32)],
+    started: impl Fn(u32) -> Option<u64>,
+) -> Vec<u32> {
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(parent) = queue.pop_front() {
+        let parent_started = started(parent);
+        for &(pid, _) in processes
+            .iter()
+            .filter(|&&(pid, parent_pid)| parent_pid == parent && pid != parent)
+        {
+            if pid == root || found.contains(&pid) {
+                continue;
+            }
+            let reused = match (parent_started, started(pid)) {
+                (Some(parent_started), Some(child_started)) => child_started < parent_started,
+                _ => false,
+            };
+            if !reused {
+                found.push(pid);
+                queue.push_back(pid);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendants_lists_parents_first() {
+        // 10 -> 20 -> 40, 10 -> 30, unrelated 50 -> 60
+        let processes = [(20, 10), (40, 20), (30, 10), (60, 50), (10, 1)];
+        assert_eq!(descendants(10, &processes, |_| None), vec![20, 30, 40]);
+        assert_eq!(descendants(40, &processes, |_| None), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_descendants_skips_reused_parent_pids() {
+        // 30 names 10 as its parent but is older, so its real parent is gone
+        let processes = [(20, 10), (30, 10)];
+        let started = |pid| match pid {
+            10 => Some(500),
+            20 => Some(600),
+            30 => Some(100),
+            _ => None,
+        };
+        assert_eq!(descendants(10, &processes, started), vec![20]);
+    }
+
+    #[test]
+    fn test_descendants_survives_cycles() {
+        let processes = [(20, 10), (10, 20), (0, 0)];
+        assert_eq!(descendants(10, &processes, |_| None), vec![20]);
+        assert_eq!(descendants(0, &processes, |_| None), Vec::<u32>::new());
+    }
+}
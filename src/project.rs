@@ -0,0 +1,228 @@
+// Project module for matching processes to the current project directory
+
+use crate::port_service::PortBinding;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// A process that holds the requested port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub pid: u32,
+    pub name: String,
+    /// Whether its working directory or command line points into the current project
+    pub in_project: bool,
+    /// Local addresses it holds the port on, e.g. 0.0.0.0 and :: for a dual-stack server
+    pub addresses: Vec<IpAddr>,
+}
+
+/// A process listening inside the current project, with the ports it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectListener {
+    pub pid: u32,
+    pub name: String,
+    pub ports: Vec<u16>,
+    /// Whether the process that started it has exited
+    pub orphaned: bool,
+}
+
+/// Collect the distinct local addresses a process holds in the given bindings, sorted
+/// Several sockets of one process on one port are a single logical binding
+pub fn addresses_of(bindings: &[PortBinding], pid: u32) -> Vec<IpAddr> {
+    let mut addresses: Vec<IpAddr> = bindings
+        .iter()
+        .filter(|binding| binding.pid == pid)
+        .map(|binding| binding.address)
+        .collect();
+    addresses.sort_unstable();
+    addresses.dedup();
+    addresses
+}
+
+/// Group listening sockets by owning process, with each process's ports sorted
+/// PID 0 (the idle process) is skipped since it only shows up for stale rows
+pub fn ports_by_process(bindings: &[PortBinding]) -> BTreeMap<u32, Vec<u16>> {
+    let mut processes: BTreeMap<u32, Vec<u16>> = BTreeMap::new();
+    for binding in bindings.iter().filter(|binding| binding.pid != 0) {
+        let ports = processes.entry(binding.pid).or_default();
+        if !ports.contains(&binding.port) {
+            ports.push(binding.port);
+        }
+    }
+    for ports in processes.values_mut() {
+        ports.sort_unstable();
+    }
+    processes
+}
+
+/// Pick the process to target among several holding the same port
+/// The first one belonging to the current project wins, otherwise the first one found
+pub fn preferred(candidates: &[Candidate]) -> Option<&Candidate> {
+    candidates
+        .iter()
+        .find(|candidate| candidate.in_project)
+        .or_else(|| candidates.first())
+}
+
+/// Find the project a directory belongs to
+/// Uses the enclosing git repository root, or the directory itself outside a repository
+pub fn project_root(directory: &Path) -> PathBuf {
+    directory
+        .ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(directory)
+        .to_path_buf()
+}
+
+/// Check whether a path lies inside the project root
+/// Windows paths compare case-insensitively and ignore the separator style
+pub fn path_in_project(root: &Path, path: &Path) -> bool {
+    let root = normalize(&root.to_string_lossy());
+    let path = normalize(&path.to_string_lossy());
+
+    path == root || path.starts_with(&format!("{}/", root))
+}
+
+/// Check whether a command line mentions a path inside the project root
+pub fn command_line_in_project(root: &Path, command_line: &str) -> bool {
+    let root = normalize(&root.to_string_lossy());
+    let command_line = normalize(command_line);
+
+    // Require a separator, quote or the end after the root so /app doesn't match /apple
+    command_line.match_indices(&root).any(|(index, _)| {
+        matches!(
+            command_line
+                .get(index + root.len()..)
+                .and_then(|rest| rest.chars().next()),
+            None | Some('/' | '"' | '\'' | ' ')
+        )
+    })
+}
+
+/// Lowercase a path, use forward slashes and drop any trailing separator
+pub fn normalize(path: &str) -> String {
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(pid: u32, in_project: bool) -> Candidate {
+        Candidate {
+            pid,
+            name: "node.exe".to_string(),
+            in_project,
+            addresses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_preferred_picks_project_process() {
+        let candidates = [
+            candidate(10, false),
+            candidate(20, true),
+            candidate(30, true),
+        ];
+        assert_eq!(preferred(&candidates).unwrap().pid, 20);
+    }
+
+    #[test]
+    fn test_preferred_falls_back_to_first() {
+        let candidates = [candidate(10, false), candidate(20, false)];
+        assert_eq!(preferred(&candidates).unwrap().pid, 10);
+        assert_eq!(preferred(&[]), None);
+    }
+
+    fn binding(pid: u32, port: u16, address: &str) -> PortBinding {
+        PortBinding {
+            pid,
+            port,
+            address: address.parse().unwrap(),
+            state: Some(crate::port_service::TcpState::Listen),
+        }
+    }
+
+    #[test]
+    fn test_addresses_of_merges_duplicate_sockets() {
+        let bindings = [
+            binding(7, 3000, "::"),
+            binding(7, 3000, "0.0.0.0"),
+            binding(7, 3000, "0.0.0.0"),
+            binding(8, 3000, "127.0.0.1"),
+        ];
+        let addresses: Vec<String> = addresses_of(&bindings, 7)
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        assert_eq!(addresses, vec!["0.0.0.0", "::"]);
+        assert!(addresses_of(&bindings, 9).is_empty());
+    }
+
+    #[test]
+    fn test_ports_by_process() {
+        let bindings = [
+            binding(7, 5173, "0.0.0.0"),
+            binding(7, 3000, "0.0.0.0"),
+            binding(7, 3000, "::"),
+            binding(0, 80, "0.0.0.0"),
+            binding(4, 445, "0.0.0.0"),
+        ];
+        let processes = ports_by_process(&bindings);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[&7], vec![3000, 5173]);
+        assert_eq!(processes[&4], vec![445]);
+    }
+
+    #[test]
+    fn test_project_root_finds_repository() {
+        let root = std::env::temp_dir().join(format!("evict-project-{}", std::process::id()));
+        let nested = root.join("src").join("bin");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+
+        assert_eq!(project_root(&nested), root);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_project_root_outside_repository() {
+        let directory = std::env::temp_dir().join(format!("evict-plain-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        // The temp directory itself is normally not inside a repository
+        if !directory.ancestors().any(|a| a.join(".git").exists()) {
+            assert_eq!(project_root(&directory), directory);
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_path_in_project() {
+        let root = Path::new(r"C:\Code\Shop");
+        assert!(path_in_project(root, Path::new(r"c:\code\shop")));
+        assert!(path_in_project(root, Path::new(r"C:\Code\Shop\web\")));
+        assert!(path_in_project(root, Path::new(r"\\?\C:\Code\Shop\api")));
+        assert!(!path_in_project(root, Path::new(r"C:\Code\Shopfront")));
+        assert!(!path_in_project(root, Path::new(r"C:\Code")));
+    }
+
+    #[test]
+    fn test_command_line_in_project() {
+        let root = Path::new(r"C:\Code\Shop");
+        assert!(command_line_in_project(
+            root,
+            r#""C:\Program Files\nodejs\node.exe" C:\Code\Shop\node_modules\vite\bin\vite.js"#
+        ));
+        assert!(command_line_in_project(
+            root,
+            r#"dotnet run --project "c:/code/shop""#
+        ));
+        assert!(!command_line_in_project(
+            root,
+            r"node C:\Code\Shopfront\server.js"
+        ));
+        assert!(!command_line_in_project(root, "node server.js"));
+    }
+}
@@ -0,0 +1,167 @@
+// Respawn module for detecting owners that will come back after termination
+
+use crate::process_info;
+use crate::process_service;
+use crate::service_control::{self, ServiceInfo};
+
+/// Service that runs scheduled tasks
+const TASK_SCHEDULER_SERVICE: &str = "Schedule";
+
+/// Hosts that run task actions on behalf of the Task Scheduler
+const TASK_HOSTS: [&str; 2] = ["taskhostw.exe", "taskeng.exe"];
+
+/// How many ancestors are checked for a process manager
+const MAX_ANCESTORS: usize = 8;
+
+/// Process managers that start their command again after it exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Supervisor {
+    Pm2,
+    Nodemon,
+}
+
+/// Explain why a process is likely to come back after it is terminated
+/// Returns one warning per reason, or an empty list when nothing restarts it
+pub fn respawn_warnings(pid: u32, services: &[ServiceInfo]) -> Vec<String> {
+    let mut warnings: Vec<String> = services
+        .iter()
+        .filter_map(|service| {
+            let delay = service_control::restart_delay(&service.name).ok()??;
+            Some(format!(
+                "The '{}' service restarts automatically {} after it fails; stop it with `sc stop {}` or change its recovery actions",
+                service.display_name,
+                describe_delay(delay),
+                service.name
+            ))
+        })
+        .collect();
+
+    if launched_by_task_scheduler(pid) {
+        warnings.push(
+            "The process was started by the Task Scheduler and may be started again by its trigger; disable the task to keep the port free"
+                .to_string(),
+        );
+    }
+
+    if let Some((supervisor, supervisor_pid)) = find_supervisor(pid) {
+        warnings.push(supervisor_warning(supervisor, supervisor_pid));
+    }
+
+    warnings
+}
+
+/// Find the closest ancestor that is a process manager
+/// Stops at the first parent that has exited, since its PID may belong to another process now
+fn find_supervisor(pid: u32) -> Option<(Supervisor, u32)> {
+    let mut current = pid;
+    for _ in 0..MAX_ANCESTORS {
+        if process_service::is_orphaned(current) {
+            return None;
+        }
+        let parent = process_service::get_parent_pid(current)?;
+        if let Some(supervisor) = process_info::command_line(parent)
+            .ok()
+            .and_then(|command_line| recognize_supervisor(&command_line))
+        {
+            return Some((supervisor, parent));
+        }
+        current = parent;
+    }
+    None
+}
+
+/// Recognize a process manager from its command line
+/// Both run as node.exe, so the script they run is what tells them apart
+fn recognize_supervisor(command_line: &str) -> Option<Supervisor> {
+    let command_line = command_line.to_ascii_lowercase();
+    if command_line.contains("pm2") {
+        Some(Supervisor::Pm2)
+    } else if command_line.contains("nodemon") {
+        Some(Supervisor::Nodemon)
+    } else {
+        None
+    }
+}
+
+/// Explain how a process manager brings the process back and how to stop it
+fn supervisor_warning(supervisor: Supervisor, pid: u32) -> String {
+    match supervisor {
+        Supervisor::Pm2 => format!(
+            "The process is managed by pm2 (PID {}), which restarts it when it exits; stop it with `pm2 stop <app>` or `pm2 stop all` instead",
+            pid
+        ),
+        Supervisor::Nodemon => format!(
+            "The process was started by nodemon (PID {}), which starts it again on the next file change; stop nodemon itself to keep the port free",
+            pid
+        ),
+    }
+}
+
+/// Check whether a process was started by the Task Scheduler
+fn launched_by_task_scheduler(pid: u32) -> bool {
+    let Some(parent) = process_service::get_parent_pid(pid) else {
+        return false;
+    };
+
+    let parent_is_task_host = process_service::get_process_name(parent).is_ok_and(|name| {
+        TASK_HOSTS
+            .iter()
+            .any(|host| name.eq_ignore_ascii_case(host))
+    });
+
+    parent_is_task_host
+        || service_control::find_services_by_pid(parent).is_ok_and(|services| {
+            services
+                .iter()
+                .any(|service| service.name.eq_ignore_ascii_case(TASK_SCHEDULER_SERVICE))
+        })
+}
+
+/// Describe a restart delay in milliseconds
+fn describe_delay(millis: u32) -> String {
+    match millis / 1000 {
+        0 => "immediately".to_string(),
+        1 => "1 second".to_string(),
+        secs => format!("{} seconds", secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_delay() {
+        assert_eq!(describe_delay(0), "immediately");
+        assert_eq!(describe_delay(999), "immediately");
+        assert_eq!(describe_delay(1000), "1 second");
+        assert_eq!(describe_delay(60000), "60 seconds");
+    }
+
+    #[test]
+    fn test_recognize_supervisor() {
+        assert_eq!(
+            recognize_supervisor(
+                r#"node "C:\Users\dev\AppData\Roaming\npm\node_modules\pm2\lib\Daemon.js""#
+            ),
+            Some(Supervisor::Pm2)
+        );
+        assert_eq!(
+            recognize_supervisor(r#"node C:\app\node_modules\nodemon\bin\nodemon.js server.js"#),
+            Some(Supervisor::Nodemon)
+        );
+        assert_eq!(recognize_supervisor("node server.js"), None);
+    }
+
+    #[test]
+    fn test_supervisor_warning_names_pid_and_remedy() {
+        let warning = supervisor_warning(Supervisor::Pm2, 1188);
+        assert!(warning.contains("pm2 (PID 1188)"));
+        assert!(warning.contains("pm2 stop"));
+    }
+
+    #[test]
+    fn test_current_process_does_not_respawn() {
+        assert!(respawn_warnings(std::process::id(), &[]).is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+// Serial port module for finding the processes holding a COM port open
+
+use crate::error::Win32Error;
+use crate::handles::{self, HandleSource};
+use crate::watchdog;
+use std::ffi::c_void;
+use std::os::windows::io::AsRawHandle;
+use std::time::Duration;
+use windows::Wdk::Foundation::{NtQueryObject, OBJECT_INFORMATION_CLASS, OBJECT_NAME_INFORMATION};
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Storage::FileSystem::{FILE_TYPE_CHAR, GetFileType, QueryDosDeviceW};
+use windows::core::HSTRING;
+
+/// ObjectNameInformation, which the windows crate does not define
+const OBJECT_NAME_INFORMATION_CLASS: OBJECT_INFORMATION_CLASS = OBJECT_INFORMATION_CLASS(1);
+
+/// How long one handle may take to report its name; character devices such as
+/// consoles block name queries while a synchronous read is pending
+const HANDLE_QUERY_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Find the kernel device a COM port name points to, e.g. \Device\Serial0
+/// USB adapters use their driver's own names, such as \Device\USBSER000
+pub fn device_path(name: &str) -> Result<String, String> {
+    let mut buffer = [0u16; 1024];
+    let len = unsafe { QueryDosDeviceW(&HSTRING::from(name), Some(&mut buffer)) };
+    if len == 0 {
+        return Err(format!(
+            "Serial port {} does not exist: {}",
+            name,
+            Win32Error::from(windows::core::Error::from_thread())
+        ));
+    }
+
+    // The result is a list of NUL-terminated strings; the first one is current
+    let target: Vec<u16> = buffer.iter().take_while(|&&c| c != 0).copied().collect();
+    Ok(String::from_utf16_lossy(&target))
+}
+
+/// Find the processes holding a COM port open
+///
+/// Serial ports are opened exclusively, so there is normally one holder.
+/// Windows has no API for it, so the system handle table is searched for
+/// file handles whose object name is the port's device
+pub fn find_holders(name: &str) -> Result<Vec<u32>, String> {
+    let device = device_path(name)?;
+
+    // Any open file of our own tells which handle type index means "file"
+    let probe = std::fs::File::open(
+        std::env::current_exe().map_err(|e| format!("Failed to locate evict: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to open probe file: {}", e))?;
+    let system_handles = handles::list_system_handles()?;
+    let file_type = handles::type_index_of(HANDLE(probe.as_raw_handle()), &system_handles)
+        .ok_or_else(|| "Failed to identify file handles".to_string())?;
+    drop(probe);
+
+    let mut source = HandleSource::new();
+    let mut holders: Vec<u32> = Vec::new();
+
+    for candidate in system_handles.iter().filter(|h| h.type_index == file_type) {
+        if holders.contains(&candidate.pid) {
+            continue;
+        }
+        let Ok(duplicate) = source.duplicate(candidate, candidate.granted_access) else {
+            continue;
+        };
+
+        // Serial ports are character devices; skipping the rest avoids pipe name queries
+        if unsafe { GetFileType(duplicate) } != FILE_TYPE_CHAR {
+            unsafe {
+                let _ = CloseHandle(duplicate);
+            }
+            continue;
+        }
+
+        // The worker owns the duplicate; a hung query leaves it open rather than racing
+        let value = duplicate.0 as usize;
+        let object_name =
+            watchdog::run("Handle name query", HANDLE_QUERY_TIMEOUT, move || unsafe {
+                let handle = HANDLE(value as *mut _);
+                let name = object_name(handle);
+                let _ = CloseHandle(handle);
+                name
+            });
+
+        if let Ok(Some(object_name)) = object_name
+            && object_name.eq_ignore_ascii_case(&device)
+        {
+            holders.push(candidate.pid);
+        }
+    }
+
+    Ok(holders)
+}
+
+/// Get the kernel object name of a handle, e.g. \Device\Serial0
+unsafe fn object_name(handle: HANDLE) -> Option<String> {
+    // The result is a UNICODE_STRING followed by the characters it points to
+    let mut buffer: Vec<u64> = vec![0; 4096 / 8];
+    let status = unsafe {
+        NtQueryObject(
+            Some(handle),
+            OBJECT_NAME_INFORMATION_CLASS,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            (buffer.len() * 8) as u32,
+            None,
+        )
+    };
+    if status.0 < 0 {
+        return None;
+    }
+
+    let name = unsafe { &(*(buffer.as_ptr() as *const OBJECT_NAME_INFORMATION)).Name };
+    if name.Buffer.is_null() {
+        return None;
+    }
+    let chars = unsafe { std::slice::from_raw_parts(name.Buffer.0, name.Length as usize / 2) };
+    Some(String::from_utf16_lossy(chars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_path_of_missing_port() {
+        let err = device_path("COM255").unwrap_err();
+        assert!(err.contains("COM255 does not exist"), "{}", err);
+    }
+
+    #[test]
+    fn test_object_name_of_null_device() {
+        let null = std::fs::File::open("NUL").unwrap();
+        let name = unsafe { object_name(HANDLE(null.as_raw_handle())) };
+        assert_eq!(name.as_deref(), Some(r"\Device\Null"));
+    }
+}
@@ -0,0 +1,329 @@
+// Service control module for Windows service queries
+
+use crate::error::Win32Error;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::ERROR_MORE_DATA;
+use windows::Win32::System::Services::{
+    CloseServiceHandle, ControlService, ENUM_SERVICE_STATUS_PROCESSW, EnumServicesStatusExW,
+    OpenSCManagerW, OpenServiceW, QueryServiceConfig2W, QueryServiceStatus, SC_ACTION_RESTART,
+    SC_ENUM_PROCESS_INFO, SC_HANDLE, SC_MANAGER_CONNECT, SC_MANAGER_ENUMERATE_SERVICE,
+    SERVICE_ACTIVE, SERVICE_CONFIG_FAILURE_ACTIONS, SERVICE_CONTROL_STOP, SERVICE_FAILURE_ACTIONSW,
+    SERVICE_QUERY_CONFIG, SERVICE_QUERY_STATUS, SERVICE_STATUS, SERVICE_STOP, SERVICE_STOPPED,
+    SERVICE_WIN32,
+};
+use windows::core::{HSTRING, PCWSTR};
+
+/// How long stop_service waits for a service to reach the stopped state
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pause between status queries while a service is stopping
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A running Windows service and the process hosting it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub display_name: String,
+    pub pid: u32,
+}
+
+/// Running services, enumerated once per run
+static RUNNING_SERVICES: OnceLock<Result<Vec<ServiceInfo>, String>> = OnceLock::new();
+
+/// Find the running services hosted by the given PID
+/// Several services can share one svchost.exe process, so all matches are returned
+///
+/// The protection, elevation and respawn checks all ask this for the same
+/// owner and its parent, so the service list is read once and reused
+pub fn find_services_by_pid(pid: u32) -> Result<Vec<ServiceInfo>, String> {
+    let services = RUNNING_SERVICES.get_or_init(list_running_services);
+    Ok(services
+        .as_ref()
+        .map_err(String::clone)?
+        .iter()
+        .filter(|service| service.pid == pid)
+        .cloned()
+        .collect())
+}
+
+/// List every running Win32 service with its hosting PID
+pub fn list_running_services() -> Result<Vec<ServiceInfo>, String> {
+    unsafe {
+        // Open the service control manager with enumeration access
+        let manager = OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ENUMERATE_SERVICE)
+            .map_err(|e| {
+                format!(
+                    "Failed to open service control manager: {}",
+                    Win32Error::from(e)
+                )
+            })?;
+
+        // Ensure handle is closed when we're done
+        let result = enumerate_services(manager);
+        let _ = CloseServiceHandle(manager);
+        result
+    }
+}
+
+/// Helper function to enumerate active services from an open manager handle
+unsafe fn enumerate_services(manager: SC_HANDLE) -> Result<Vec<ServiceInfo>, String> {
+    let mut services = Vec::new();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut resume_handle: u32 = 0;
+
+    loop {
+        let mut bytes_needed: u32 = 0;
+        let mut returned: u32 = 0;
+
+        // The first call has no buffer and only reports the required size
+        let result = unsafe {
+            EnumServicesStatusExW(
+                manager,
+                SC_ENUM_PROCESS_INFO,
+                SERVICE_WIN32,
+                SERVICE_ACTIVE,
+                (!buffer.is_empty()).then_some(buffer.as_mut_slice()),
+                &mut bytes_needed,
+                &mut returned,
+                Some(&mut resume_handle),
+                PCWSTR::null(),
+            )
+        };
+
+        // Collect the entries returned by this call
+        let entries = buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW;
+        for i in 0..returned as usize {
+            unsafe {
+                let entry = entries.add(i);
+                services.push(ServiceInfo {
+                    name: (*entry).lpServiceName.to_string().unwrap_or_default(),
+                    display_name: (*entry).lpDisplayName.to_string().unwrap_or_default(),
+                    pid: (*entry).ServiceStatusProcess.dwProcessId,
+                });
+            }
+        }
+
+        match result {
+            Ok(()) => return Ok(services),
+            Err(err) if err.code() == ERROR_MORE_DATA.to_hresult() => {
+                if returned == 0 && bytes_needed as usize <= buffer.len() {
+                    return Err(format!(
+                        "Failed to enumerate services: {}",
+                        Win32Error::from(err)
+                    ));
+                }
+                buffer = vec![0; (bytes_needed as usize).max(buffer.len())];
+            }
+            Err(err) => {
+                return Err(format!(
+                    "Failed to enumerate services: {}",
+                    Win32Error::from(err)
+                ));
+            }
+        }
+    }
+}
+
+/// Get the delay before the service control manager restarts a failed service
+/// Returns Ok(None) when the service's recovery actions never restart it
+///
+/// Terminating a service process counts as a failure, so a service with a
+/// restart action comes back on its own after evict kills it
+pub fn restart_delay(name: &str) -> Result<Option<u32>, String> {
+    unsafe {
+        let manager =
+            OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT).map_err(|e| {
+                format!(
+                    "Failed to open service control manager: {}",
+                    Win32Error::from(e)
+                )
+            })?;
+
+        let result = match OpenServiceW(manager, &HSTRING::from(name), SERVICE_QUERY_CONFIG) {
+            Ok(service) => {
+                let delay = query_restart_delay(service);
+                let _ = CloseServiceHandle(service);
+                delay
+            }
+            Err(err) => Err(format!(
+                "Failed to open service {}: {}",
+                name,
+                Win32Error::from(err)
+            )),
+        };
+
+        let _ = CloseServiceHandle(manager);
+        result
+    }
+}
+
+/// Check whether the current user may stop a service
+/// Only opens the service with stop access; nothing is stopped
+pub fn can_stop(name: &str) -> Result<(), String> {
+    unsafe {
+        let manager =
+            OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT).map_err(|e| {
+                format!(
+                    "Failed to open service control manager: {}",
+                    Win32Error::from(e)
+                )
+            })?;
+
+        let result = match OpenServiceW(manager, &HSTRING::from(name), SERVICE_STOP) {
+            Ok(service) => {
+                let _ = CloseServiceHandle(service);
+                Ok(())
+            }
+            Err(err) => Err(format!(
+                "Failed to open service {} for stopping: {}",
+                name,
+                Win32Error::from(err)
+            )),
+        };
+
+        let _ = CloseServiceHandle(manager);
+        result
+    }
+}
+
+/// Stop a service and wait until the service control manager reports it stopped
+pub fn stop_service(name: &str, timeout: Duration) -> Result<(), String> {
+    unsafe {
+        let manager =
+            OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT).map_err(|e| {
+                format!(
+                    "Failed to open service control manager: {}",
+                    Win32Error::from(e)
+                )
+            })?;
+
+        let result = match OpenServiceW(
+            manager,
+            &HSTRING::from(name),
+            SERVICE_STOP | SERVICE_QUERY_STATUS,
+        ) {
+            Ok(service) => {
+                let stopped = stop_and_wait(service, name, timeout);
+                let _ = CloseServiceHandle(service);
+                stopped
+            }
+            Err(err) => Err(format!(
+                "Failed to open service {} for stopping: {}",
+                name,
+                Win32Error::from(err)
+            )),
+        };
+
+        let _ = CloseServiceHandle(manager);
+        result
+    }
+}
+
+/// Helper function to send the stop control to an open service and poll its status
+unsafe fn stop_and_wait(service: SC_HANDLE, name: &str, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    let mut status = SERVICE_STATUS::default();
+    unsafe { ControlService(service, SERVICE_CONTROL_STOP, &mut status) }
+        .map_err(|e| format!("Failed to stop service {}: {}", name, Win32Error::from(e)))?;
+
+    while status.dwCurrentState != SERVICE_STOPPED {
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Service {} did not stop within {} seconds",
+                name,
+                timeout.as_secs()
+            ));
+        }
+        thread::sleep(STOP_POLL_INTERVAL);
+        unsafe { QueryServiceStatus(service, &mut status) }.map_err(|e| {
+            format!(
+                "Failed to query the status of service {}: {}",
+                name,
+                Win32Error::from(e)
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Helper function to read the first restart action of an open service
+unsafe fn query_restart_delay(service: SC_HANDLE) -> Result<Option<u32>, String> {
+    // The first call only reports the required size
+    let mut bytes_needed: u32 = 0;
+    let _ = unsafe {
+        QueryServiceConfig2W(
+            service,
+            SERVICE_CONFIG_FAILURE_ACTIONS,
+            None,
+            &mut bytes_needed,
+        )
+    };
+
+    // Use a u64 buffer so the structure is suitably aligned
+    let mut buffer: Vec<u64> = vec![0; (bytes_needed as usize).div_ceil(8).max(1)];
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) };
+    unsafe {
+        QueryServiceConfig2W(
+            service,
+            SERVICE_CONFIG_FAILURE_ACTIONS,
+            Some(bytes),
+            &mut bytes_needed,
+        )
+    }
+    .map_err(|e| {
+        format!(
+            "Failed to query service recovery actions: {}",
+            Win32Error::from(e)
+        )
+    })?;
+
+    let actions = buffer.as_ptr() as *const SERVICE_FAILURE_ACTIONSW;
+    unsafe {
+        let count = (*actions).cActions as usize;
+        let list = (*actions).lpsaActions;
+        if list.is_null() {
+            return Ok(None);
+        }
+
+        Ok((0..count)
+            .map(|i| *list.add(i))
+            .find(|action| action.Type == SC_ACTION_RESTART)
+            .map(|action| action.Delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_running_services_returns_result() {
+        let result = list_running_services();
+        assert!(result.is_ok(), "Service enumeration should succeed");
+
+        // Every running service must report a name
+        for service in result.unwrap() {
+            assert!(!service.name.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_restart_delay_of_task_scheduler() {
+        // The Task Scheduler service is present on every supported Windows version
+        assert!(restart_delay("Schedule").is_ok());
+    }
+
+    #[test]
+    fn test_restart_delay_of_unknown_service_fails() {
+        assert!(restart_delay("EvictNoSuchService").is_err());
+    }
+
+    #[test]
+    fn test_current_process_hosts_no_services() {
+        let result = find_services_by_pid(std::process::id());
+        assert_eq!(result, Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,211 @@
+// Transcript module for the change record written by --transcript
+
+use crate::certificate::civil_from_days;
+use crate::cli::ResultReport;
+use crate::execution::{Outcome, StepResult};
+
+/// Everything the change record of one run says
+pub struct Transcript<'a> {
+    /// The command line as it was run
+    pub command: String,
+    /// Account evict ran as, e.g. CONTOSO\alice
+    pub operator: String,
+    pub host: String,
+    /// Start and end of the run, in seconds since 1970-01-01 UTC
+    pub started: u64,
+    pub finished: u64,
+    pub protocol: &'static str,
+    /// The owner evict chose and what happened to it
+    pub report: &'a ResultReport,
+    /// Services hosted by the owner
+    pub services: &'a [String],
+    /// Every process acted on, the chosen owner first
+    pub steps: &'a [StepResult],
+    /// Outcome of --verify-bind and --verify-cmd, one line each
+    pub checks: &'a [String],
+}
+
+/// Render the change record as Markdown, ready to paste into a change ticket
+pub fn render(transcript: &Transcript) -> String {
+    let report = transcript.report;
+    let unknown = || "unknown".to_string();
+    let mut lines = vec![
+        format!("# evict change record: port {}", report.port),
+        String::new(),
+        format!("- **Requested:** {}", code(&transcript.command)),
+        format!(
+            "- **Operator:** {} on {}",
+            transcript.operator, transcript.host
+        ),
+        format!("- **Started:** {}", format_utc(transcript.started)),
+        format!("- **Finished:** {}", format_utc(transcript.finished)),
+        String::new(),
+        "## Found".to_string(),
+        String::new(),
+        format!(
+            "{} port {} was held by PID {} ({}).",
+            transcript.protocol, report.port, report.pid, report.name
+        ),
+        String::new(),
+        format!(
+            "- Path: {}",
+            report.path.as_deref().map_or_else(unknown, code)
+        ),
+        format!(
+            "- Command line: {}",
+            report.command_line.as_deref().map_or_else(unknown, code)
+        ),
+    ];
+    if !transcript.services.is_empty() {
+        lines.push(format!("- Services: {}", transcript.services.join(", ")));
+    }
+
+    lines.extend([
+        String::new(),
+        "## Done".to_string(),
+        String::new(),
+        "| Target | Owner | Action | Outcome |".to_string(),
+        "| --- | --- | --- | --- |".to_string(),
+    ]);
+    for step in transcript.steps {
+        let outcome = match &step.outcome {
+            Outcome::Done => "ok".to_string(),
+            Outcome::Failed(err) => format!("failed: {}", err.message.replace('|', "\\|")),
+            Outcome::Skipped => "skipped".to_string(),
+        };
+        lines.push(format!(
+            "| {} | {} | {} | {} |",
+            step.target, step.owner, step.action, outcome
+        ));
+    }
+    lines.push(String::new());
+    lines.push(match &report.outcome {
+        Ok(()) => format!("Port {} was freed.", report.port),
+        Err(err) => format!("Port {} was not freed: {}", report.port, err),
+    });
+
+    if !transcript.checks.is_empty() {
+        lines.extend([String::new(), "## Verification".to_string(), String::new()]);
+        lines.extend(transcript.checks.iter().map(|check| format!("- {}", check)));
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Format seconds since 1970-01-01 as a UTC date and time
+fn format_utc(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Format text as inline Markdown code
+/// Text with backticks is fenced with two, as Markdown requires
+fn code(text: &str) -> String {
+    if text.contains('`') {
+        format!("`` {} ``", text)
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn report(outcome: Result<(), String>) -> ResultReport {
+        ResultReport {
+            port: 8080,
+            pid: 1234,
+            name: "node.exe".to_string(),
+            path: Some(r"C:\Program Files\nodejs\node.exe".to_string()),
+            command_line: None,
+            action: "kill",
+            outcome,
+        }
+    }
+
+    fn step(outcome: Outcome) -> StepResult {
+        StepResult {
+            target: "PID 1234".to_string(),
+            owner: "node.exe".to_string(),
+            action: "kill",
+            outcome,
+            duration: Duration::from_millis(3),
+        }
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(1_792_141_923), "2026-10-16 09:12:03 UTC");
+    }
+
+    #[test]
+    fn test_code_with_backticks() {
+        assert_eq!(code("evict 80"), "`evict 80`");
+        assert_eq!(code("echo `x`"), "`` echo `x` ``");
+    }
+
+    #[test]
+    fn test_render_freed_port() {
+        let report = report(Ok(()));
+        let steps = [step(Outcome::Done)];
+        let text = render(&Transcript {
+            command: "evict 8080 --transcript out.md".to_string(),
+            operator: r"CONTOSO\alice".to_string(),
+            host: "HOST01".to_string(),
+            started: 0,
+            finished: 2,
+            protocol: "TCP",
+            report: &report,
+            services: &[],
+            steps: &steps,
+            checks: &["--verify-bind: port 8080 could be bound".to_string()],
+        });
+
+        assert!(text.starts_with("# evict change record: port 8080\n"));
+        assert!(text.contains("- **Requested:** `evict 8080 --transcript out.md`\n"));
+        assert!(text.contains("- **Operator:** CONTOSO\\alice on HOST01\n"));
+        assert!(text.contains("- **Finished:** 1970-01-01 00:00:02 UTC\n"));
+        assert!(text.contains("TCP port 8080 was held by PID 1234 (node.exe).\n"));
+        assert!(text.contains("- Command line: unknown\n"));
+        assert!(text.contains("| PID 1234 | node.exe | kill | ok |\n"));
+        assert!(text.contains("Port 8080 was freed.\n"));
+        assert!(text.contains("## Verification\n\n- --verify-bind: port 8080 could be bound\n"));
+        assert!(!text.contains("Services"));
+    }
+
+    #[test]
+    fn test_render_failed_termination() {
+        let report = report(Err("Access is denied.".to_string()));
+        let steps = [step(Outcome::Failed("Access | denied".into()))];
+        let text = render(&Transcript {
+            command: "evict 8080".to_string(),
+            operator: "unknown".to_string(),
+            host: "HOST01".to_string(),
+            started: 0,
+            finished: 0,
+            protocol: "TCP",
+            report: &report,
+            services: &["W3SVC".to_string()],
+            steps: &steps,
+            checks: &[],
+        });
+
+        assert!(text.contains("- Services: W3SVC\n"));
+        assert!(text.contains("| failed: Access \\| denied |"));
+        assert!(text.contains("Port 8080 was not freed: Access is denied."));
+        assert!(!text.contains("## Verification"));
+    }
+}
@@ -0,0 +1,237 @@
+// Unix socket module for finding the processes bound to an AF_UNIX socket path
+
+use crate::error::Win32Error;
+use crate::handles::{self, HandleSource};
+use crate::process_info;
+use crate::project;
+use crate::watchdog;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Networking::WinSock::{
+    AF_UNIX, SOCK_STREAM, SOCKADDR, SOCKADDR_UN, SOCKET, WSADATA, WSAStartup, closesocket,
+    getsockname, socket,
+};
+use windows::Win32::Storage::FileSystem::{
+    FILE_ATTRIBUTE_REPARSE_POINT, FILE_TYPE_PIPE, FindClose, FindFirstFileW, GetFileType,
+    WIN32_FIND_DATAW,
+};
+use windows::core::HSTRING;
+
+/// Reparse tag of the file an AF_UNIX socket leaves at its path
+const IO_REPARSE_TAG_AF_UNIX: u32 = 0x8000_0023;
+
+/// How long one handle may take to answer; pipe handles with a pending
+/// synchronous read block every query until the read completes
+const HANDLE_QUERY_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Find the processes holding a socket bound to the given path
+///
+/// Windows has no table of AF_UNIX sockets, so every socket-like handle in the
+/// system is duplicated and asked for its local address. Listening sockets
+/// and the connections they accepted both report the bound path
+pub fn find_holders(path: &Path) -> Result<Vec<u32>, String> {
+    let target = std::path::absolute(path)
+        .map_err(|e| format!("Failed to resolve '{}': {}", path.display(), e))?;
+
+    start_winsock()?;
+
+    // Sockets are file objects; an AF_UNIX socket of our own tells which type index that is
+    let probe = unsafe { socket(i32::from(AF_UNIX), SOCK_STREAM, 0) }.map_err(|e| {
+        format!(
+            "AF_UNIX sockets are not available (Windows 10 1803 or later is required): {}",
+            Win32Error::from(e)
+        )
+    })?;
+    let system_handles = handles::list_system_handles();
+    let file_type = system_handles
+        .as_ref()
+        .ok()
+        .and_then(|list| handles::type_index_of(HANDLE(probe.0 as *mut _), list));
+    unsafe {
+        closesocket(probe);
+    }
+
+    let system_handles = system_handles?;
+    let file_type = file_type.ok_or_else(|| "Failed to identify socket handles".to_string())?;
+
+    let mut source = HandleSource::new();
+    let mut holders: Vec<u32> = Vec::new();
+
+    for candidate in system_handles.iter().filter(|h| h.type_index == file_type) {
+        if holders.contains(&candidate.pid) {
+            continue;
+        }
+        let Ok(duplicate) = source.duplicate(candidate, candidate.granted_access) else {
+            continue;
+        };
+
+        // Sockets and pipes are the only handles Winsock can answer for
+        if unsafe { GetFileType(duplicate) } != FILE_TYPE_PIPE {
+            unsafe {
+                let _ = CloseHandle(duplicate);
+            }
+            continue;
+        }
+
+        // The worker owns the duplicate; a hung query leaves it open rather than racing
+        let value = duplicate.0 as usize;
+        let bound = watchdog::run("Socket query", HANDLE_QUERY_TIMEOUT, move || unsafe {
+            let handle = HANDLE(value as *mut _);
+            let path = local_path(handle);
+            let _ = CloseHandle(handle);
+            path
+        });
+
+        let Ok(Some(bound)) = bound else {
+            continue;
+        };
+        if same_socket_path(&target, &resolve(candidate.pid, &bound)) {
+            holders.push(candidate.pid);
+        }
+    }
+
+    Ok(holders)
+}
+
+/// Check whether a path is the file an AF_UNIX socket leaves behind
+/// The file stays after its socket is closed, and binding the path fails until it is removed
+pub fn is_socket_file(path: &Path) -> bool {
+    let mut data = WIN32_FIND_DATAW::default();
+    let Ok(find) = (unsafe { FindFirstFileW(&HSTRING::from(path), &mut data) }) else {
+        return false;
+    };
+    unsafe {
+        let _ = FindClose(find);
+    }
+
+    data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT.0 != 0
+        && data.dwReserved0 == IO_REPARSE_TAG_AF_UNIX
+}
+
+/// Initialize Winsock for this process; repeated calls are harmless
+fn start_winsock() -> Result<(), String> {
+    let mut data = WSADATA::default();
+    let status = unsafe { WSAStartup(0x0202, &mut data) };
+    if status != 0 {
+        return Err(format!(
+            "Failed to initialize Winsock: {}",
+            Win32Error::from_code(status as u32)
+        ));
+    }
+    Ok(())
+}
+
+/// Read the path an AF_UNIX socket handle is bound to
+/// Returns None for pipes and other sockets, and for sockets without a path
+unsafe fn local_path(handle: HANDLE) -> Option<String> {
+    let mut address = SOCKADDR_UN::default();
+    let mut len = size_of::<SOCKADDR_UN>() as i32;
+    let status = unsafe {
+        getsockname(
+            SOCKET(handle.0 as usize),
+            &mut address as *mut SOCKADDR_UN as *mut SOCKADDR,
+            &mut len,
+        )
+    };
+    if status != 0 || address.sun_family.0 != AF_UNIX {
+        return None;
+    }
+
+    let path = sun_path(&address.sun_path);
+    (!path.is_empty()).then_some(path)
+}
+
+/// Decode the NUL-terminated UTF-8 path of a SOCKADDR_UN
+fn sun_path(raw: &[i8]) -> String {
+    let bytes: Vec<u8> = raw
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| byte as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Make a bound path absolute
+/// Relative paths were bound relative to the holder's working directory
+fn resolve(pid: u32, bound: &str) -> PathBuf {
+    let bound = Path::new(bound);
+    if bound.is_absolute() {
+        return bound.to_path_buf();
+    }
+    match process_info::current_directory(pid) {
+        Ok(directory) => directory.join(bound),
+        Err(_) => bound.to_path_buf(),
+    }
+}
+
+/// Compare socket paths the way the file system does
+fn same_socket_path(a: &Path, b: &Path) -> bool {
+    project::normalize(&a.to_string_lossy()) == project::normalize(&b.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Networking::WinSock::{ADDRESS_FAMILY, bind, listen};
+
+    #[test]
+    fn test_sun_path_stops_at_nul() {
+        let mut raw = [0i8; 108];
+        for (slot, byte) in raw.iter_mut().zip(b"C:\\tmp\\app.sock") {
+            *slot = *byte as i8;
+        }
+        assert_eq!(sun_path(&raw), r"C:\tmp\app.sock");
+        assert_eq!(sun_path(&[0; 108]), "");
+    }
+
+    #[test]
+    fn test_same_socket_path_ignores_case_and_separators() {
+        assert!(same_socket_path(
+            Path::new(r"C:\Temp\App.sock"),
+            Path::new("c:/temp/app.sock")
+        ));
+        assert!(!same_socket_path(
+            Path::new(r"C:\Temp\app.sock"),
+            Path::new(r"C:\Temp\app.sock2")
+        ));
+    }
+
+    #[test]
+    fn test_find_holders_of_own_socket() {
+        let path = std::env::temp_dir().join(format!("evict-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut address = SOCKADDR_UN {
+            sun_family: ADDRESS_FAMILY(AF_UNIX),
+            ..Default::default()
+        };
+        let bytes = path.to_string_lossy().into_owned().into_bytes();
+        for (slot, byte) in address.sun_path.iter_mut().zip(&bytes) {
+            *slot = *byte as i8;
+        }
+
+        start_winsock().unwrap();
+        let listener = unsafe { socket(i32::from(AF_UNIX), SOCK_STREAM, 0) }.unwrap();
+        unsafe {
+            assert_eq!(
+                bind(
+                    listener,
+                    &address as *const SOCKADDR_UN as *const SOCKADDR,
+                    size_of::<SOCKADDR_UN>() as i32
+                ),
+                0
+            );
+            assert_eq!(listen(listener, 1), 0);
+        }
+
+        assert!(is_socket_file(&path));
+        assert_eq!(find_holders(&path).unwrap(), vec![std::process::id()]);
+
+        unsafe {
+            closesocket(listener);
+        }
+        assert!(find_holders(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
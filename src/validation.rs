@@ -3,12 +3,21 @@
 /// Validates a port string and returns a u16 port number if valid
 ///
 /// Valid ports are in the range 1-65535
+/// Surrounding whitespace and a leading colon (as in ":3000") are accepted;
+/// other formatting such as "8,080" or "0x1F90" is rejected with a hint
 /// Returns an error for non-numeric input or out-of-range values
 pub fn validate_port(port_str: &str) -> Result<u16, String> {
     // Try to parse as u16
-    let port = port_str
+    let normalized = normalize_port_input(port_str);
+    let port = normalized
         .parse::<u16>()
-        .map_err(|_| format!("Port must be a valid number, got: {}", port_str))?;
+        .map_err(|_| match port_format_hint(normalized) {
+            Some(hint) => format!(
+                "Port must be a plain number between 1 and 65535, got: {} ({})",
+                port_str, hint
+            ),
+            None => format!("Port must be a valid number, got: {}", port_str),
+        })?;
 
     // Check range (1-65535)
     if port == 0 {
@@ -18,6 +27,105 @@ pub fn validate_port(port_str: &str) -> Result<u16, String> {
     Ok(port)
 }
 
+/// Strip the harmless leftovers of copying a port: whitespace and the colon of "host:port"
+pub fn normalize_port_input(input: &str) -> &str {
+    let trimmed = input.trim();
+    trimmed.strip_prefix(':').unwrap_or(trimmed).trim_start()
+}
+
+/// Recognize common ways a port number gets formatted and say how to write it
+/// Returns None when the input doesn't look like a formatted number at all
+pub fn port_format_hint(input: &str) -> Option<String> {
+    if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        return Some(format!("is hexadecimal; write {}", value));
+    }
+
+    let is_separator = |c: char| matches!(c, ',' | '.' | '_' | '\'' | ' ');
+    if input.starts_with(|c: char| c.is_ascii_digit())
+        && input.contains(is_separator)
+        && input.chars().all(|c| c.is_ascii_digit() || is_separator(c))
+    {
+        let digits: String = input.chars().filter(char::is_ascii_digit).collect();
+        return Some(format!("uses digit separators; write {}", digits));
+    }
+
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+        return Some("is above 65535, the highest port".to_string());
+    }
+
+    None
+}
+
+/// Validates a port range written as START-END and returns its bounds
+///
+/// Both ends must be valid ports and START must not exceed END
+pub fn validate_port_range(range_str: &str) -> Result<(u16, u16), String> {
+    let (start, end) = range_str
+        .split_once('-')
+        .ok_or_else(|| format!("Port range must look like START-END, got: {}", range_str))?;
+
+    let start = validate_port(start.trim())?;
+    let end = validate_port(end.trim())?;
+    if start > end {
+        return Err(format!(
+            "Port range start must not exceed its end, got: {}",
+            range_str
+        ));
+    }
+
+    Ok((start, end))
+}
+
+/// Validates a serial port name and returns it as COM<N>
+///
+/// Accepts any letter case and the device namespace prefix (\\.\COM10);
+/// Windows numbers serial ports from 1 to 255
+pub fn validate_com_port(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    let bare = trimmed.strip_prefix(r"\\.\").unwrap_or(trimmed);
+    let number = bare
+        .get(..3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("COM"))
+        .and_then(|_| bare.get(3..))
+        .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+        .and_then(|digits| digits.parse::<u8>().ok())
+        .filter(|&number| number != 0)
+        .ok_or_else(|| format!("Serial port must look like COM1-COM255, got: {}", name))?;
+
+    Ok(format!("COM{}", number))
+}
+
+/// Validates a process image name as given to --name and returns it with an extension
+///
+/// "node" becomes "node.exe"; paths are rejected, since only the image name is compared
+pub fn validate_process_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() || trimmed.contains(['\\', '/', ':']) {
+        return Err(format!(
+            "Process name must be an image name such as node.exe, got: {}",
+            name
+        ));
+    }
+
+    if trimmed.contains('.') {
+        Ok(trimmed.to_string())
+    } else {
+        Ok(format!("{}.exe", trimmed))
+    }
+}
+
+/// Check whether a port is in the well-known (privileged) range 1-1023
+///
+/// These ports usually belong to system services, so terminating their owner
+/// requires explicit confirmation
+pub fn is_well_known_port(port: u16) -> bool {
+    port < 1024
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,6 +156,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_port_range() {
+        assert_eq!(validate_port_range("8000-9000"), Ok((8000, 9000)));
+        assert_eq!(validate_port_range("80 - 80"), Ok((80, 80)));
+    }
+
+    #[test]
+    fn test_validate_port_range_rejects_bad_ranges() {
+        assert!(validate_port_range("8000").is_err());
+        assert!(validate_port_range("9000-8000").is_err());
+        assert!(validate_port_range("0-100").is_err());
+        assert!(validate_port_range("8000-70000").is_err());
+    }
+
     // Unit tests for edge cases
     // Requirements: 3.1, 3.2
 
@@ -91,6 +213,71 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_well_known_port_boundaries() {
+        assert!(is_well_known_port(1));
+        assert!(is_well_known_port(443));
+        assert!(is_well_known_port(1023));
+        assert!(!is_well_known_port(1024));
+        assert!(!is_well_known_port(8080));
+    }
+
+    #[test]
+    fn test_validate_port_normalizes_copied_input() {
+        assert_eq!(validate_port(" 8080 "), Ok(8080));
+        assert_eq!(validate_port(":3000"), Ok(3000));
+        assert_eq!(validate_port("\t: 443\n"), Ok(443));
+        assert!(validate_port(":").is_err());
+        assert!(validate_port("::3000").is_err());
+    }
+
+    #[test]
+    fn test_validate_port_hints_at_formatting() {
+        let err = validate_port("8,080").unwrap_err();
+        assert!(err.contains("uses digit separators; write 8080"), "{}", err);
+
+        let err = validate_port("0x1F90").unwrap_err();
+        assert!(err.contains("is hexadecimal; write 8080"), "{}", err);
+
+        let err = validate_port("70000").unwrap_err();
+        assert!(err.contains("above 65535"), "{}", err);
+    }
+
+    #[test]
+    fn test_port_format_hint_ignores_plain_garbage() {
+        assert_eq!(port_format_hint("abc"), None);
+        assert_eq!(port_format_hint("-1"), None);
+        assert_eq!(port_format_hint("0xZZ"), None);
+        assert_eq!(port_format_hint(",80"), None);
+    }
+
+    #[test]
+    fn test_validate_com_port() {
+        assert_eq!(validate_com_port("COM3"), Ok("COM3".to_string()));
+        assert_eq!(validate_com_port(" com12 "), Ok("COM12".to_string()));
+        assert_eq!(validate_com_port(r"\\.\COM255"), Ok("COM255".to_string()));
+
+        for bad in ["COM", "COM0", "COM256", "COM+3", "LPT1", "3", ""] {
+            assert!(validate_com_port(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_validate_process_name() {
+        assert_eq!(
+            validate_process_name("node.exe"),
+            Ok("node.exe".to_string())
+        );
+        assert_eq!(validate_process_name(" node "), Ok("node.exe".to_string()));
+        assert_eq!(
+            validate_process_name("Code.EXE"),
+            Ok("Code.EXE".to_string())
+        );
+        assert!(validate_process_name("").is_err());
+        assert!(validate_process_name(r"C:\tools\node.exe").is_err());
+        assert!(validate_process_name("bin/node").is_err());
+    }
+
     #[test]
     fn test_negative_number() {
         let result = validate_port("-1");
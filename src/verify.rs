@@ -0,0 +1,174 @@
+// Verify module for checking that a replacement came up after a termination
+
+use crate::port_service::{self, PortBinding, PortScanner, Protocol};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long --verify-cmd waits for the replacement and its check by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often the listener table is read while waiting for the replacement
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long --verify-bind keeps trying to bind the freed port
+pub const BIND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause between --verify-bind attempts
+const BIND_RETRY: Duration = Duration::from_millis(100);
+
+/// Wait until a process listens on the port again
+/// Returns the new owner, or None when nothing came up before the deadline
+pub fn wait_for_listener(port: u16, deadline: Instant) -> Result<Option<u32>, String> {
+    loop {
+        let listeners = port_service::IpHelperScanner
+            .list_listeners()
+            .or_else(|_| port_service::WmiScanner.list_listeners())?;
+        if let Some(owner) = new_owner(&listeners, port) {
+            return Ok(Some(owner));
+        }
+        if Instant::now() + POLL_INTERVAL > deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run the verification command through cmd.exe
+/// Returns its exit code; a command still running at the deadline is terminated
+pub fn run_command(command: &str, deadline: Instant) -> Result<i32, String> {
+    let mut child = Command::new("cmd")
+        .arg("/C")
+        .arg(command)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+
+    loop {
+        let status = child
+            .try_wait()
+            .map_err(|e| format!("Failed to wait for '{}': {}", command, e))?;
+        if let Some(status) = status {
+            // Only a process killed without an exit code lacks one
+            return Ok(status.code().unwrap_or(-1));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("'{}' did not finish in time", command));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Bind the port on every address the old owner used and release it right away
+///
+/// An empty connection table does not prove that a new server can bind: an
+/// exclusive socket or closing connections can still be in the way. The
+/// sockets of a terminated process can take a moment to close, so failed
+/// binds are retried until the deadline
+pub fn bind_port(
+    port: u16,
+    addresses: &[IpAddr],
+    protocol: Protocol,
+    deadline: Instant,
+) -> Result<(), String> {
+    let any = [IpAddr::V4(Ipv4Addr::UNSPECIFIED)];
+    let addresses = if addresses.is_empty() {
+        &any[..]
+    } else {
+        addresses
+    };
+
+    loop {
+        let failure = addresses
+            .iter()
+            .find_map(|&address| bind_once(SocketAddr::new(address, port), protocol).err());
+        match failure {
+            None => return Ok(()),
+            Some(err) if Instant::now() + BIND_RETRY > deadline => return Err(err),
+            Some(_) => thread::sleep(BIND_RETRY),
+        }
+    }
+}
+
+/// Bind one address and close the socket again
+fn bind_once(address: SocketAddr, protocol: Protocol) -> Result<(), String> {
+    let bound = match protocol {
+        Protocol::Tcp => TcpListener::bind(address).map(drop),
+        Protocol::Udp => UdpSocket::bind(address).map(drop),
+    };
+    bound.map_err(|e| format!("Failed to bind {} {}: {}", protocol.as_str(), address, e))
+}
+
+/// Find the first process listening on the port
+/// PID 0 only shows up for stale rows and does not count as a replacement
+fn new_owner(listeners: &[PortBinding], port: u16) -> Option<u32> {
+    listeners
+        .iter()
+        .find(|binding| binding.port == port && binding.pid != 0)
+        .map(|binding| binding.pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn binding(pid: u32, port: u16) -> PortBinding {
+        PortBinding {
+            pid,
+            port,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(port_service::TcpState::Listen),
+        }
+    }
+
+    #[test]
+    fn test_new_owner_skips_other_ports_and_stale_rows() {
+        let listeners = [binding(10, 3000), binding(0, 8080), binding(20, 8080)];
+        assert_eq!(new_owner(&listeners, 8080), Some(20));
+        assert_eq!(new_owner(&listeners, 9000), None);
+    }
+
+    #[test]
+    fn test_wait_for_listener_finds_own_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        assert_eq!(
+            wait_for_listener(port, deadline),
+            Ok(Some(std::process::id()))
+        );
+    }
+
+    #[test]
+    fn test_bind_port_of_free_and_held_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let loopback = [IpAddr::V4(Ipv4Addr::LOCALHOST)];
+
+        let deadline = Instant::now() + Duration::from_millis(300);
+        let err = bind_port(port, &loopback, Protocol::Tcp, deadline).unwrap_err();
+        assert!(err.contains("Failed to bind TCP 127.0.0.1:"), "{}", err);
+
+        drop(listener);
+        let deadline = Instant::now() + BIND_TIMEOUT;
+        assert_eq!(bind_port(port, &loopback, Protocol::Tcp, deadline), Ok(()));
+    }
+
+    #[test]
+    fn test_run_command_reports_exit_code() {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        assert_eq!(run_command("exit 0", deadline), Ok(0));
+        assert_eq!(run_command("exit 3", deadline), Ok(3));
+    }
+
+    #[test]
+    fn test_run_command_times_out() {
+        let deadline = Instant::now() + Duration::from_millis(200);
+        let err = run_command("ping -n 10 127.0.0.1 > NUL", deadline).unwrap_err();
+        assert!(err.contains("did not finish in time"), "{}", err);
+    }
+}
@@ -0,0 +1,208 @@
+// Watch module for keeping a port free by terminating whatever binds it
+
+use crate::port_service::{PortBinding, PortScanner};
+use crate::process_service::ProcessManager;
+use std::time::Duration;
+
+/// How often the connection table is read while watching a port
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A process found on the watched port and what terminating it did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sweep {
+    pub pid: u32,
+    pub name: String,
+    pub outcome: Result<(), String>,
+}
+
+/// Read the TCP listeners once and terminate every process listening on the port
+/// Connections using it as their local port are left alone. Protected processes
+/// and service hosts are reported instead of being terminated, since the service
+/// manager would only start the service again for the next poll
+pub fn sweep(
+    scanner: &dyn PortScanner,
+    processes: &dyn ProcessManager,
+    services: &dyn Fn(u32) -> Vec<String>,
+    port: u16,
+    own_pid: u32,
+    exit_code: u32,
+) -> Result<Vec<Sweep>, String> {
+    let bindings = scanner.list_listeners()?;
+    Ok(holders(&bindings, port, own_pid)
+        .into_iter()
+        .map(|pid| {
+            let name = processes
+                .process_name(pid)
+                .unwrap_or_else(|_| "unknown".to_string());
+            let outcome = match processes.protection_level(pid) {
+                Some(level) => Err(format!(
+                    "{} (PID {}) is a protected process ({}) and cannot be terminated",
+                    name, pid, level
+                )),
+                None => match services(pid).as_slice() {
+                    [] => processes.kill(pid, exit_code),
+                    hosted => Err(format!(
+                        "{} (PID {}) hosts the Windows service {} and was left alone; stop the service to keep the port free",
+                        name,
+                        pid,
+                        hosted.join(", ")
+                    )),
+                },
+            };
+            Sweep { pid, name, outcome }
+        })
+        .collect())
+}
+
+/// Find the processes holding the watched port, each PID once
+/// PID 0 only shows up for stale rows, and evict never terminates itself
+pub fn holders(bindings: &[PortBinding], port: u16, own_pid: u32) -> Vec<u32> {
+    let mut pids = Vec::new();
+    for binding in bindings {
+        if binding.port == port
+            && binding.pid != 0
+            && binding.pid != own_pid
+            && !pids.contains(&binding.pid)
+        {
+            pids.push(binding.pid);
+        }
+    }
+    pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::net::Ipv4Addr;
+
+    fn binding(pid: u32, port: u16) -> PortBinding {
+        PortBinding {
+            pid,
+            port,
+            address: Ipv4Addr::UNSPECIFIED.into(),
+            state: Some(crate::port_service::TcpState::Listen),
+        }
+    }
+
+    /// Connection table with fixed TCP rows
+    struct MockScanner(Vec<PortBinding>);
+
+    impl PortScanner for MockScanner {
+        fn find_process_by_port(&self, port: u16) -> Result<Option<PortBinding>, String> {
+            Ok(self.0.iter().find(|binding| binding.port == port).cloned())
+        }
+
+        fn list_bindings(&self) -> Result<Vec<PortBinding>, String> {
+            Ok(self.0.clone())
+        }
+
+        fn list_listeners(&self) -> Result<Vec<PortBinding>, String> {
+            Ok(self.0.clone())
+        }
+
+        fn find_udp_process_by_port(&self, _port: u16) -> Result<Option<PortBinding>, String> {
+            Ok(None)
+        }
+
+        fn list_udp_bindings(&self) -> Result<Vec<PortBinding>, String> {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Processes that record what was done to them; PID 4 is protected
+    #[derive(Default)]
+    struct MockProcesses {
+        killed: RefCell<Vec<(u32, u32)>>,
+    }
+
+    impl ProcessManager for MockProcesses {
+        fn process_name(&self, pid: u32) -> Result<String, String> {
+            match pid {
+                4 => Ok("System".to_string()),
+                _ => Ok("node.exe".to_string()),
+            }
+        }
+
+        fn protection_level(&self, pid: u32) -> Option<&'static str> {
+            (pid == 4).then_some("WinTcb")
+        }
+
+        fn kill(&self, pid: u32, exit_code: u32) -> Result<(), String> {
+            self.killed.borrow_mut().push((pid, exit_code));
+            Ok(())
+        }
+
+        fn terminate_gracefully(
+            &self,
+            pid: u32,
+            _timeout: Duration,
+            _console_group: bool,
+        ) -> Result<(), String> {
+            Err(format!("PID {} has no window", pid))
+        }
+
+        fn is_running(&self, pid: u32) -> bool {
+            !self
+                .killed
+                .borrow()
+                .iter()
+                .any(|&(killed, _)| killed == pid)
+        }
+    }
+
+    #[test]
+    fn test_holders_skips_stale_rows_and_own_process() {
+        let bindings = [
+            binding(10, 3000),
+            binding(0, 3000),
+            binding(20, 3000),
+            binding(10, 3000),
+            binding(99, 3000),
+            binding(30, 8080),
+        ];
+        assert_eq!(holders(&bindings, 3000, 99), vec![10, 20]);
+        assert_eq!(holders(&bindings, 9000, 99), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_sweep_kills_holders_with_exit_code() {
+        let scanner = MockScanner(vec![binding(10, 3000), binding(30, 8080)]);
+        let processes = MockProcesses::default();
+        let swept = sweep(&scanner, &processes, &|_| Vec::new(), 3000, 99, 7).unwrap();
+        assert_eq!(
+            swept,
+            vec![Sweep {
+                pid: 10,
+                name: "node.exe".to_string(),
+                outcome: Ok(())
+            }]
+        );
+        assert_eq!(*processes.killed.borrow(), vec![(10, 7)]);
+        assert!(!processes.is_running(10));
+    }
+
+    #[test]
+    fn test_sweep_leaves_protected_processes_alone() {
+        let scanner = MockScanner(vec![binding(4, 445)]);
+        let processes = MockProcesses::default();
+        let swept = sweep(&scanner, &processes, &|_| Vec::new(), 445, 99, 1).unwrap();
+        let outcome = swept.first().map(|sweep| sweep.outcome.clone());
+        assert!(matches!(outcome, Some(Err(err)) if err.contains("protected process (WinTcb)")));
+        assert!(processes.killed.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_sweep_leaves_service_hosts_alone() {
+        let scanner = MockScanner(vec![binding(10, 80), binding(20, 80)]);
+        let processes = MockProcesses::default();
+        let services = |pid: u32| match pid {
+            10 => vec!["W3SVC".to_string()],
+            _ => Vec::new(),
+        };
+        let swept = sweep(&scanner, &processes, &services, 80, 99, 1).unwrap();
+        let outcome = swept.first().map(|sweep| sweep.outcome.clone());
+        assert!(matches!(outcome, Some(Err(err)) if err.contains("Windows service W3SVC")));
+        assert_eq!(*processes.killed.borrow(), vec![(20, 1)]);
+    }
+}
@@ -0,0 +1,65 @@
+// Watchdog module for bounding calls that can hang inside Windows
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How long a lookup step may take before evict gives up on it
+pub const STEP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Run a potentially blocking call on a worker thread and wait at most `timeout`
+///
+/// A hung kernel driver or security product hook can block OpenProcess, WMI
+/// or socket calls indefinitely. On timeout the worker is left behind, since a
+/// thread stuck in the kernel cannot be cancelled, and the error names the step
+pub fn run<T, F>(step: &str, timeout: Duration, call: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name(format!("evict: {}", step))
+        .spawn(move || {
+            // The receiver is gone if the watchdog already gave up
+            let _ = sender.send(call());
+        })
+        .map_err(|e| format!("Failed to start {}: {}", step, e))?;
+
+    receiver.recv_timeout(timeout).map_err(|err| match err {
+        mpsc::RecvTimeoutError::Timeout => format!(
+            "{} did not finish within {} seconds; a driver or security product may be blocking it",
+            step,
+            timeout.as_secs()
+        ),
+        mpsc::RecvTimeoutError::Disconnected => format!("{} failed unexpectedly", step),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_returns_result() {
+        let result = run("Addition", Duration::from_secs(5), || 2 + 2);
+        assert_eq!(result, Ok(4));
+    }
+
+    #[test]
+    fn test_run_times_out_and_names_step() {
+        let result = run("Slow lookup", Duration::from_millis(50), || {
+            thread::sleep(Duration::from_secs(2));
+        });
+        let err = result.unwrap_err();
+        assert!(err.starts_with("Slow lookup did not finish"));
+    }
+
+    #[test]
+    fn test_run_reports_panicking_call() {
+        let result: Result<(), String> = run("Broken step", Duration::from_secs(5), || {
+            panic!("boom");
+        });
+        assert_eq!(result, Err("Broken step failed unexpectedly".to_string()));
+    }
+}
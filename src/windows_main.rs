@@ -0,0 +1,2457 @@
+use evict::error::{Error, ErrorKind};
+use evict::{
+    app_package, app_shutdown, cli, clipboard, config, execution, explain, file_lock, find,
+    free_port, image_info, inventory, job_object, method, port_range, port_service, port_sharing,
+    privilege, probe, process_info, process_service, project, respawn, serial_port,
+    service_control, transcript, unix_socket, validation, verify, watch, watchdog,
+};
+
+use port_service::{PortScanner, Protocol};
+use process_service::Signal;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Exit code for a free port under --fail-if-free, distinct from real failures
+const FREE_EXIT_CODE: i32 = 2;
+
+/// How often the connection table is read while it only lists exited processes
+const STALE_RETRIES: u32 = 3;
+
+/// Pause between reads of a stale connection table
+const STALE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How long after the lookup the owner is checked again before termination
+/// Below this nothing slow (a prompt, a dump, a shutdown request) happened in between
+const RECHECK_AFTER: std::time::Duration = std::time::Duration::from_millis(500);
+
+pub fn main() {
+    // Helper mode that delivers console control events for --sigterm
+    if let Some((pid, group, event)) = cli::parse_console_ctrl_helper() {
+        process::exit(process_service::run_console_ctrl_helper(pid, group, event));
+    }
+
+    // A copy relaunched by --elevate writes its output where the unelevated evict reads it
+    if let Some(path) = cli::parse_elevated_output()
+        && let Err(err) = privilege::redirect_output(&path)
+    {
+        cli::display_error(&err.into());
+        process::exit(1);
+    }
+
+    // Parse command line arguments
+    let args = match cli::parse_args() {
+        Ok(cli::Command::Evict(args)) => args,
+        Ok(cli::Command::FreePort { near }) => {
+            let found = match near {
+                Some(near) => free_port::find_free_port_near(near).ok_or_else(|| {
+                    format!(
+                        "No free port within {} ports above {}",
+                        free_port::SEARCH_DISTANCE,
+                        near
+                    )
+                }),
+                None => free_port::any_free_port(),
+            };
+            match found {
+                Ok(port) => {
+                    println!("{}", port);
+                    process::exit(0);
+                }
+                Err(err) => {
+                    cli::display_error(&err.into());
+                    process::exit(1);
+                }
+            }
+        }
+        Ok(cli::Command::Range {
+            start,
+            end,
+            summary,
+            backend,
+        }) => {
+            let bindings = scanner(backend).list_bindings();
+            match bindings {
+                Ok(bindings) => {
+                    let usage = port_range::RangeUsage::new(start, end, &bindings);
+                    cli::display_range_usage(&usage, summary, |pid| {
+                        let name = process_service::get_process_name(pid)
+                            .unwrap_or_else(|_| "unknown".to_string());
+                        if process_service::is_orphaned(pid) {
+                            format!("{} [orphaned]", name)
+                        } else {
+                            name
+                        }
+                    });
+                    process::exit(0);
+                }
+                Err(err) => {
+                    cli::display_error(&err.into());
+                    process::exit(1);
+                }
+            }
+        }
+        Ok(cli::Command::ByName {
+            name,
+            yes,
+            force,
+            privileged_ok,
+            json,
+            policy,
+            backend,
+        }) => {
+            if json {
+                cli::enable_json_output();
+            }
+            let config = load_config();
+            process::exit(run_by_name(
+                &name,
+                yes,
+                force,
+                privileged_ok,
+                policy,
+                &config,
+                backend,
+            ))
+        }
+        Ok(cli::Command::Watch {
+            port,
+            yes,
+            privileged_ok,
+            exit_code,
+            backend,
+        }) => {
+            let config = load_config();
+            process::exit(run_watch(
+                port,
+                yes || !config.safety.confirms(),
+                privileged_ok,
+                exit_code,
+                &config,
+                backend,
+            ))
+        }
+        Ok(cli::Command::Workspace {
+            yes,
+            force,
+            privileged_ok,
+            json,
+            orphans_only,
+            policy,
+            backend,
+        }) => {
+            if json {
+                cli::enable_json_output();
+            }
+            let config = load_config();
+            process::exit(run_workspace(
+                yes,
+                force,
+                privileged_ok,
+                orphans_only,
+                policy,
+                &config,
+                backend,
+            ))
+        }
+        Ok(cli::Command::Explain { port, backend }) => process::exit(run_explain(port, backend)),
+        Ok(cli::Command::List { backend }) => process::exit(run_list(backend)),
+        Ok(cli::Command::Find { filter, backend }) => process::exit(run_find(&filter, backend)),
+        Ok(cli::Command::Inventory {
+            json,
+            details,
+            backend,
+        }) => process::exit(run_inventory(json, details, backend)),
+        Ok(cli::Command::Unix {
+            path,
+            yes,
+            force,
+            policy,
+        }) => {
+            let config = load_config();
+            process::exit(run_unix(&path, yes, force, policy, &config))
+        }
+        Ok(cli::Command::File {
+            path,
+            yes,
+            force,
+            restart,
+            policy,
+        }) => {
+            let config = load_config();
+            process::exit(run_file(&path, yes, force, restart, policy, &config))
+        }
+        Ok(cli::Command::Com {
+            port,
+            yes,
+            force,
+            policy,
+        }) => {
+            let config = load_config();
+            process::exit(run_com(&port, yes, force, policy, &config))
+        }
+        Ok(cli::Command::ConfigValidate) => process::exit(run_config_validate()),
+        Ok(cli::Command::ConfigInit { force, interactive }) => {
+            process::exit(run_config_init(force, interactive))
+        }
+        Err(err) => {
+            cli::display_error(&Error::new(ErrorKind::InvalidArgument, err));
+            process::exit(1);
+        }
+    };
+
+    if args.json {
+        cli::enable_json_output();
+    }
+
+    // Validate the port
+    let port = match validation::validate_port(&args.port.to_string())
+        .map_err(|err| Error::new(ErrorKind::InvalidArgument, err))
+    {
+        Ok(port) => port,
+        Err(err) => {
+            cli::display_error(&err);
+            print_error_json(args.port, args.protocol, None, &err);
+            process::exit(1);
+        }
+    };
+
+    // Load the configuration file
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            cli::display_error(&err);
+            print_error_json(port, args.protocol, None, &err);
+            process::exit(1);
+        }
+    };
+
+    // Read the connection table once, only its listeners unless --any-state; the
+    // port's rows give the owner and every other process sharing the port
+    let started = unix_now();
+    let looked_up = std::time::Instant::now();
+    let (first, owners) = match read_owners(port, args.backend, args.protocol, args.listen_only)
+        .map_err(Error::from)
+    {
+        Ok((owners, stale)) => match (owners.first().cloned(), stale) {
+            (Some(first), _) => (first, owners),
+            (None, Some(stale)) => {
+                cli::display_line(&format!(
+                    "Port {} is not in use (PID {} in the connection table has exited)",
+                    port, stale
+                ));
+                print_free_json(port, args.protocol);
+                exit_free(args.fail_if_free);
+            }
+            (None, None) => {
+                let connections = match args.protocol {
+                    Protocol::Tcp if args.listen_only => connection_count(port, args.backend),
+                    _ => 0,
+                };
+                if connections > 0 {
+                    cli::display_line(&format!(
+                        "Port {} has no listener; {} connection(s) use it as their local port (pass --any-state to target their processes)",
+                        port, connections
+                    ));
+                } else {
+                    cli::display_line(&format!("Port {} is not in use", port));
+                }
+                print_free_json(port, args.protocol);
+                exit_free(args.fail_if_free);
+            }
+        },
+        Err(err) => {
+            cli::display_error_with_hint(&err, "Try running as administrator");
+            print_error_json(port, args.protocol, None, &err);
+            process::exit(1);
+        }
+    };
+
+    // Several processes can share a port; prefer the one started from this project
+    let (mut binding, candidates) = choose_candidate(first, &owners);
+    let preferred_pid = binding.pid;
+
+    // Someone at a terminal picks the processes instead; the preferred one is the default
+    let picking = !candidates.is_empty()
+        && !args.all
+        && !args.strict
+        && !args.yes
+        && !args.dry_run
+        && !args.json
+        && config.safety.confirms()
+        && cli::can_prompt();
+    let mut picked_others: Vec<u32> = Vec::new();
+    if picking {
+        let entries: Vec<cli::PickEntry> = candidates
+            .iter()
+            .map(|candidate| cli::PickEntry {
+                description: cli::describe_candidate(candidate),
+                path: process_service::get_process_path(candidate.pid).ok(),
+            })
+            .collect();
+        let default = candidates
+            .iter()
+            .position(|candidate| candidate.pid == binding.pid);
+        // --tree and --kill-job act on the subtree or job of one process
+        let multiple = !args.tree && !args.kill_job;
+        let heading = format!("Port {} is held by {} processes", port, candidates.len());
+        let chosen = match cli::pick_processes(&heading, &entries, default, multiple) {
+            Ok(chosen) => chosen,
+            Err(err) => {
+                cli::display_error(&err);
+                print_error_json(port, args.protocol, None, &err);
+                exit_busy(port, args.suggest);
+            }
+        };
+        let mut chosen_pids = chosen
+            .iter()
+            .filter_map(|&index| candidates.get(index))
+            .map(|candidate| candidate.pid);
+        if let Some(first) = chosen_pids.next()
+            && let Some(owner) = owners.iter().find(|owner| owner.pid == first)
+        {
+            binding = owner.clone();
+        }
+        picked_others = chosen_pids.collect();
+    } else if !candidates.is_empty() {
+        cli::display_candidates(port, &candidates, binding.pid);
+
+        // Automation must not be surprised by which process got picked
+        if args.strict && !args.all {
+            let message = Error::new(
+                ErrorKind::NotConfirmed,
+                format!(
+                    "Port {} is held by {} processes; pass --all to terminate every one of them",
+                    port,
+                    candidates.len()
+                ),
+            );
+            cli::display_error(&message);
+            print_error_json(port, args.protocol, None, &message);
+            exit_busy(port, args.suggest);
+        }
+    }
+
+    // Get the process name
+    let pid = binding.pid;
+    let process_name =
+        match watchdog::run("Process name lookup", watchdog::STEP_TIMEOUT, move || {
+            process_service::lookup_process_name(pid)
+        })
+        .map_err(Error::from)
+        .and_then(|name| name)
+        {
+            Ok(name) => name,
+            Err(err) => {
+                cli::display_error_with_hint(&err, "Try running as administrator");
+                print_error_json(port, args.protocol, None, &err);
+                process::exit(1);
+            }
+        };
+
+    // Display process information
+    let sockets: Vec<port_service::PortBinding> = owners
+        .iter()
+        .filter(|owner| owner.pid == binding.pid)
+        .cloned()
+        .collect();
+    cli::display_process_info(binding.pid, &process_name, &sockets);
+
+    // Packaged (MSIX/UWP) apps are terminated through the app model
+    let package = app_package::get_package_full_name(binding.pid)
+        .ok()
+        .flatten();
+    if let Some(package) = &package {
+        cli::display_line(&format!("  Package: {}", package));
+    }
+
+    // Say in advance which ways of freeing the port can work with this token
+    if args.details {
+        let services = service_control::find_services_by_pid(binding.pid).unwrap_or_default();
+        cli::display_capabilities(&privilege::capabilities(binding.pid, &services));
+    }
+
+    // Show what the listener speaks, since the process name alone can be vague
+    if args.identify {
+        let identification = watchdog::run("Protocol probe", watchdog::STEP_TIMEOUT, move || {
+            probe::identify(port)
+        });
+        match identification {
+            Err(err) => cli::display_warning(&err),
+            Ok(Some(identification)) => {
+                cli::display_line(&format!("  Looks like: {}", identification));
+                if let Some(certificate) = &identification.certificate {
+                    cli::display_certificate(certificate);
+                }
+                if let Some(page) = &identification.page {
+                    cli::display_http_page(page);
+                }
+            }
+            Ok(None) => cli::display_line("  Looks like: unknown (no recognizable response)"),
+        }
+    }
+
+    // Protected processes cannot be terminated, not even as administrator
+    if let Some(level) = process_service::get_protection_level(binding.pid) {
+        let message = Error::new(
+            ErrorKind::ProtectedProcess,
+            format!(
+                "{} (PID {}) is a protected process ({}) and cannot be terminated, even as administrator",
+                process_name, binding.pid, level
+            ),
+        );
+        cli::display_error(&message);
+        cli::display_protected_alternatives(
+            &service_control::find_services_by_pid(binding.pid).unwrap_or_default(),
+        );
+        print_error_json(
+            port,
+            args.protocol,
+            Some((&binding, &process_name)),
+            &message,
+        );
+        exit_busy(port, args.suggest);
+    }
+
+    // Without elevation the checks below still run; the owner is left alone afterwards
+    let needs_elevation = if privilege::is_elevated() {
+        None
+    } else {
+        privilege::elevation_requirement(binding.pid)
+    };
+
+    // The checks behind the warnings below take longer than finding the owner, so
+    // they only run when the warnings are read before anything is terminated:
+    // ahead of a question, in a --dry-run plan or with --details
+    let warn = args.details
+        || args.dry_run
+        || config.safety.confirms_every_termination()
+        || (config.safety.confirms() && !args.yes);
+
+    // Report the job object, which --kill-job terminates as a whole
+    let job = match (args.kill_job || warn)
+        .then(|| job_object::find_job(binding.pid))
+        .transpose()
+        .map(Option::flatten)
+        .map_err(Error::from)
+    {
+        Ok(job) => job,
+        Err(err) if args.kill_job => {
+            cli::display_error(&err);
+            print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+            process::exit(1);
+        }
+        Err(err) => {
+            cli::display_warning(&err.message);
+            None
+        }
+    };
+    if let Some(job) = &job {
+        cli::display_job_info(binding.pid, &job.member_pids, job.kill_on_close);
+    }
+    if args.kill_job && job.is_none() {
+        let message = Error::from(format!("PID {} is not in a job object", binding.pid));
+        cli::display_error(&message);
+        print_error_json(
+            port,
+            args.protocol,
+            Some((&binding, &process_name)),
+            &message,
+        );
+        process::exit(1);
+    }
+
+    // Processes terminated along with the owner: its subtree with --tree, the port's
+    // other holders with --all
+    let extra_targets: Vec<(u32, String)> = if args.tree {
+        let pids = match process_service::descendant_pids(binding.pid).map_err(Error::from) {
+            Ok(pids) => pids,
+            Err(err) => {
+                cli::display_error(&err);
+                print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+                process::exit(1);
+            }
+        };
+        let children: Vec<(u32, String)> = pids
+            .into_iter()
+            .map(|pid| {
+                let name = process_service::get_process_name(pid)
+                    .unwrap_or_else(|_| "unknown".to_string());
+                (pid, name)
+            })
+            .collect();
+        cli::display_tree_info(binding.pid, &children);
+        children
+    } else if args.all || !picked_others.is_empty() {
+        candidates
+            .iter()
+            .filter(|candidate| candidate.pid != binding.pid)
+            .filter(|candidate| args.all || picked_others.contains(&candidate.pid))
+            .map(|candidate| (candidate.pid, candidate.name.clone()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // The services the owner hosts, for the warnings and for what depends on them
+    let services = if warn
+        || args.method.is_some()
+        || args.transcript.is_some()
+        || needs_elevation.is_some()
+        || config.safety.requires_force_for_services()
+    {
+        service_control::find_services_by_pid(binding.pid).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    // Warn when the owner is likely to come back on its own
+    if warn {
+        for warning in respawn::respawn_warnings(binding.pid, &services) {
+            cli::display_warning(&warning);
+        }
+    }
+
+    // Say when terminating the owner alone may not make the port bindable; counting
+    // the connections reads the whole table again, which --details and --verify-bind ask for
+    let service_names: Vec<String> = services.iter().map(|s| s.name.clone()).collect();
+    if let Some(warning) = port_sharing::shared_service_warning(&service_names) {
+        cli::display_warning(&warning);
+    }
+    if args.protocol == Protocol::Tcp
+        && (args.details || args.verify_bind)
+        && let Some(note) =
+            port_sharing::lingering_warning(port, connection_count(port, args.backend))
+    {
+        cli::display_note(&note);
+    }
+
+    // --method replaces the automatic ladder, so it has to suit this owner up front
+    if let Some(method) = args.method
+        && let Err(err) = method::plan(
+            method,
+            &method::Target {
+                pid: binding.pid,
+                protocol: args.protocol,
+                services: &service_names,
+            },
+        )
+    {
+        let message = Error::from(format!("{}; nothing was terminated", err));
+        cli::display_error(&message);
+        print_error_json(
+            port,
+            args.protocol,
+            Some((&binding, &process_name)),
+            &message,
+        );
+        exit_busy(port, args.suggest);
+    }
+
+    // The safety profile can demand --force for services and cap how much one run terminates
+    if let Err(err) = config
+        .safety
+        .check_service_hosts(&[(binding.pid, service_names.clone())], args.force)
+    {
+        let message = Error::new(ErrorKind::NotConfirmed, err);
+        cli::display_error_with_hint(
+            &message,
+            "Pass --force if terminating the service is intended",
+        );
+        print_error_json(
+            port,
+            args.protocol,
+            Some((&binding, &process_name)),
+            &message,
+        );
+        exit_busy(port, args.suggest);
+    }
+    let affected = match &job {
+        Some(job) if args.kill_job => job.member_pids.len(),
+        _ => 1 + extra_targets.len(),
+    };
+    if let Err(err) = config
+        .safety
+        .check_blast_radius(affected)
+        .map_err(Error::from)
+    {
+        cli::display_error(&err);
+        print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+        exit_busy(port, args.suggest);
+    }
+
+    // --graceful and dev servers are asked to exit first, unless the command line chose a signal
+    let graceful_first = |name: &str| {
+        args.graceful
+            || (!args.signal_chosen
+                && config.termination_for(name) == config::Termination::Graceful)
+    };
+    let target_graceful = package.is_none() && graceful_first(&process_name);
+    let action_for = |graceful: bool| match (args.method, args.signal) {
+        (Some(method), _) => method.as_str(),
+        (None, Signal::Kill) if graceful => "graceful",
+        (None, Signal::Kill) => "kill",
+        (None, Signal::Term) => "sigterm",
+    };
+    let action = match &job {
+        Some(_) if args.kill_job => "kill-job",
+        _ => action_for(target_graceful),
+    };
+
+    // Everything has been looked up and checked; --dry-run shows the plan and stops,
+    // also when terminating would need elevation
+    if args.dry_run {
+        let targets: Vec<(u32, String, &'static str)> = match &job {
+            Some(job) if args.kill_job => job
+                .member_pids
+                .iter()
+                .map(|&pid| {
+                    let name = process_service::get_process_name(pid)
+                        .unwrap_or_else(|_| "unknown".to_string());
+                    (pid, name, action)
+                })
+                .collect(),
+            _ => std::iter::once((binding.pid, process_name.clone(), action))
+                .chain(
+                    extra_targets
+                        .iter()
+                        .map(|(pid, name)| (*pid, name.clone(), action_for(graceful_first(name)))),
+                )
+                .collect(),
+        };
+        let targets: Vec<_> = targets
+            .into_iter()
+            .map(|(pid, name, action)| {
+                let path = process_service::get_process_path(pid).ok();
+                (pid, name, action, path)
+            })
+            .collect();
+        cli::display_dry_run(&targets);
+        if let Some(reason) = &needs_elevation {
+            cli::display_line(&format!(
+                "Terminating PID {} would require administrator privileges: {}",
+                binding.pid, reason
+            ));
+        }
+
+        let steps: Vec<execution::StepResult> = targets
+            .iter()
+            .map(|(pid, name, action, _)| execution::StepResult {
+                target: format!("PID {}", pid),
+                owner: name.clone(),
+                action,
+                outcome: execution::Outcome::Skipped,
+                duration: std::time::Duration::ZERO,
+            })
+            .collect();
+        print_json(&cli::JsonResult {
+            port,
+            protocol: args.protocol,
+            found: Some((&binding, &process_name)),
+            action: Some(action),
+            steps: &steps,
+            outcome: &Ok(()),
+            checks: &[],
+            dry_run: true,
+        });
+        process::exit(0);
+    }
+
+    // Everything that works without elevation is done; report what was skipped and stop
+    if let Some(reason) = &needs_elevation {
+        if let Ok(path) = process_service::get_process_path(binding.pid) {
+            cli::display_line(&format!("  Path: {}", path));
+        }
+        if let Ok(command_line) = process_info::command_line(binding.pid) {
+            cli::display_line(&format!("  Command line: {}", command_line));
+        }
+        cli::display_skipped_actions(&privilege::capabilities(binding.pid, &services));
+
+        // An elevated copy cannot ask questions or be told which processes were picked,
+        // so it takes over only when it would terminate the same ones without asking
+        let picked_all = picking && picked_others.len() + 1 == candidates.len();
+        let picked_default = binding.pid == preferred_pid && picked_others.is_empty();
+        let relaunchable = !config.safety.confirms_every_termination()
+            && (!picking || picked_default || picked_all);
+        if args.elevate && !relaunchable {
+            cli::display_warning(
+                "--elevate cannot pass on the processes picked here or the questions of the paranoid safety profile",
+            );
+        }
+        let offer = relaunchable
+            && !args.elevate
+            && !args.yes
+            && !args.json
+            && config.safety.confirms()
+            && cli::can_prompt();
+        let elevate = (args.elevate && relaunchable)
+            || (offer && cli::confirm_elevation(&process_name, binding.pid).is_ok());
+        if elevate {
+            // The questions are asked here, so the elevated copy gets --yes and --privileged-ok
+            let confirm_port =
+                is_sensitive_port(&config, port) && !args.privileged_ok && config.safety.confirms();
+            if confirm_port && let Err(err) = cli::confirm_sensitive_port(port) {
+                cli::display_error(&err);
+                print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+                exit_busy(port, args.suggest);
+            }
+            let confirm_kill =
+                args.elevate && !args.yes && !picking && !confirm_port && config.safety.confirms();
+            if confirm_kill
+                && let Err(err) = cli::confirm_kill(&process_name, binding.pid, affected - 1)
+            {
+                cli::display_error(&err);
+                print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+                exit_busy(port, args.suggest);
+            }
+            run_elevated(picked_all);
+        }
+
+        let message = Error::new(
+            ErrorKind::AccessDenied,
+            format!(
+                "Terminating PID {} requires administrator privileges: {}",
+                binding.pid, reason
+            ),
+        );
+        cli::display_error_with_hint(
+            &message,
+            "Pass --elevate or run evict from an elevated terminal; exit code 4 means only elevation is missing",
+        );
+        let skipped = [execution::StepResult {
+            target: format!("PID {}", binding.pid),
+            owner: process_name.clone(),
+            action,
+            outcome: execution::Outcome::Skipped,
+            duration: std::time::Duration::ZERO,
+        }];
+        print_json(&cli::JsonResult {
+            port,
+            protocol: args.protocol,
+            found: Some((&binding, &process_name)),
+            action: Some(action),
+            steps: &skipped,
+            outcome: &Err(message),
+            checks: &[],
+            dry_run: args.dry_run,
+        });
+        if args.suggest {
+            cli::display_suggestion(port, free_port::find_free_port_near(port));
+        }
+        process::exit(4);
+    }
+
+    // Privileged and production ports need the port typed back; others a yes/no answer
+    let confirm_port =
+        is_sensitive_port(&config, port) && !args.privileged_ok && config.safety.confirms();
+    if confirm_port && let Err(err) = cli::confirm_sensitive_port(port) {
+        cli::display_error(&err);
+        print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+        exit_busy(port, args.suggest);
+    }
+    // Picking the processes already answered the question, except for the paranoid profile
+    let confirm_kill = config.safety.confirms_every_termination()
+        || (config.safety.confirms() && !args.yes && !picking);
+    if confirm_kill
+        && !confirm_port
+        && let Err(err) = cli::confirm_kill(&process_name, binding.pid, affected - 1)
+    {
+        cli::display_error(&err);
+        print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+        exit_busy(port, args.suggest);
+    }
+
+    // Keep a minidump for post-mortem analysis before the process is gone
+    if args.dump {
+        let path = std::path::PathBuf::from(format!(
+            "{}-{}.dmp",
+            process_name.trim_end_matches(".exe"),
+            binding.pid
+        ));
+        if let Err(err) = process_service::write_minidump(binding.pid, &path).map_err(Error::from) {
+            cli::display_error_with_hint(
+                &err,
+                "Run without --dump to terminate the process anyway",
+            );
+            print_error_json(port, args.protocol, Some((&binding, &process_name)), &err);
+            process::exit(1);
+        }
+        cli::display_line(&format!("Wrote minidump to {}", path.display()));
+    }
+
+    // Ask the application to shut itself down before using the OS-level ladder
+    if args.app_graceful {
+        match config.app_shutdown_for(&process_name) {
+            Some(action) => {
+                let outcome =
+                    app_shutdown::request_shutdown(binding.pid, port, &action).and_then(|()| {
+                        process_service::wait_for_exit(binding.pid, args.graceful_timeout)
+                    });
+                match outcome {
+                    Ok(true) => {
+                        cli::display_success(port);
+                        print_json(&cli::JsonResult {
+                            port,
+                            protocol: args.protocol,
+                            found: Some((&binding, &process_name)),
+                            action: Some("app-graceful"),
+                            steps: &[],
+                            outcome: &Ok(()),
+                            checks: &[],
+                            dry_run: false,
+                        });
+                        process::exit(0);
+                    }
+                    Ok(false) => cli::display_warning(&format!(
+                        "{} did not exit after the shutdown request; falling back to normal termination",
+                        process_name
+                    )),
+                    Err(err) => cli::display_warning(&format!(
+                        "{}; falling back to normal termination",
+                        err
+                    )),
+                }
+            }
+            None => cli::display_note(&format!(
+                "No application shutdown action is known for {}; using normal termination",
+                process_name
+            )),
+        }
+    }
+
+    // Confirmations and dumps take time; make sure the owner hasn't changed meanwhile
+    let recheck = if looked_up.elapsed() >= RECHECK_AFTER {
+        live_owners(port, args.backend, args.protocol, args.listen_only)
+    } else {
+        Ok(vec![binding.clone()])
+    };
+    match recheck.map(|owners| {
+        let still_owner = owners.iter().any(|owner| owner.pid == binding.pid);
+        (still_owner, owners.into_iter().next())
+    }) {
+        Ok((true, _)) => {}
+        Ok((false, Some(owner))) => {
+            let message = Error::from(format!(
+                "Port {} is now held by PID {} instead of PID {}; nothing was terminated",
+                port, owner.pid, binding.pid
+            ));
+            cli::display_error_with_hint(&message, "Run evict again to see the new owner");
+            print_error_json(
+                port,
+                args.protocol,
+                Some((&binding, &process_name)),
+                &message,
+            );
+            exit_busy(port, args.suggest);
+        }
+        Ok((false, None)) => {
+            cli::display_line(&format!(
+                "PID {} released port {} before it was terminated",
+                binding.pid, port
+            ));
+            cli::display_success(port);
+            print_json(&cli::JsonResult {
+                port,
+                protocol: args.protocol,
+                found: Some((&binding, &process_name)),
+                action: None,
+                steps: &[],
+                outcome: &Ok(()),
+                checks: &[],
+                dry_run: false,
+            });
+            process::exit(0);
+        }
+        Err(err) => cli::display_warning(&format!("Could not re-check the owner: {}", err)),
+    }
+
+    // Read the details for --copy, --verify-cmd and --transcript while the owner is still running
+    let details =
+        (args.copy || args.verify_cmd.is_some() || args.transcript.is_some()).then(|| {
+            (
+                process_service::get_process_path(binding.pid).ok(),
+                process_info::command_line(binding.pid).ok(),
+            )
+        });
+
+    // Terminate the process
+    let terminate_target = || match (args.method, &job, args.signal) {
+        (Some(method), _, _) => apply_method(
+            method,
+            binding.pid,
+            port,
+            args.protocol,
+            args.exit_code,
+            args.group,
+            args.graceful_timeout,
+        ),
+        (None, Some(job), _) if args.kill_job => job.terminate(args.exit_code),
+        (None, _, Signal::Kill) if target_graceful => {
+            terminate_graceful_first(binding.pid, args.exit_code, args.graceful_timeout)
+        }
+        (None, _, Signal::Kill) => match &package {
+            Some(package) => app_package::terminate_package(package).or_else(|err| {
+                cli::display_warning(&format!("{}; terminating the process directly", err));
+                process_service::terminate_process(binding.pid, args.exit_code)
+            }),
+            None => process_service::terminate_process(binding.pid, args.exit_code),
+        },
+        (None, _, Signal::Term) => {
+            process_service::terminate_gracefully(binding.pid, args.graceful_timeout, args.group)
+                .map_err(Error::from)
+        }
+    };
+
+    // With --all or --tree, the other targets go as well, after the owner
+    let (result, steps) = if !extra_targets.is_empty() {
+        let mut steps = vec![execution::Step {
+            target: format!("PID {}", binding.pid),
+            owner: process_name.clone(),
+            action,
+            run: Box::new(terminate_target),
+        }];
+        for (pid, name) in &extra_targets {
+            let pid = *pid;
+            let graceful = graceful_first(name);
+            steps.push(execution::Step {
+                target: format!("PID {}", pid),
+                owner: name.clone(),
+                action: action_for(graceful),
+                run: Box::new(move || match (args.method, args.signal) {
+                    (Some(method), _) => apply_method(
+                        method,
+                        pid,
+                        port,
+                        args.protocol,
+                        args.exit_code,
+                        args.group,
+                        args.graceful_timeout,
+                    ),
+                    (None, Signal::Kill) if graceful => {
+                        terminate_graceful_first(pid, args.exit_code, args.graceful_timeout)
+                    }
+                    (None, Signal::Kill) => process_service::terminate_process(pid, args.exit_code),
+                    (None, Signal::Term) => process_service::terminate_gracefully(
+                        pid,
+                        args.graceful_timeout,
+                        args.group,
+                    )
+                    .map_err(Error::from),
+                }),
+            });
+        }
+
+        let results = execution::execute(steps, args.policy);
+        cli::display_summary(&results);
+        (
+            execution::first_failure(&results).map_or(Ok(()), Err),
+            results,
+        )
+    } else {
+        let step_started = std::time::Instant::now();
+        let result = terminate_target();
+        let step = execution::StepResult {
+            target: format!("PID {}", binding.pid),
+            owner: process_name.clone(),
+            action,
+            outcome: match &result {
+                Ok(()) => execution::Outcome::Done,
+                Err(err) => execution::Outcome::Failed(err.clone()),
+            },
+            duration: step_started.elapsed(),
+        };
+        (result, vec![step])
+    };
+
+    // Other processes sharing the port keep it after the target is gone,
+    // which must not pass for having freed it
+    let left_holding = match &result {
+        Ok(()) if !args.all && !candidates.is_empty() => {
+            remaining_holders(port, &args, binding.pid)
+        }
+        _ => Vec::new(),
+    };
+    let result = if left_holding.is_empty() {
+        result
+    } else {
+        let holders: Vec<String> = left_holding
+            .iter()
+            .map(|(pid, name)| format!("PID {} {}", pid, name))
+            .collect();
+        Err(Error::from(format!(
+            "Port {} is still held by {} after PID {} was terminated",
+            port,
+            holders.join(", "),
+            binding.pid
+        )))
+    };
+    let (path, command_line) = details.unwrap_or_default();
+    let report = cli::ResultReport {
+        port,
+        pid: binding.pid,
+        name: process_name.clone(),
+        path,
+        command_line,
+        action,
+        outcome: result.clone().map_err(|err| err.message),
+    };
+    if args.copy {
+        copy_report(&report);
+    }
+
+    let mut checks = Vec::new();
+    let code = match &result {
+        // The listener keeps the port, so there is nothing to announce or verify
+        Ok(()) if args.method == Some(method::Method::ConnectionReset) => 0,
+        Ok(()) => {
+            cli::display_success(port);
+            let mut code = 0;
+            if args.verify_bind {
+                let addresses = if args.all {
+                    let mut addresses: Vec<_> = owners.iter().map(|owner| owner.address).collect();
+                    addresses.sort();
+                    addresses.dedup();
+                    addresses
+                } else {
+                    project::addresses_of(&owners, binding.pid)
+                };
+                let deadline = std::time::Instant::now() + verify::BIND_TIMEOUT;
+                match verify::bind_port(port, &addresses, args.protocol, deadline) {
+                    Ok(()) => {
+                        cli::display_line(&format!("Verified: port {} can be bound again", port));
+                        checks.push(format!("--verify-bind: port {} could be bound again", port));
+                    }
+                    Err(err) => {
+                        cli::display_error_with_hint(
+                            &Error::from(format!("Port {} is still not bindable: {}", port, err)),
+                            &format!(
+                                "Another socket may hold the port exclusively; run 'evict explain {}'",
+                                port
+                            ),
+                        );
+                        checks.push(format!("--verify-bind failed: {}", err));
+                        code = 3;
+                    }
+                }
+            }
+            if code == 0
+                && let Some(command) = &args.verify_cmd
+            {
+                let rollback = cli::rollback_advice(
+                    &process_name,
+                    binding.pid,
+                    report.command_line.as_deref(),
+                );
+                code = run_verification(port, command, args.verify_timeout, &rollback);
+                checks.push(match code {
+                    0 => format!("--verify-cmd \"{}\": passed", command),
+                    _ => format!("--verify-cmd \"{}\": failed", command),
+                });
+            }
+            code
+        }
+        Err(err) if !left_holding.is_empty() => {
+            cli::display_error_with_hint(
+                err,
+                "Pass --all to terminate every process holding the port",
+            );
+            1
+        }
+        Err(err) => {
+            cli::display_error_with_hint(err, "Try running as administrator");
+            1
+        }
+    };
+
+    if let Some(path) = &args.transcript {
+        write_transcript(
+            path,
+            &transcript::Transcript {
+                command: invocation(),
+                operator: privilege::process_user(process::id())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                host: std::env::var("COMPUTERNAME").unwrap_or_default(),
+                started,
+                finished: unix_now(),
+                protocol: args.protocol.as_str(),
+                report: &report,
+                services: &service_names,
+                steps: &steps,
+                checks: &checks,
+            },
+        );
+    }
+    print_json(&cli::JsonResult {
+        port,
+        protocol: args.protocol,
+        found: Some((&binding, &process_name)),
+        action: Some(action),
+        steps: &steps,
+        outcome: &result,
+        checks: &checks,
+        dry_run: false,
+    });
+    if result.is_err() {
+        exit_busy(port, args.suggest);
+    }
+    process::exit(code);
+}
+
+/// List the processes other than the terminated one that still hold the port
+/// A table that cannot be read counts as none, since the termination itself succeeded
+fn remaining_holders(port: u16, args: &cli::CliArgs, terminated: u32) -> Vec<(u32, String)> {
+    if args.method == Some(method::Method::ConnectionReset) {
+        return Vec::new();
+    }
+    let owners =
+        live_owners(port, args.backend, args.protocol, args.listen_only).unwrap_or_default();
+    let mut pids: Vec<u32> = Vec::new();
+    for owner in owners {
+        if owner.pid != terminated && owner.pid != 0 && !pids.contains(&owner.pid) {
+            pids.push(owner.pid);
+        }
+    }
+    named_holders(pids)
+}
+
+/// Free the port from one process with the single mechanism chosen by --method
+/// The method is checked against the process first, since --all applies it to
+/// processes that were not checked up front
+fn apply_method(
+    method: method::Method,
+    pid: u32,
+    port: u16,
+    protocol: Protocol,
+    exit_code: u32,
+    console_group: bool,
+    timeout: std::time::Duration,
+) -> Result<(), Error> {
+    let services: Vec<String> = service_control::find_services_by_pid(pid)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|service| service.name)
+        .collect();
+    let target = method::Target {
+        pid,
+        protocol,
+        services: &services,
+    };
+
+    match method::plan(method, &target)? {
+        method::Plan::Terminate => process_service::terminate_process(pid, exit_code),
+        method::Plan::CtrlBreak => Ok(process_service::ctrl_break_and_wait(
+            pid,
+            timeout,
+            console_group,
+        )?),
+        method::Plan::WmClose => Ok(process_service::close_windows_and_wait(pid, timeout)?),
+        method::Plan::StopService(name) => Ok(service_control::stop_service(
+            name,
+            service_control::STOP_TIMEOUT,
+        )?),
+        method::Plan::ResetConnections => {
+            let count = port_service::reset_connections(port, pid)?;
+            cli::display_line(&format!(
+                "Reset {} connection(s) of PID {} on port {}; the listener keeps the port",
+                count, pid, port
+            ));
+            Ok(())
+        }
+    }
+}
+
+/// Print the --json result; without --json the text output already said it all
+fn print_json(result: &cli::JsonResult) {
+    if cli::json_output() {
+        println!("{}", cli::result_json(result));
+    }
+}
+
+/// Print the --json result for a port that nothing was using
+fn print_free_json(port: u16, protocol: Protocol) {
+    print_json(&cli::JsonResult {
+        port,
+        protocol,
+        found: None,
+        action: None,
+        steps: &[],
+        outcome: &Ok(()),
+        checks: &[],
+        dry_run: false,
+    });
+}
+
+/// Print the --json result for a run that stopped with an error before terminating anything
+/// `found` is the owner and its name once they are known
+fn print_error_json(
+    port: u16,
+    protocol: Protocol,
+    found: Option<(&port_service::PortBinding, &str)>,
+    error: &Error,
+) {
+    print_json(&cli::JsonResult {
+        port,
+        protocol,
+        found,
+        action: None,
+        steps: &[],
+        outcome: &Err(error.clone()),
+        checks: &[],
+        dry_run: false,
+    });
+}
+
+/// Write the --transcript change record
+/// Failing to do so only warns, since the change has already been made
+fn write_transcript(path: &std::path::Path, record: &transcript::Transcript) {
+    match std::fs::write(path, transcript::render(record)) {
+        Ok(()) => cli::display_line(&format!("Wrote the change record to {}", path.display())),
+        Err(e) => cli::display_warning(&format!("Failed to write '{}': {}", path.display(), e)),
+    }
+}
+
+/// Run evict again as administrator through the UAC prompt, print what the elevated
+/// copy printed and exit with its exit code
+/// `all` passes on that every process holding the port was picked; returns only
+/// when the elevated copy could not be started, e.g. because the prompt was declined
+fn run_elevated(all: bool) {
+    let extra = ["--yes", "--privileged-ok"]
+        .into_iter()
+        .chain(all.then_some("--all"))
+        .map(str::to_string);
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != "--elevate")
+        .chain(extra)
+        .collect();
+
+    cli::display_line("Relaunching evict as administrator...");
+    match privilege::relaunch_elevated(&args) {
+        Ok((exit_code, output)) => {
+            print!("{}", output);
+            cli::display_line(&format!(
+                "The elevated evict exited with code {}",
+                exit_code
+            ));
+            process::exit(exit_code as i32);
+        }
+        Err(err) => cli::display_warning(&err),
+    }
+}
+
+/// The command line evict was started with, quoted so it can be run again
+fn invocation() -> String {
+    std::iter::once("evict".to_string())
+        .chain(std::env::args().skip(1).map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("\"{}\"", arg)
+            } else {
+                arg
+            }
+        }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Current time in seconds since 1970-01-01 UTC
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Wait for the replacement of the terminated owner, then run the --verify-cmd check
+/// Returns the process exit code: 0 when the check passed, 3 otherwise
+fn run_verification(port: u16, command: &str, timeout: std::time::Duration, rollback: &str) -> i32 {
+    let deadline = std::time::Instant::now() + timeout;
+    cli::display_line(&format!(
+        "Waiting up to {} seconds for a new listener on port {}; restart the service now",
+        timeout.as_secs(),
+        port
+    ));
+
+    let owner = match verify::wait_for_listener(port, deadline) {
+        Ok(Some(owner)) => owner,
+        Ok(None) => {
+            cli::display_error_with_hint(
+                &Error::from(format!(
+                    "Nothing listens on port {} after {} seconds",
+                    port,
+                    timeout.as_secs()
+                )),
+                rollback,
+            );
+            return 3;
+        }
+        Err(err) => {
+            cli::display_error_with_hint(&err.into(), rollback);
+            return 3;
+        }
+    };
+
+    let name = process_service::get_process_name(owner).unwrap_or_else(|_| "unknown".to_string());
+    cli::display_line(&format!(
+        "{} (PID {}) now listens on port {}",
+        name, owner, port
+    ));
+    match verify::run_command(command, deadline) {
+        Ok(0) => {
+            cli::display_line(&format!("Verification passed: '{}' succeeded", command));
+            0
+        }
+        Ok(code) => {
+            cli::display_error_with_hint(
+                &Error::from(format!(
+                    "Verification failed: '{}' exited with {}",
+                    command, code
+                )),
+                rollback,
+            );
+            3
+        }
+        Err(err) => {
+            cli::display_error_with_hint(
+                &Error::from(format!("Verification failed: {}", err)),
+                rollback,
+            );
+            3
+        }
+    }
+}
+
+/// Ask a process to exit, and terminate it forcibly when it does not within the timeout
+/// Used with --graceful and for owners whose termination rule is graceful
+fn terminate_graceful_first(
+    pid: u32,
+    exit_code: u32,
+    timeout: std::time::Duration,
+) -> Result<(), Error> {
+    process_service::terminate_gracefully(pid, timeout, false).or_else(|_| {
+        cli::display_note(&format!(
+            "PID {} did not exit when asked; terminating it forcibly",
+            pid
+        ));
+        process_service::terminate_process(pid, exit_code)
+    })
+}
+
+/// Build the step that terminates a process the way its termination rule says
+/// For the commands without signal options: --name, workspace, unix, com and file
+fn rule_step<'a>(config: &config::Config, pid: u32, name: &str) -> execution::Step<'a> {
+    let graceful = config.termination_for(name) == config::Termination::Graceful;
+    execution::Step {
+        target: format!("PID {}", pid),
+        owner: name.to_string(),
+        action: if graceful { "graceful" } else { "kill" },
+        run: Box::new(move || {
+            if graceful {
+                terminate_graceful_first(
+                    pid,
+                    process_service::DEFAULT_EXIT_CODE,
+                    process_service::GRACEFUL_TIMEOUT,
+                )
+            } else {
+                process_service::terminate_process(pid, process_service::DEFAULT_EXIT_CODE)
+            }
+        }),
+    }
+}
+
+/// Check whether a port is privileged or listed in production_ports
+fn is_sensitive_port(config: &config::Config, port: u16) -> bool {
+    validation::is_well_known_port(port) || config.production_ports.contains(&port)
+}
+
+/// Have every privileged or production port the listeners hold typed back
+/// Returns whether any was, which also answers the yes/no question
+fn confirm_sensitive_ports(
+    listeners: &[project::ProjectListener],
+    config: &config::Config,
+    privileged_ok: bool,
+) -> Result<bool, Error> {
+    if privileged_ok || !config.safety.confirms() {
+        return Ok(false);
+    }
+    let mut ports: Vec<u16> = listeners
+        .iter()
+        .flat_map(|listener| listener.ports.iter().copied())
+        .filter(|&port| is_sensitive_port(config, port))
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    for &port in &ports {
+        cli::confirm_sensitive_port(port)?;
+    }
+    Ok(!ports.is_empty())
+}
+
+/// Load the configuration, exiting with an error when it is invalid
+fn load_config() -> config::Config {
+    match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            cli::display_error(&err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Put the result on the clipboard for --copy; failing to do so only warns
+fn copy_report(report: &cli::ResultReport) {
+    match clipboard::copy_text(&cli::format_report(report)) {
+        Ok(()) => cli::display_line("Copied the result to the clipboard"),
+        Err(err) => cli::display_warning(&err),
+    }
+}
+
+/// Connection table used without --backend: iphlpapi, falling back to WMI when it
+/// cannot be read
+struct FallbackScanner;
+
+/// Whether the fallback to WMI was reported; --watch reads the table every poll
+static FALLBACK_REPORTED: AtomicBool = AtomicBool::new(false);
+
+impl FallbackScanner {
+    /// Read from iphlpapi, or from WMI when that fails, warning the first time
+    fn read<T>(read: impl Fn(&dyn PortScanner) -> Result<T, String>) -> Result<T, String> {
+        read(&port_service::IpHelperScanner).or_else(|err| {
+            if !FALLBACK_REPORTED.swap(true, Ordering::Relaxed) {
+                cli::display_warning(&format!("{}; falling back to WMI", err));
+            }
+            read(&port_service::WmiScanner)
+        })
+    }
+}
+
+impl PortScanner for FallbackScanner {
+    fn find_process_by_port(&self, port: u16) -> Result<Option<port_service::PortBinding>, String> {
+        Self::read(|scanner| scanner.find_process_by_port(port))
+    }
+
+    fn list_bindings(&self) -> Result<Vec<port_service::PortBinding>, String> {
+        Self::read(|scanner| scanner.list_bindings())
+    }
+
+    fn list_listeners(&self) -> Result<Vec<port_service::PortBinding>, String> {
+        Self::read(|scanner| scanner.list_listeners())
+    }
+
+    fn find_udp_process_by_port(
+        &self,
+        port: u16,
+    ) -> Result<Option<port_service::PortBinding>, String> {
+        Self::read(|scanner| scanner.find_udp_process_by_port(port))
+    }
+
+    fn list_udp_bindings(&self) -> Result<Vec<port_service::PortBinding>, String> {
+        Self::read(|scanner| scanner.list_udp_bindings())
+    }
+}
+
+/// Get the connection table chosen with --backend, or iphlpapi with the WMI fallback
+fn scanner(backend: Option<port_service::Backend>) -> Box<dyn PortScanner> {
+    match backend {
+        Some(backend) => backend.scanner(),
+        None => Box::new(FallbackScanner),
+    }
+}
+
+/// List the live processes holding a port, in connection table order
+fn live_owners(
+    port: u16,
+    backend: Option<port_service::Backend>,
+    protocol: Protocol,
+    listen_only: bool,
+) -> Result<Vec<port_service::PortBinding>, String> {
+    read_owners(port, backend, protocol, listen_only).map(|(owners, _)| owners)
+}
+
+/// List the live processes holding a port, in connection table order
+/// The table is read again only while it lists nothing but processes that have
+/// already exited, since rows can outlive their process for a moment; the PID
+/// of such a row comes back when no live owner turned up
+fn read_owners(
+    port: u16,
+    backend: Option<port_service::Backend>,
+    protocol: Protocol,
+    listen_only: bool,
+) -> Result<(Vec<port_service::PortBinding>, Option<u32>), String> {
+    let mut stale = None;
+    for attempt in 0..STALE_RETRIES {
+        if attempt > 0 {
+            std::thread::sleep(STALE_RETRY_DELAY);
+        }
+
+        let bindings = watchdog::run(
+            "Connection table listing",
+            watchdog::STEP_TIMEOUT,
+            move || scanner(backend).list_sockets(protocol, listen_only),
+        )??;
+        let rows: Vec<port_service::PortBinding> = bindings
+            .into_iter()
+            .filter(|binding| binding.port == port)
+            .collect();
+        if rows.is_empty() {
+            return Ok((rows, stale));
+        }
+
+        stale = rows.first().map(|row| row.pid);
+        let live: Vec<port_service::PortBinding> = rows
+            .into_iter()
+            .filter(|binding| process_service::is_running(binding.pid))
+            .collect();
+        if !live.is_empty() {
+            return Ok((live, None));
+        }
+    }
+
+    Ok((Vec::new(), stale))
+}
+
+/// Count the TCP rows on a port that are not listeners, such as open and TIME_WAIT connections
+/// Only feeds warnings, so a table that cannot be read counts as none
+fn connection_count(port: u16, backend: Option<port_service::Backend>) -> usize {
+    scanner(backend).list_bindings().map_or(0, |rows| {
+        rows.iter()
+            .filter(|row| row.port == port && row.state != Some(port_service::TcpState::Listen))
+            .count()
+    })
+}
+
+/// Check the configuration file and show the settings evict would use
+/// Returns the process exit code
+fn run_config_validate() -> i32 {
+    // The machine-wide file provides the defaults the user's file builds on
+    let mut base = config::Config::default();
+    if let Some(path) = config::machine_config_path()
+        && let Ok(Some(contents)) = config::read(&path)
+    {
+        println!("Machine config file: {}", path.display());
+        let (config, errors) = config::validate(&contents);
+        if !errors.is_empty() {
+            for error in &errors {
+                cli::display_error(&Error::new(
+                    ErrorKind::InvalidConfig,
+                    format!("{}: {}", path.display(), error),
+                ));
+            }
+            cli::display_note("evict refuses to run until the configuration is fixed");
+            return 1;
+        }
+        base = config;
+    }
+
+    let Some(path) = config::config_path() else {
+        println!("No configuration file location: neither EVICT_CONFIG nor APPDATA is set");
+        cli::display_effective_config(&base);
+        return 0;
+    };
+    let origin = if std::env::var_os("EVICT_CONFIG").is_some() {
+        "from EVICT_CONFIG"
+    } else {
+        "default location"
+    };
+
+    let contents = match config::read(&path) {
+        Ok(Some(contents)) => contents,
+        Ok(None) => {
+            println!("Config file: {} ({}, not found)", path.display(), origin);
+            cli::display_effective_config(&base);
+            return 0;
+        }
+        Err(err) => {
+            cli::display_error(&err.into());
+            return 1;
+        }
+    };
+    println!("Config file: {} ({})", path.display(), origin);
+
+    let (config, errors) = config::validate_over(base, &contents);
+    if !errors.is_empty() {
+        for error in &errors {
+            cli::display_error(&Error::new(
+                ErrorKind::InvalidConfig,
+                format!("{}: {}", path.display(), error),
+            ));
+        }
+        cli::display_note("evict refuses to run until the configuration is fixed");
+        return 1;
+    }
+
+    println!("Configuration is valid");
+    println!();
+    cli::display_effective_config(&config);
+    0
+}
+
+/// Write a commented configuration file to the configuration location
+/// Returns the process exit code
+fn run_config_init(force: bool, interactive: bool) -> i32 {
+    let Some(path) = config::config_path() else {
+        cli::display_error(&Error::from(
+            "No configuration file location: neither EVICT_CONFIG nor APPDATA is set",
+        ));
+        return 1;
+    };
+
+    let (production_ports, app_shutdown) = if interactive {
+        match cli::prompt_config_setup() {
+            Ok(settings) => settings,
+            Err(err) => {
+                cli::display_error(&err.into());
+                return 1;
+            }
+        }
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let contents = config::template(&production_ports, &app_shutdown);
+    match config::write_new(&path, &contents, force) {
+        Ok(()) => {
+            println!("Wrote {}", path.display());
+            0
+        }
+        Err(err) => {
+            cli::display_error(&err.into());
+            1
+        }
+    }
+}
+
+/// Terminate every listener started from the current repository
+/// With orphans_only, only listeners whose parent process has exited
+/// Returns the process exit code
+fn run_workspace(
+    yes: bool,
+    force: bool,
+    privileged_ok: bool,
+    orphans_only: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+    backend: Option<port_service::Backend>,
+) -> i32 {
+    let root = match std::env::current_dir() {
+        Ok(directory) => project::project_root(&directory),
+        Err(err) => {
+            let err = format!("Failed to read the current directory: {}", err);
+            return report_batch(0, Err(err.into()));
+        }
+    };
+
+    let listeners = match find_sockets(&listening(), backend, false) {
+        Ok(listeners) => listeners_by_process(&listeners),
+        Err(err) => return report_batch(0, Err(err.into())),
+    };
+    let matches: Vec<project::ProjectListener> = listeners
+        .into_iter()
+        .filter(|listener| listener.orphaned || !orphans_only)
+        .filter(|listener| process_in_project(&root, listener.pid))
+        .collect();
+
+    if matches.is_empty() {
+        let kind = if orphans_only {
+            "orphaned listeners"
+        } else {
+            "listeners"
+        };
+        cli::display_line(&format!("No {} were started from {}", kind, root.display()));
+        return report_batch(0, Ok(Vec::new()));
+    }
+    cli::display_listeners(
+        &format!("Listeners started from {}", root.display()),
+        &matches,
+    );
+    report_batch(
+        matches.len(),
+        terminate_listeners(&matches, yes, force, privileged_ok, policy, config),
+    )
+}
+
+/// Terminate whatever binds the port as soon as it shows up, until evict is interrupted
+/// Returns the process exit code when watching cannot start
+fn run_watch(
+    port: u16,
+    yes: bool,
+    privileged_ok: bool,
+    exit_code: u32,
+    config: &config::Config,
+    backend: Option<port_service::Backend>,
+) -> i32 {
+    if config.safety.confirms_every_termination() {
+        cli::display_error_with_hint(
+            &Error::from(
+                "The paranoid safety profile confirms every termination, which --watch cannot do",
+            ),
+            "Free the port with 'evict <PORT>' instead, or use another safety profile",
+        );
+        return 1;
+    }
+    // Privileged and production ports need the port typed back, once for the whole watch
+    let confirm_port =
+        is_sensitive_port(config, port) && !privileged_ok && config.safety.confirms();
+    if confirm_port && let Err(err) = cli::confirm_sensitive_port(port) {
+        cli::display_error(&err);
+        return 1;
+    }
+    if !yes
+        && !confirm_port
+        && let Err(err) = cli::confirm_watch(port)
+    {
+        cli::display_error(&err);
+        return 1;
+    }
+
+    println!("Watching port {}; press Ctrl+C to stop", port);
+    let own_pid = process::id();
+    let scanner = scanner(backend);
+    // A process that cannot be terminated is reported once, not on every poll
+    let mut reported = std::collections::HashSet::new();
+    let mut last_error = None;
+    loop {
+        let swept = watch::sweep(
+            scanner.as_ref(),
+            &process_service::Win32ProcessManager,
+            &|pid| {
+                service_control::find_services_by_pid(pid)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|service| service.name)
+                    .collect()
+            },
+            port,
+            own_pid,
+            exit_code,
+        );
+        match swept {
+            Ok(swept) => {
+                last_error = None;
+                for sweep in swept {
+                    match sweep.outcome {
+                        Ok(()) => println!(
+                            "Terminated {} (PID {}), which bound port {}",
+                            sweep.name, sweep.pid, port
+                        ),
+                        Err(err) if reported.insert(sweep.pid) => cli::display_error(&err.into()),
+                        Err(_) => {}
+                    }
+                }
+            }
+            Err(err) => {
+                if last_error.as_ref() != Some(&err) {
+                    cli::display_warning(&err);
+                }
+                last_error = Some(err);
+            }
+        }
+        std::thread::sleep(watch::POLL_INTERVAL);
+    }
+}
+
+/// Terminate every process with the given image name that listens on a TCP port
+/// Returns the process exit code
+fn run_by_name(
+    name: &str,
+    yes: bool,
+    force: bool,
+    privileged_ok: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+    backend: Option<port_service::Backend>,
+) -> i32 {
+    let filter = find::Filter {
+        name: Some(name.to_string()),
+        ..listening()
+    };
+    let matches = match find_sockets(&filter, backend, false) {
+        Ok(matches) => listeners_by_process(&matches),
+        Err(err) => return report_batch(0, Err(err.into())),
+    };
+
+    if matches.is_empty() {
+        cli::display_line(&format!(
+            "No process named {} is listening on a TCP port",
+            name
+        ));
+        return report_batch(0, Ok(Vec::new()));
+    }
+
+    // Someone at a terminal picks which of several matches go; otherwise all of them do
+    let heading = format!("Listeners named {}", name);
+    let picking = !yes
+        && config.safety.confirms()
+        && !cli::json_output()
+        && matches.len() > 1
+        && cli::can_prompt();
+    let matches = if picking {
+        let entries: Vec<cli::PickEntry> = matches
+            .iter()
+            .map(|listener| cli::PickEntry {
+                description: cli::describe_listener(listener),
+                path: process_service::get_process_path(listener.pid).ok(),
+            })
+            .collect();
+        match cli::pick_processes(&heading, &entries, None, true) {
+            Ok(chosen) => chosen
+                .iter()
+                .filter_map(|&index| matches.get(index).cloned())
+                .collect(),
+            Err(err) => return report_batch(matches.len(), Err(err)),
+        }
+    } else {
+        cli::display_listeners(&heading, &matches);
+        matches
+    };
+    // Picking the processes already answered the question, except for the paranoid profile
+    report_batch(
+        matches.len(),
+        terminate_listeners(
+            &matches,
+            yes || picking,
+            force,
+            privileged_ok,
+            policy,
+            config,
+        ),
+    )
+}
+
+/// Check, confirm and terminate the listeners --name or workspace selected
+/// Returns what was done to each of them
+fn terminate_listeners(
+    matches: &[project::ProjectListener],
+    yes: bool,
+    force: bool,
+    privileged_ok: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+) -> Result<Vec<execution::StepResult>, Error> {
+    config.safety.check_blast_radius(matches.len())?;
+    check_service_hosts(matches.iter().map(|listener| listener.pid), config, force)?;
+    let confirmed = confirm_sensitive_ports(matches, config, privileged_ok)?;
+    if asks_before_terminating(config, yes) && !confirmed {
+        cli::confirm_termination(matches.len())?;
+    }
+
+    let steps = matches
+        .iter()
+        .map(|listener| rule_step(config, listener.pid, &listener.name))
+        .collect();
+    let results = execution::execute(steps, policy);
+    cli::display_summary(&results);
+    Ok(results)
+}
+
+/// Report how --name or workspace ended and return the exit code
+/// With --json one JSON object says so, whatever the outcome
+fn report_batch(matched: usize, outcome: Result<Vec<execution::StepResult>, Error>) -> i32 {
+    let (steps, result) = match outcome {
+        Ok(steps) => {
+            let result = execution::first_failure(&steps).map_or(Ok(()), Err);
+            (steps, result)
+        }
+        Err(err) => {
+            cli::display_error(&err);
+            (Vec::new(), Err(err))
+        }
+    };
+    if cli::json_output() {
+        println!("{}", cli::batch_json(matched, &steps, &result));
+    }
+    i32::from(result.is_err())
+}
+
+/// List every listening process on the machine, as text or as one JSON document
+/// Returns the process exit code
+fn run_inventory(json: bool, details: bool, backend: Option<port_service::Backend>) -> i32 {
+    let listeners = scanner(backend).list_listeners();
+    let listeners = match listeners {
+        Ok(listeners) => listeners,
+        Err(err) => {
+            cli::display_error(&err.into());
+            return 1;
+        }
+    };
+
+    // Shared executables such as svchost.exe are hashed and checked only once
+    let mut images: std::collections::HashMap<String, (Option<String>, Option<&'static str>)> =
+        std::collections::HashMap::new();
+    let processes: Vec<inventory::InventoryProcess> = project::ports_by_process(&listeners)
+        .into_iter()
+        .map(|(pid, ports)| inventory::InventoryProcess {
+            pid,
+            name: process_service::get_process_name(pid).unwrap_or_else(|_| "unknown".to_string()),
+            ports,
+            details: details.then(|| process_details(pid, &mut images)),
+        })
+        .collect();
+
+    if json {
+        let host = std::env::var("COMPUTERNAME").unwrap_or_default();
+        println!("{}", cli::inventory_json(&host, unix_now(), &processes));
+    } else {
+        cli::display_inventory(&processes);
+    }
+    0
+}
+
+/// Run every detector against a port and explain the result; nothing is terminated
+/// Returns the process exit code
+fn run_explain(port: u16, backend: Option<port_service::Backend>) -> i32 {
+    let config = load_config();
+
+    // Explaining a busy port covers its connections as well as its listeners
+    let owners = match live_owners(port, backend, Protocol::Tcp, false) {
+        Ok(owners) => owners,
+        Err(err) => {
+            cli::display_error_with_hint(&err.into(), "Try running as administrator");
+            return 1;
+        }
+    };
+    let elevated = privilege::is_elevated();
+    let owners: Vec<explain::Owner> = project::ports_by_process(&owners)
+        .into_keys()
+        .map(|pid| {
+            let services = service_control::find_services_by_pid(pid).unwrap_or_default();
+            explain::Owner {
+                pid,
+                name: process_service::get_process_name(pid)
+                    .unwrap_or_else(|_| "unknown".to_string()),
+                package: app_package::get_package_full_name(pid).ok().flatten(),
+                protection: process_service::get_protection_level(pid),
+                elevation: (!elevated)
+                    .then(|| privilege::elevation_requirement(pid))
+                    .flatten(),
+                job_members: job_object::find_job(pid)
+                    .ok()
+                    .flatten()
+                    .map(|job| job.member_pids.len()),
+                respawn: respawn::respawn_warnings(pid, &services),
+                services: services.into_iter().map(|service| service.name).collect(),
+            }
+        })
+        .collect();
+
+    // The other detectors only add context, so their failures are warnings
+    let udp_owners = match live_owners(port, None, Protocol::Udp, false) {
+        Ok(bindings) => named_holders(project::ports_by_process(&bindings).into_keys().collect()),
+        Err(err) => {
+            cli::display_warning(&err);
+            Vec::new()
+        }
+    };
+    let excluded = explain::excluded_range(port).unwrap_or_else(|err| {
+        cli::display_warning(&err);
+        None
+    });
+
+    let findings = explain::Findings {
+        port,
+        owners,
+        udp_owners,
+        excluded,
+        sensitive: validation::is_well_known_port(port) || config.production_ports.contains(&port),
+        connections: connection_count(port, backend),
+    };
+    cli::display_explanation(port, &explain::explain(&findings));
+    0
+}
+
+/// Run a find query against the connection table
+/// find, list, --name and workspace all select their sockets this way. A query
+/// for listeners reads only the listener table, and accounts are looked up only
+/// with `users` or a user filter
+fn find_sockets(
+    filter: &find::Filter,
+    backend: Option<port_service::Backend>,
+    users: bool,
+) -> Result<Vec<find::Match>, String> {
+    let scanner = scanner(backend);
+    let sockets = if filter.state == Some(port_service::TcpState::Listen) {
+        scanner.list_listeners()?
+    } else {
+        scanner.list_bindings()?
+    };
+    let users = users || filter.user.is_some();
+    Ok(find::query(sockets, filter, |pid| {
+        let name = process_service::get_process_name(pid).unwrap_or_else(|_| "unknown".to_string());
+        (name, users.then(|| privilege::process_user(pid)).flatten())
+    }))
+}
+
+/// The filter of every listening TCP socket
+fn listening() -> find::Filter {
+    find::Filter {
+        state: Some(port_service::TcpState::Listen),
+        ..Default::default()
+    }
+}
+
+/// Group the matches of a query by owning process, skipping evict itself
+fn listeners_by_process(matches: &[find::Match]) -> Vec<project::ProjectListener> {
+    let own_pid = process::id();
+    let bindings: Vec<port_service::PortBinding> =
+        matches.iter().map(|found| found.binding.clone()).collect();
+    project::ports_by_process(&bindings)
+        .into_iter()
+        .filter(|&(pid, _)| pid != own_pid)
+        .map(|(pid, ports)| project::ProjectListener {
+            pid,
+            name: matches
+                .iter()
+                .find(|found| found.binding.pid == pid)
+                .map_or_else(|| "unknown".to_string(), |found| found.name.clone()),
+            ports,
+            orphaned: process_service::is_orphaned(pid),
+        })
+        .collect()
+}
+
+/// List every listening TCP socket with the name of its process
+/// Returns the process exit code
+fn run_list(backend: Option<port_service::Backend>) -> i32 {
+    let listeners = match find_sockets(&listening(), backend, false) {
+        Ok(listeners) => listeners,
+        Err(err) => {
+            cli::display_error(&err.into());
+            return 1;
+        }
+    };
+
+    let rows: Vec<(port_service::PortBinding, String)> = listeners
+        .into_iter()
+        .map(|found| (found.binding, found.name))
+        .collect();
+    cli::display_listing(&rows);
+    0
+}
+
+/// List the TCP sockets that match every filter of the find command
+/// Returns the process exit code: 0 with matches, 1 without, like findstr
+fn run_find(filter: &find::Filter, backend: Option<port_service::Backend>) -> i32 {
+    let matches = match find_sockets(filter, backend, true) {
+        Ok(matches) => matches,
+        Err(err) => {
+            cli::display_error(&err.into());
+            return 1;
+        }
+    };
+
+    if matches.is_empty() {
+        println!("No TCP socket matches the filters");
+        return 1;
+    }
+    cli::display_matches(&matches);
+    0
+}
+
+/// Collect the --details fields of one inventory process
+fn process_details(
+    pid: u32,
+    images: &mut std::collections::HashMap<String, (Option<String>, Option<&'static str>)>,
+) -> inventory::ProcessDetails {
+    let path = process_service::get_process_path(pid).ok();
+    let (sha256, signature) = match &path {
+        Some(path) => images
+            .entry(path.clone())
+            .or_insert_with(|| {
+                let image = std::path::Path::new(path);
+                (
+                    image_info::sha256(image).ok(),
+                    image_info::signature(image)
+                        .ok()
+                        .map(image_info::Signature::as_str),
+                )
+            })
+            .clone(),
+        None => (None, None),
+    };
+
+    inventory::ProcessDetails {
+        path,
+        command_line: process_info::command_line(pid).ok(),
+        user: privilege::process_user(pid),
+        services: service_control::find_services_by_pid(pid)
+            .map(|services| services.into_iter().map(|s| s.name).collect())
+            .unwrap_or_default(),
+        sha256,
+        signature,
+    }
+}
+
+/// Terminate the processes bound to an AF_UNIX socket path, then remove the
+/// socket file so the path can be bound again
+/// Returns the process exit code
+fn run_unix(
+    path: &std::path::Path,
+    yes: bool,
+    force: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+) -> i32 {
+    let target = path.to_path_buf();
+    let holders = watchdog::run("Socket handle scan", watchdog::STEP_TIMEOUT, move || {
+        unix_socket::find_holders(&target)
+    })
+    .and_then(|holders| holders);
+    let holders = match holders {
+        Ok(holders) => named_holders(holders),
+        Err(err) => {
+            cli::display_error_with_hint(&err.into(), "Try running as administrator");
+            return 1;
+        }
+    };
+
+    if !holders.is_empty()
+        && !terminate_holders(
+            &path.display().to_string(),
+            &holders,
+            yes,
+            force,
+            policy,
+            config,
+        )
+    {
+        return 1;
+    }
+
+    // The socket file outlives its socket and blocks the next bind
+    if unix_socket::is_socket_file(path) {
+        if let Err(err) = std::fs::remove_file(path) {
+            cli::display_error(&Error::from(format!(
+                "Failed to remove socket file {}: {}",
+                path.display(),
+                err
+            )));
+            return 1;
+        }
+        if holders.is_empty() {
+            println!("Removed stale socket file {}", path.display());
+        }
+    } else if holders.is_empty() {
+        println!("No process holds {}", path.display());
+        return 0;
+    }
+
+    println!("{} is now free", path.display());
+    0
+}
+
+/// Terminate the processes holding a serial port open
+/// Returns the process exit code
+fn run_com(
+    port: &str,
+    yes: bool,
+    force: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+) -> i32 {
+    let name = port.to_string();
+    let holders = watchdog::run(
+        "Serial port handle scan",
+        watchdog::STEP_TIMEOUT,
+        move || serial_port::find_holders(&name),
+    )
+    .and_then(|holders| holders);
+    let holders = match holders {
+        Ok(holders) => named_holders(holders),
+        Err(err) => {
+            cli::display_error_with_hint(&err.into(), "Try running as administrator");
+            return 1;
+        }
+    };
+
+    if holders.is_empty() {
+        println!("{} is not open in any process", port);
+        return 0;
+    }
+    if !terminate_holders(port, &holders, yes, force, policy, config) {
+        return 1;
+    }
+
+    println!("{} is now free", port);
+    0
+}
+
+/// Terminate the processes locking a file or the files of a directory, or
+/// have the Restart Manager restart them
+/// Returns the process exit code
+fn run_file(
+    path: &std::path::Path,
+    yes: bool,
+    force: bool,
+    restart: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+) -> i32 {
+    let lookup = file_lock::files_to_register(path).and_then(|files| {
+        let session = file_lock::Session::start()?;
+        session.register(&files)?;
+        let lockers = session.lockers()?;
+        Ok((session, lockers))
+    });
+    let (session, lockers) = match lookup {
+        Ok(lookup) => lookup,
+        Err(err) => {
+            cli::display_error(&err.into());
+            return 1;
+        }
+    };
+
+    let own_pid = process::id();
+    let lockers: Vec<file_lock::Locker> = lockers
+        .into_iter()
+        .filter(|locker| locker.pid != own_pid)
+        .collect();
+    if lockers.is_empty() {
+        println!("No process is locking {}", path.display());
+        return 0;
+    }
+
+    let holders: Vec<(u32, String)> = lockers
+        .iter()
+        .map(|locker| {
+            let mut name = process_service::get_process_name(locker.pid)
+                .unwrap_or_else(|_| locker.app_name.clone());
+            if let Some(service) = &locker.service {
+                name.push_str(&format!(" (service {})", service));
+            }
+            if restart && locker.restartable {
+                name.push_str(" [restartable]");
+            }
+            (locker.pid, name)
+        })
+        .collect();
+
+    if !restart {
+        if !terminate_holders(
+            &path.display().to_string(),
+            &holders,
+            yes,
+            force,
+            policy,
+            config,
+        ) {
+            return 1;
+        }
+        println!("{} is no longer locked", path.display());
+        return 0;
+    }
+
+    cli::display_holders(&path.display().to_string(), &holders);
+    if let Err(err) = config.safety.check_blast_radius(holders.len()) {
+        cli::display_error(&err.into());
+        return 1;
+    }
+    if !elevation_allows(&holders) {
+        return 1;
+    }
+    if let Err(err) = check_service_hosts(holders.iter().map(|(pid, _)| *pid), config, force) {
+        cli::display_error_with_hint(&err, "Pass --force if restarting the service is intended");
+        return 1;
+    }
+    for locker in lockers.iter().filter(|locker| !locker.restartable) {
+        cli::display_warning(&format!(
+            "PID {} has not registered for restart and will only be shut down",
+            locker.pid
+        ));
+    }
+    if asks_before_terminating(config, yes)
+        && let Err(err) = cli::confirm_restart(holders.len())
+    {
+        cli::display_error(&err);
+        return 1;
+    }
+
+    match session.restart() {
+        Ok(()) => {
+            println!("Restarted the processes locking {}", path.display());
+            0
+        }
+        Err(err) => {
+            cli::display_error_with_hint(&err.into(), "Try running as administrator");
+            1
+        }
+    }
+}
+
+/// Look up the names of the processes holding a resource, leaving out evict itself
+fn named_holders(pids: Vec<u32>) -> Vec<(u32, String)> {
+    let own_pid = process::id();
+    pids.into_iter()
+        .filter(|&pid| pid != own_pid)
+        .map(|pid| {
+            let name =
+                process_service::get_process_name(pid).unwrap_or_else(|_| "unknown".to_string());
+            (pid, name)
+        })
+        .collect()
+}
+
+/// List the holders of a resource, confirm and terminate them all
+/// Returns whether every holder was terminated; failures are already reported
+fn terminate_holders(
+    resource: &str,
+    holders: &[(u32, String)],
+    yes: bool,
+    force: bool,
+    policy: execution::FailurePolicy,
+    config: &config::Config,
+) -> bool {
+    cli::display_holders(resource, holders);
+    if let Err(err) = config.safety.check_blast_radius(holders.len()) {
+        cli::display_error(&err.into());
+        return false;
+    }
+    if !elevation_allows(holders) {
+        return false;
+    }
+    if let Err(err) = check_service_hosts(holders.iter().map(|(pid, _)| *pid), config, force) {
+        cli::display_error_with_hint(&err, "Pass --force if terminating the service is intended");
+        return false;
+    }
+    if asks_before_terminating(config, yes)
+        && let Err(err) = cli::confirm_termination(holders.len())
+    {
+        cli::display_error(&err);
+        return false;
+    }
+
+    let steps = holders
+        .iter()
+        .map(|(pid, name)| rule_step(config, *pid, name))
+        .collect();
+    let results = execution::execute(steps, policy);
+    cli::display_summary(&results);
+    match execution::first_failure(&results) {
+        Some(err) => {
+            cli::display_error_with_hint(&err, "Try running as administrator");
+            false
+        }
+        None => true,
+    }
+}
+
+/// Whether a batch command asks before terminating; the paranoid profile asks
+/// even with --yes, yolo never does
+fn asks_before_terminating(config: &config::Config, yes: bool) -> bool {
+    config.safety.confirms_every_termination() || (!yes && config.safety.confirms())
+}
+
+/// Refuse to terminate a process hosting a Windows service without --force when
+/// the safety profile demands it
+fn check_service_hosts(
+    pids: impl Iterator<Item = u32>,
+    config: &config::Config,
+    force: bool,
+) -> Result<(), Error> {
+    if !config.safety.requires_force_for_services() || force {
+        return Ok(());
+    }
+    let hosts: Vec<(u32, Vec<String>)> = pids
+        .map(|pid| {
+            let services = service_control::find_services_by_pid(pid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|service| service.name)
+                .collect();
+            (pid, services)
+        })
+        .collect();
+    config
+        .safety
+        .check_service_hosts(&hosts, force)
+        .map_err(|err| Error::new(ErrorKind::NotConfirmed, err))
+}
+
+/// Check up front whether acting on every holder is possible without elevation
+/// Reports the first holder that needs administrator privileges
+fn elevation_allows(holders: &[(u32, String)]) -> bool {
+    if privilege::is_elevated() {
+        return true;
+    }
+    let blocked = holders
+        .iter()
+        .find_map(|(pid, _)| privilege::elevation_requirement(*pid).map(|reason| (pid, reason)));
+    match blocked {
+        Some((pid, reason)) => {
+            cli::display_error_with_hint(
+                &Error::new(
+                    ErrorKind::AccessDenied,
+                    format!(
+                        "Terminating PID {} requires administrator privileges: {}",
+                        pid, reason
+                    ),
+                ),
+                "Run evict from an elevated terminal",
+            );
+            false
+        }
+        None => true,
+    }
+}
+
+/// Pick which of the processes holding a port to target
+/// `owners` are the port's rows, the first of them `first`; they tell the
+/// addresses each process holds the port on
+/// Returns the target and the candidates when several processes hold the port
+/// (none when it has a single owner)
+fn choose_candidate(
+    first: port_service::PortBinding,
+    owners: &[port_service::PortBinding],
+) -> (port_service::PortBinding, Vec<project::Candidate>) {
+    // A process on several addresses or on both stacks is one candidate
+    let mut pids = vec![first.pid];
+    for owner in owners {
+        if owner.pid != 0 && !pids.contains(&owner.pid) {
+            pids.push(owner.pid);
+        }
+    }
+    if pids.len() < 2 {
+        return (first, Vec::new());
+    }
+    let Ok(current_dir) = std::env::current_dir() else {
+        return (first, Vec::new());
+    };
+    let root = project::project_root(&current_dir);
+
+    let candidates: Vec<project::Candidate> = pids
+        .into_iter()
+        .map(|pid| project::Candidate {
+            pid,
+            name: process_service::get_process_name(pid).unwrap_or_else(|_| "unknown".to_string()),
+            in_project: process_in_project(&root, pid),
+            addresses: project::addresses_of(owners, pid),
+        })
+        .collect();
+    let Some(binding) = project::preferred(&candidates)
+        .and_then(|chosen| owners.iter().find(|owner| owner.pid == chosen.pid))
+        .cloned()
+    else {
+        return (first, Vec::new());
+    };
+    (binding, candidates)
+}
+
+/// Check whether a process was started from or for the given project
+fn process_in_project(root: &std::path::Path, pid: u32) -> bool {
+    let directory_matches = process_info::current_directory(pid)
+        .is_ok_and(|directory| project::path_in_project(root, &directory));
+
+    directory_matches
+        || process_info::command_line(pid)
+            .is_ok_and(|command_line| project::command_line_in_project(root, &command_line))
+}
+
+/// Exit after finding nothing using the port
+/// Success unless --fail-if-free asked for a free port to count as a failure
+fn exit_free(fail_if_free: bool) -> ! {
+    process::exit(if fail_if_free { FREE_EXIT_CODE } else { 0 })
+}
+
+/// Exit after leaving the owner running, suggesting a free port if asked to
+fn exit_busy(port: u16, suggest: bool) -> ! {
+    if suggest {
+        cli::display_suggestion(port, free_port::find_free_port_near(port));
+    }
+    process::exit(1);
+}
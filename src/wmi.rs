@@ -0,0 +1,157 @@
+// WMI module for Windows Management Instrumentation queries
+
+use crate::error::Win32Error;
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx,
+    CoSetProxyBlanket, CoUninitialize, EOAC_NONE, RPC_C_AUTHN_LEVEL_CALL,
+    RPC_C_IMP_LEVEL_IMPERSONATE,
+};
+use windows::Win32::System::Rpc::{RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE};
+use windows::Win32::System::Variant::{VARIANT, VariantClear, VariantToString};
+use windows::Win32::System::Wmi::{
+    IWbemClassObject, IWbemLocator, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+    WBEM_INFINITE, WbemLocator,
+};
+use windows::core::{BSTR, HSTRING, PCWSTR};
+
+/// Namespace holding the classic Win32_* classes
+pub const CIMV2: &str = "ROOT\\CIMV2";
+
+/// Run a WQL query and read the requested properties of every returned object
+///
+/// Each row holds one value per requested property, in the same order
+/// Values are converted to strings; missing or null properties become None
+pub fn query(
+    namespace: &str,
+    wql: &str,
+    properties: &[&str],
+) -> Result<Vec<Vec<Option<String>>>, String> {
+    unsafe {
+        // COM may already be initialized by the caller in another mode, which is fine
+        let init = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let result = run_query(namespace, wql, properties)
+            .map_err(|e| format!("WMI query failed: {}", Win32Error::from(e)));
+
+        // Interfaces are released inside run_query, before COM is torn down
+        if init.is_ok() {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+/// Helper function to connect to a namespace and execute the query
+unsafe fn run_query(
+    namespace: &str,
+    wql: &str,
+    properties: &[&str],
+) -> windows::core::Result<Vec<Vec<Option<String>>>> {
+    unsafe {
+        let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+        let services = locator.ConnectServer(
+            &BSTR::from(namespace),
+            &BSTR::new(),
+            &BSTR::new(),
+            &BSTR::new(),
+            0,
+            &BSTR::new(),
+            None,
+        )?;
+
+        // Let WMI impersonate us so results reflect our own access rights
+        CoSetProxyBlanket(
+            &services,
+            RPC_C_AUTHN_WINNT,
+            RPC_C_AUTHZ_NONE,
+            PCWSTR::null(),
+            RPC_C_AUTHN_LEVEL_CALL,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            None,
+            EOAC_NONE,
+        )?;
+
+        let enumerator = services.ExecQuery(
+            &BSTR::from("WQL"),
+            &BSTR::from(wql),
+            WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+            None,
+        )?;
+
+        // Pull objects one at a time until the enumerator is exhausted
+        let mut rows = Vec::new();
+        loop {
+            let mut objects = [None];
+            let mut returned: u32 = 0;
+            enumerator
+                .Next(WBEM_INFINITE, &mut objects, &mut returned)
+                .ok()?;
+
+            let [Some(object)] = objects else {
+                break;
+            };
+            rows.push(
+                properties
+                    .iter()
+                    .map(|property| read_property(&object, property))
+                    .collect(),
+            );
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Helper function to read one property of a WMI object as a string
+unsafe fn read_property(object: &IWbemClassObject, name: &str) -> Option<String> {
+    let mut value = VARIANT::default();
+    let mut buffer = [0u16; 1024];
+
+    unsafe {
+        object
+            .Get(&HSTRING::from(name), 0, &mut value, None, None)
+            .ok()?;
+        let converted = VariantToString(&value, &mut buffer);
+        let _ = VariantClear(&mut value);
+        converted.ok()?;
+    }
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    let text = String::from_utf16_lossy(buffer.get(..len)?);
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_current_process() {
+        let wql = format!(
+            "SELECT ProcessId, Name FROM Win32_Process WHERE ProcessId = {}",
+            std::process::id()
+        );
+        let rows = query(CIMV2, &wql, &["ProcessId", "Name"]).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        let row = rows.first().unwrap();
+        assert_eq!(row.first().unwrap(), &Some(std::process::id().to_string()));
+        assert!(row.get(1).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_query_missing_property_is_none() {
+        let wql = format!(
+            "SELECT ProcessId FROM Win32_Process WHERE ProcessId = {}",
+            std::process::id()
+        );
+        let rows = query(CIMV2, &wql, &["NoSuchProperty"]).unwrap();
+        assert_eq!(rows, vec![vec![None]]);
+    }
+
+    #[test]
+    fn test_query_invalid_namespace_fails() {
+        let result = query("ROOT\\NoSuchNamespace", "SELECT * FROM Foo", &["Bar"]);
+        assert!(result.is_err());
+    }
+}
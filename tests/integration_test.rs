@@ -1,5 +1,6 @@
 // Integration tests for the complete flow
 // Requirements: 1.1, 1.2, 1.3, 1.4, 2.1, 2.2
+#![allow(clippy::needless_borrows_for_generic_args)]
 
 use std::net::TcpListener;
 use std::process::Command;
@@ -29,6 +30,18 @@ fn test_complete_flow_with_free_port() {
     );
 }
 
+#[test]
+fn test_fail_if_free_exits_nonzero_for_free_port() {
+    let output = Command::new("cargo")
+        .args(&["run", "--", "54322", "--fail-if-free"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("not in use"), "stdout: {}", stdout);
+    assert_eq!(output.status.code(), Some(2));
+}
+
 #[test]
 fn test_no_arguments_shows_usage() {
     // Run the evict command without arguments
@@ -120,9 +133,9 @@ fn test_complete_flow_with_test_process() {
     // Give it a moment to bind
     thread::sleep(Duration::from_millis(100));
 
-    // Run evict on that port
+    // Run evict on that port; without a terminal it would refuse to ask
     let output = Command::new("cargo")
-        .args(&["run", "--", &port.to_string()])
+        .args(&["run", "--", &port.to_string(), "--yes"])
         .output()
         .expect("Failed to execute command");
 